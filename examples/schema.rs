@@ -3,8 +3,24 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use cw20_bid::msg::{BidResponse, ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg};
-use cw20_bid::state::{BestBid, BidRecord, Config};
+use cw20_bid::batch::BatchClearing;
+#[cfg(feature = "debug")]
+use cw20_bid::debug::InvariantsResponse;
+#[cfg(feature = "history")]
+use cw20_bid::history::Settlement;
+#[cfg(feature = "history")]
+use cw20_bid::msg::SettlementsResponse;
+use cw20_bid::msg::{
+    ApiVersionResponse, BidResponse, BidderPositionResponse, BundleResponse, ExecuteMsg,
+    InstallmentBalanceResponse, InstantiateMsg, MigrateMsg, OffersResponse,
+    PoolContributionResponse, QueryMsg, ReceiveMsg, RefundOwedResponse, SudoMsg,
+};
+use cw20_bid::pool::Pool;
+use cw20_bid::state::{
+    AuctionStatus, BestBid, BidRecord, Config, Counteroffer, Installments, Payout,
+};
+#[cfg(feature = "stats")]
+use cw20_bid::stats::BidderStats;
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -15,9 +31,32 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(ReceiveMsg), &out_dir);
+    export_schema(&schema_for!(SudoMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(ApiVersionResponse), &out_dir);
     export_schema(&schema_for!(BidResponse), &out_dir);
+    export_schema(&schema_for!(BidderPositionResponse), &out_dir);
+    export_schema(&schema_for!(OffersResponse), &out_dir);
+    export_schema(&schema_for!(BundleResponse), &out_dir);
+    export_schema(&schema_for!(InstallmentBalanceResponse), &out_dir);
+    export_schema(&schema_for!(Pool), &out_dir);
+    export_schema(&schema_for!(PoolContributionResponse), &out_dir);
+    export_schema(&schema_for!(RefundOwedResponse), &out_dir);
+    #[cfg(feature = "history")]
+    export_schema(&schema_for!(SettlementsResponse), &out_dir);
     export_schema(&schema_for!(Config), &out_dir);
+    export_schema(&schema_for!(AuctionStatus), &out_dir);
     export_schema(&schema_for!(BidRecord), &out_dir);
     export_schema(&schema_for!(BestBid), &out_dir);
+    export_schema(&schema_for!(Payout), &out_dir);
+    export_schema(&schema_for!(Counteroffer), &out_dir);
+    export_schema(&schema_for!(Installments), &out_dir);
+    export_schema(&schema_for!(BatchClearing), &out_dir);
+    #[cfg(feature = "stats")]
+    export_schema(&schema_for!(BidderStats), &out_dir);
+    #[cfg(feature = "history")]
+    export_schema(&schema_for!(Settlement), &out_dir);
+    #[cfg(feature = "debug")]
+    export_schema(&schema_for!(InvariantsResponse), &out_dir);
 }
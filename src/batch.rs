@@ -0,0 +1,69 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Uint128};
+use cw_storage_plus::{Item, Map};
+
+use crate::state::BidRecord;
+
+// Every filled bid pays the uniform `price`. Bids above it are filled in full, and bids at it
+// share `marginal_supply` pro rata over their `marginal_demand`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BatchClearing {
+    pub price: Uint128,
+    pub marginal_supply: Uint128,
+    pub marginal_demand: Uint128,
+}
+
+// Payment held for each batch bid, keyed by bidder and bid id, until the bidder claims.
+pub const BATCH_ESCROW: Map<(&Addr, u64), Uint128> = Map::new("besc");
+pub const BATCH_CLEARING: Item<BatchClearing> = Item::new("bclr");
+// Clearing ranks every bid at close, so their number is capped to keep `Close` within the block
+// gas limit.
+pub const MAX_BATCH_BIDS: u64 = 500;
+
+// Walks the price levels from the highest down until demand covers `supply`. Without enough
+// demand every bid is filled, at the reserve price.
+pub fn compute_clearing(
+    bids: &[BidRecord],
+    supply: Uint128,
+    reserve_price: Uint128,
+) -> BatchClearing {
+    let mut levels: BTreeMap<Uint128, Uint128> = BTreeMap::new();
+    for bid in bids {
+        let demand = levels.entry(bid.price).or_default();
+        *demand = demand.saturating_add(bid.quantity);
+    }
+    let mut filled = Uint128::zero();
+    for (price, demand) in levels.into_iter().rev() {
+        let remaining = supply - filled;
+        if demand >= remaining {
+            return BatchClearing {
+                price,
+                marginal_supply: remaining,
+                marginal_demand: demand,
+            };
+        }
+        filled += demand;
+    }
+    BatchClearing {
+        price: reserve_price,
+        marginal_supply: Uint128::zero(),
+        marginal_demand: Uint128::zero(),
+    }
+}
+
+// Quantity a bid receives at the clearing price, rounded down.
+pub fn fill_for(clearing: &BatchClearing, bid: &BidRecord) -> Uint128 {
+    match bid.price.cmp(&clearing.price) {
+        Ordering::Greater => bid.quantity,
+        Ordering::Equal if clearing.marginal_demand.is_zero() => bid.quantity,
+        Ordering::Equal => bid
+            .quantity
+            .multiply_ratio(clearing.marginal_supply, clearing.marginal_demand),
+        Ordering::Less => Uint128::zero(),
+    }
+}
@@ -1,18 +1,73 @@
+use std::collections::BTreeMap;
+
 #[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    entry_point, from_binary, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response,
-    StdResult, Uint128, Uint64,
+    from_binary, to_binary, Addr, BankMsg, Binary, BlockInfo, CosmosMsg, Deps, DepsMut, Empty, Env,
+    Event, MessageInfo, Order, Response, StdError, StdResult, Storage, Timestamp, Uint128, Uint64,
+    WasmMsg,
 };
 use cw2::set_contract_version;
-use cw20::{Cw20Contract, Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw20::{
+    AllowanceResponse, BalanceResponse, Cw20Contract, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg,
+    TokenInfoResponse,
+};
+use cw_storage_plus::{Bound, Map};
+use cw_utils::{nonpayable, Duration, Expiration};
 
+use crate::batch::{compute_clearing, fill_for, BATCH_CLEARING, BATCH_ESCROW, MAX_BATCH_BIDS};
+use crate::cw1155::{send_tokens_msg, Cw1155ReceiveMsg};
+use crate::cw3::{query_proposal, Status};
+use crate::cw721::{transfer_nft_msg, Cw721ReceiveMsg};
+#[cfg(feature = "debug")]
+use crate::debug;
+use crate::draw::{drawn_value, mix_entry, seed_commitment, SEED, SEED_REVEAL_PERIOD_IN_BLOCKS};
 use crate::error::ContractError;
-use crate::msg::{BidResponse, ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg};
-use crate::state::{BestBid, BidRecord, Config, BEST_BID, BID_RECORDS, BID_SEQ, CONFIG};
+#[cfg(feature = "history")]
+use crate::history::{self, Settlement};
+use crate::math::{split_bps, BPS_DENOMINATOR};
+use crate::migrations::{ensure_from_older_version, run_migrations};
+use crate::msg::{
+    ApiVersionResponse, BidResponse, BidderPositionResponse, BundleItemMsg, BundleItemResponse,
+    BundleResponse, ExecuteMsg, InstallmentBalanceResponse, InstantiateMsg, LeaseResponse,
+    MigrateMsg, OffersResponse, PoolContributionResponse, PoolRefund, QueryMsg, ReceiveMsg,
+    RefundOwedResponse, RelistConfig, SudoMsg, TokenReceiveMsg, VestingResponse, API_VERSION,
+};
+use crate::pool::{
+    refund_for, share_bps, Pool, CONTRIBUTOR_POOLS, POOLS, POOL_BIDS, POOL_CONTRIBUTIONS, POOL_SEQ,
+};
+use crate::state::{
+    AuctionStatus, AuctionType, BestBid, BidRecord, BundleItem, Commitment, Config, Counteroffer,
+    HybridPhase, InstallmentPlan, Lease, LeaseTerms, PauseScope, Payout, ACCEPTED_OFFER, BEST_BID,
+    BID_BONDS, BID_COOLDOWNS, BID_COUNTS, BID_RECORDS, BID_SEQ, BUNDLE_DEPOSITED, CANDLE_END,
+    CANDLE_SNAPSHOTS, COMMITMENTS, CONFIG, COUNTEROFFER, DIRECT_OFFERS, ESCROWED, EXTENDED,
+    FEE_POOL, FIXED_PRICE, FORFEITED, INSTALLMENTS, LEADING_DEPOSIT, LEASE, LOT_DEPOSITED, OFFERS,
+    PAUSED, PAUSE_SCOPES, PAYOUT, PROXY_MAX, RETRACTED, ROUND, ROUND_BIDS, RUNNER_UP,
+    SECRET_RESERVE, SETTLEMENT_DEADLINE, STATUS, TICKETS, TICKET_PURCHASES, TICKET_SEQ,
+};
+#[cfg(feature = "stats")]
+use crate::stats;
+use crate::strategy::{has_single_buyer, validate_auction_type};
+use crate::validation::{
+    clearing_price, dutch_price_at, increment_at, reserve_price_at, sealed_commitment,
+    validate_bid, validate_quantity, validate_reserve, validate_step,
+};
+use crate::vesting::{validate_schedule, vested_amount, Vesting, VESTING};
 
 const CONTRACT_NAME: &str = "crates.io:cw20-bid";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+const MAX_EVENT_PREFIX_LENGTH: usize = 32;
+
+// Roughly a minute to a year at six second blocks.
+const MIN_DURATION_IN_BLOCKS: u64 = 10;
+const MAX_DURATION_IN_BLOCKS: u64 = 5_256_000;
+const MIN_DURATION_IN_SECONDS: u64 = 60;
+const MAX_DURATION_IN_SECONDS: u64 = 31_536_000;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -22,29 +77,391 @@ pub fn instantiate(
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    let timeout = env
-        .block
-        .height
-        .checked_add(msg.duration_in_blocks.u64())
-        .expect("Failed to add block height");
+    let timeout = auction_timeout(
+        &env.block,
+        msg.duration_in_blocks,
+        msg.duration,
+        msg.expiration,
+    )?;
+    if msg.increment.is_zero() {
+        return Err(ContractError::ZeroIncrement {});
+    }
+    // A typo'd address would leave the lot unsellable, so make sure it answers as a cw20.
+    let token_addr = deps.api.addr_validate(msg.token_addr.as_str())?;
+    let token_info: TokenInfoResponse = deps
+        .querier
+        .query_wasm_smart(&token_addr, &Cw20QueryMsg::TokenInfo {})
+        .map_err(|_| ContractError::NotACw20Token {
+            addr: token_addr.to_string(),
+        })?;
+    let bundle = msg
+        .bundle
+        .unwrap_or_default()
+        .into_iter()
+        .map(|item| match item {
+            BundleItemMsg::Cw20 { address, amount } => Ok(BundleItem::Cw20 {
+                address: deps.api.addr_validate(&address)?,
+                amount,
+            }),
+            BundleItemMsg::Cw721 { address, token_id } => Ok(BundleItem::Cw721 {
+                address: deps.api.addr_validate(&address)?,
+                token_id,
+            }),
+            BundleItemMsg::Cw1155 {
+                address,
+                token_id,
+                amount,
+            } => Ok(BundleItem::Cw1155 {
+                address: deps.api.addr_validate(&address)?,
+                token_id,
+                amount,
+            }),
+        })
+        .collect::<StdResult<Vec<_>>>()?;
     let config = Config {
         seller: info.sender.clone(),
-        token_addr: deps.api.addr_validate(msg.token_addr.as_str())?,
+        token_addr,
+        token_decimals: token_info.decimals,
+        token_symbol: token_info.symbol,
         reserve_price: msg.reserve_price,
         increment: msg.increment,
-        timeout: Uint64::new(timeout),
+        timeout,
+        payout_delay: msg.payout_delay_in_blocks,
+        quantity: msg.quantity.unwrap_or_else(|| Uint128::new(1)),
+        max_bid_multiplier: msg.max_bid_multiplier,
+        default_limit: msg.default_limit.unwrap_or(DEFAULT_LIMIT),
+        max_limit: msg.max_limit.unwrap_or(MAX_LIMIT),
+        proposal_confirmation: msg.proposal_confirmation.unwrap_or(false),
+        manager: None,
+        start: Uint64::new(env.block.height),
+        reserve_decay: msg.reserve_decay,
+        allow_self_raise: msg.allow_self_raise.unwrap_or(false),
+        // Native coins attached at instantiate are the lot, released to the winner on settlement.
+        lot: info.funds.clone(),
+        installment_plan: msg.installment_plan,
+        event_prefix: msg.event_prefix,
+        escrow: msg.escrow.unwrap_or(false),
+        allow_seller_bids: msg.allow_seller_bids.unwrap_or(false),
+        verify_funds: msg.verify_funds.unwrap_or(false),
+        settlement_window: msg.settlement_window_in_blocks,
+        max_extension: msg.max_extension,
+        soft_close_window: msg.soft_close_window,
+        extension: msg.extension,
+        strict_increments: msg.strict_increments.unwrap_or(false),
+        increment_tiers: msg.increment_tiers.unwrap_or_default(),
+        bid_cooldown: msg.bid_cooldown,
+        max_bids_per_bidder: msg.max_bids_per_bidder,
+        retraction_penalty_bps: msg.retraction_penalty_bps,
+        require_exact_payment: msg.require_exact_payment.unwrap_or(false),
+        admin: msg
+            .admin
+            .map(|admin| deps.api.addr_validate(&admin))
+            .transpose()?,
+        auction_type: msg.auction_type.unwrap_or(AuctionType::English),
+        second_price: msg.second_price.unwrap_or(false),
+        buy_now_price: msg.buy_now_price,
+        lot_token: msg
+            .lot_token
+            .map(|lot_token| deps.api.addr_validate(&lot_token))
+            .transpose()?,
+        reserve_hash: msg.reserve_hash,
+        seed_hash: msg.seed_hash,
+        bundle,
+        bid_bond: msg.bid_bond,
+        payout_vesting: msg.payout_vesting,
+        lease: msg.lease,
     };
+    if config.quantity.is_zero() {
+        return Err(ContractError::ZeroQuantity {});
+    }
+    if let Some(max_bid_multiplier) = config.max_bid_multiplier {
+        if max_bid_multiplier <= Uint128::new(1) {
+            return Err(ContractError::InvalidMaxBidMultiplier {});
+        }
+    }
+    if let Some(reserve_decay) = &config.reserve_decay {
+        if reserve_decay.interval_in_blocks.is_zero()
+            || reserve_decay.decay_bps == 0
+            || reserve_decay.decay_bps > BPS_DENOMINATOR
+            || reserve_decay.floor > config.reserve_price
+        {
+            return Err(ContractError::InvalidReserveDecay {});
+        }
+    }
+    // Strict increments step from the reserve by a single increment, so they cannot be tiered.
+    let tiers_ascending = config
+        .increment_tiers
+        .windows(2)
+        .all(|tiers| tiers[0].below < tiers[1].below);
+    if !tiers_ascending
+        || config
+            .increment_tiers
+            .iter()
+            .any(|tier| tier.increment.is_zero())
+        || (config.strict_increments && !config.increment_tiers.is_empty())
+    {
+        return Err(ContractError::InvalidIncrementTiers {});
+    }
+    if let Some(installment_plan) = &config.installment_plan {
+        if installment_plan.count == 0
+            || installment_plan.interval_in_blocks.is_zero()
+            || installment_plan.forfeit_bps > BPS_DENOMINATOR
+            || matches!(
+                installment_plan.down_payment_bps,
+                Some(bps) if bps == 0 || bps >= BPS_DENOMINATOR
+            )
+        {
+            return Err(ContractError::InvalidInstallmentPlan {});
+        }
+    }
+    if config.escrow && config.installment_plan.is_some() {
+        return Err(ContractError::EscrowInstallmentsUnsupported {});
+    }
+    if let Some(lease) = &config.lease {
+        if lease.period_in_blocks.is_zero()
+            || lease.period_payment.is_zero()
+            || lease.periods == 0
+            || config.installment_plan.is_some()
+        {
+            return Err(ContractError::InvalidLeaseTerms {});
+        }
+    }
+    if let Some(payout_vesting) = &config.payout_vesting {
+        if !validate_schedule(payout_vesting)
+            || config.payout_delay.is_some()
+            || config.proposal_confirmation
+        {
+            return Err(ContractError::InvalidVestingSchedule {});
+        }
+    }
+    validate_auction_type(&config)?;
+    // Each cw20 token and cw1155 token id can only be bundled once so a deposit matches a single
+    // item.
+    for (index, item) in config.bundle.iter().enumerate() {
+        let invalid = match item {
+            BundleItem::Cw20 { address, amount } => {
+                amount.is_zero()
+                    || *address == config.token_addr
+                    || config.bundle[..index].iter().any(|other| match other {
+                        BundleItem::Cw20 {
+                            address: other_address,
+                            ..
+                        } => other_address == address,
+                        BundleItem::Cw721 { .. } | BundleItem::Cw1155 { .. } => false,
+                    })
+            }
+            BundleItem::Cw721 { .. } => config.bundle[..index].contains(item),
+            BundleItem::Cw1155 {
+                address,
+                token_id,
+                amount,
+            } => {
+                amount.is_zero()
+                    || config.bundle[..index].iter().any(|other| {
+                        matches!(
+                            other,
+                            BundleItem::Cw1155 {
+                                address: other_address,
+                                token_id: other_token_id,
+                                ..
+                            } if other_address == address && other_token_id == token_id
+                        )
+                    })
+            }
+        };
+        if invalid {
+            return Err(ContractError::InvalidBundle {});
+        }
+    }
+    if config.reserve_hash.is_some() && config.auction_type != AuctionType::English {
+        return Err(ContractError::UnsupportedByAuctionType {});
+    }
+    if let Some(buy_now_price) = config.buy_now_price {
+        if buy_now_price < config.reserve_price {
+            return Err(ContractError::InvalidBuyNowPrice {});
+        }
+        if config.auction_type != AuctionType::English {
+            return Err(ContractError::UnsupportedByAuctionType {});
+        }
+    }
+    // Deadlines after the close are counted in blocks from the timeout height, as is the Dutch
+    // price curve.
+    if (config.installment_plan.is_some()
+        || config.settlement_window.is_some()
+        || config.auction_type != AuctionType::English)
+        && !matches!(config.timeout, Expiration::AtHeight(_))
+    {
+        return Err(ContractError::HeightExpirationRequired {});
+    }
+    if let Some(settlement_window) = config.settlement_window {
+        if settlement_window.is_zero() {
+            return Err(ContractError::ZeroSettlementWindow {});
+        }
+        // Installments already have their own deadlines and default handling.
+        if config.installment_plan.is_some() {
+            return Err(ContractError::SettlementWindowInstallmentsUnsupported {});
+        }
+    }
+    if let Some(max_extension) = config.max_extension {
+        extension_amount(&config, max_extension)?;
+    }
+    if let Some(Duration::Height(0) | Duration::Time(0)) = config.bid_cooldown {
+        return Err(ContractError::ZeroBidCooldown {});
+    }
+    if config.max_bids_per_bidder == Some(0) {
+        return Err(ContractError::ZeroBidLimit {});
+    }
+    if config.bid_bond == Some(Uint128::zero()) {
+        return Err(ContractError::ZeroBidBond {});
+    }
+    if let Some(retraction_penalty_bps) = config.retraction_penalty_bps {
+        if retraction_penalty_bps > BPS_DENOMINATOR
+            || !config.escrow
+            || config.auction_type != AuctionType::English
+        {
+            return Err(ContractError::InvalidRetractionPenalty {});
+        }
+    }
+    match (config.soft_close_window, config.extension) {
+        (None, None) => {}
+        (Some(soft_close_window), Some(extension)) => {
+            extension_amount(&config, soft_close_window)?;
+            extension_amount(&config, extension)?;
+        }
+        _ => return Err(ContractError::InvalidSoftClose {}),
+    }
+    if let Some(event_prefix) = &config.event_prefix {
+        if event_prefix.is_empty()
+            || event_prefix.len() > MAX_EVENT_PREFIX_LENGTH
+            || !event_prefix
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(ContractError::InvalidEventPrefix {
+                prefix: event_prefix.clone(),
+            });
+        }
+    }
+    if config.max_limit == 0 || config.default_limit > config.max_limit {
+        return Err(ContractError::InvalidPaginationLimits {
+            default_limit: config.default_limit,
+            max_limit: config.max_limit,
+        });
+    }
     CONFIG.save(deps.storage, &config)?;
 
     BID_SEQ.save(deps.storage, &0u64)?;
+    STATUS.save(deps.storage, &AuctionStatus::Created)?;
 
     Ok(Response::new()
-        .add_attribute("action", "instantiate")
+        .add_attribute("action", prefixed(&config, "instantiate"))
         .add_attribute("seller", info.sender)
         .add_attribute("token_addr", msg.token_addr)
         .add_attribute("reserve_price", msg.reserve_price)
         .add_attribute("increment", msg.increment)
-        .add_attribute("timeout", timeout.to_string()))
+        .add_attribute("timeout", timeout.to_string())
+        .add_attribute(
+            "payout_delay",
+            msg.payout_delay_in_blocks.unwrap_or_default(),
+        )
+        .add_attribute("quantity", config.quantity))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let from_version = ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    run_migrations(deps.storage, &from_version, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", from_version)
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+// Lets chain governance end a stuck auction, bypassing the timeout and the usual permissions.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    match msg {
+        SudoMsg::ForceClose {} => sudo_force_close(deps, env, config),
+        SudoMsg::ForceCancelAndRefund {} => sudo_force_cancel_and_refund(deps, env, config),
+    }
+}
+
+pub fn sudo_force_close(
+    deps: DepsMut,
+    env: Env,
+    config: Config,
+) -> Result<Response, ContractError> {
+    let status = auction_status(deps.storage, &config, &env.block)?;
+    ensure_status(
+        status,
+        &[
+            AuctionStatus::Created,
+            AuctionStatus::Active,
+            AuctionStatus::Ended,
+        ],
+    )?;
+    let res = close_and_settle(deps.storage, &env, &config)?;
+    Ok(res.add_attribute("action", prefixed(&config, "sudo_force_close")))
+}
+
+// Cancels an unsettled auction and returns everything the contract holds for it: escrowed bids
+// and paid installments to the buyer, sealed-bid deposits to their bidders, and the native lot to
+// the seller.
+pub fn sudo_force_cancel_and_refund(
+    deps: DepsMut,
+    env: Env,
+    config: Config,
+) -> Result<Response, ContractError> {
+    let status = auction_status(deps.storage, &config, &env.block)?;
+    ensure_status(
+        status,
+        &[
+            AuctionStatus::Created,
+            AuctionStatus::Active,
+            AuctionStatus::Ended,
+            AuctionStatus::Failed,
+        ],
+    )?;
+
+    let closed_event = close_auction(deps.storage, &config)?;
+    STATUS.save(deps.storage, &AuctionStatus::Cancelled)?;
+    COUNTEROFFER.remove(deps.storage);
+
+    let cw20 = Cw20Contract(config.token_addr.clone());
+    let mut refund = ESCROWED.may_load(deps.storage)?.unwrap_or_default();
+    ESCROWED.remove(deps.storage);
+    PROXY_MAX.remove(deps.storage);
+    // A defaulted plan has already been split between the seller and the buyer.
+    if let Some(installments) = INSTALLMENTS.may_load(deps.storage)? {
+        if !installments.defaulted {
+            refund = refund.checked_add(installments.paid)?;
+            INSTALLMENTS.remove(deps.storage);
+        }
+    }
+    let mut res = Response::new().add_events(closed_event);
+    // Batch bidders claim their own refunds once the auction is cancelled.
+    if let Some(lot_msg) = lot_token_refund(deps.storage, &config)? {
+        res = res.add_message(lot_msg);
+    }
+    if !refund.is_zero() {
+        let best_bid = BEST_BID.load(deps.storage)?;
+        let buyer = best_bid.bid_record.buyer;
+        res = res
+            .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: buyer.to_string(),
+                amount: refund,
+            })?)
+            .add_event(refund_event(&config, &buyer, refund, "cancelled"))
+            .add_attribute("buyer", buyer);
+    }
+    res = refund_sealed_deposits(deps.storage, &config, res)?;
+    res = res.add_messages(lot_msgs(deps.storage, &env, &config, &config.seller)?);
+
+    Ok(res
+        .add_attribute("action", prefixed(&config, "sudo_force_cancel_and_refund"))
+        .add_attribute("refund", refund))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -54,365 +471,8992 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // Payments are made in the cw20 token, so native coins sent along would be stuck here.
+    nonpayable(&info)?;
+    // Config is loaded once per message and handed to the handlers.
+    let config = CONFIG.load(deps.storage)?;
+    if let Some(scope) = pause_scope(&msg) {
+        if PAUSED.may_load(deps.storage)?.unwrap_or_default().contains(&scope) {
+            return Err(ContractError::ContractPaused {});
+        }
+    }
     match msg {
-        ExecuteMsg::Bid { price } => execute_bid(deps, env.block.height, info, price),
-        ExecuteMsg::Receive(msg) => execute_receive(deps, env.block.height, info, msg),
+        ExecuteMsg::Bid { price, quantity } => {
+            execute_bid(deps, env, info, config, price, quantity)
+        }
+        ExecuteMsg::Receive(TokenReceiveMsg::Cw20(msg)) => {
+            execute_receive(deps, env, info, config, msg)
+        }
+        ExecuteMsg::Receive(TokenReceiveMsg::Cw1155(msg)) => {
+            execute_receive_cw1155(deps, &env.block, info, config, msg)
+        }
+        ExecuteMsg::ReceiveNft(msg) => execute_receive_nft(deps, &env.block, info, config, msg),
+        ExecuteMsg::ClaimPayout { proposal_id } => {
+            execute_claim_payout(deps, &env, info, config, proposal_id)
+        }
+        ExecuteMsg::Offer { price } => execute_offer(deps, &env.block, info, config, price),
+        ExecuteMsg::AcceptOffer { buyer } => execute_accept_offer(deps, env, info, config, buyer),
+        ExecuteMsg::Counteroffer {
+            buyer,
+            price,
+            duration_in_blocks,
+        } => execute_counteroffer(
+            deps,
+            &env.block,
+            info,
+            config,
+            buyer,
+            price,
+            duration_in_blocks,
+        ),
+        ExecuteMsg::AcceptCounteroffer {} => {
+            execute_accept_counteroffer(deps, &env.block, info, config)
+        }
+        ExecuteMsg::SetManager { manager } => {
+            execute_set_manager(deps, &env.block, info, config, manager)
+        }
+        ExecuteMsg::ReclaimLot {} => execute_reclaim_lot(deps, &env, info, config),
+        ExecuteMsg::Cancel {} => execute_cancel(deps, &env, info, config),
+        ExecuteMsg::ClaimDefault {} => execute_claim_default(deps, &env.block, info, config),
+        ExecuteMsg::Close {} => execute_close(deps, env, config),
+        ExecuteMsg::ForfeitWinner {} => execute_forfeit_winner(deps, &env.block, config),
+        ExecuteMsg::ExtendDeadline { additional } => {
+            execute_extend_deadline(deps, &env.block, info, config, additional)
+        }
+        ExecuteMsg::Pause { scopes } => execute_set_paused(deps, info, config, scopes, true),
+        ExecuteMsg::Unpause { scopes } => execute_set_paused(deps, info, config, scopes, false),
+        ExecuteMsg::Reveal { price, salt } => {
+            execute_reveal(deps, &env.block, info, config, price, salt)
+        }
+        ExecuteMsg::SlashUnrevealed {} => execute_slash_unrevealed(deps, &env.block, config),
+        ExecuteMsg::ClaimBatchFill {} => execute_claim_batch_fill(deps, &env.block, info, config),
+        ExecuteMsg::ClaimTicketRefund {} => {
+            execute_claim_ticket_refund(deps, &env.block, info, config)
+        }
+        ExecuteMsg::ConvertToFixedPrice { price } => {
+            execute_convert_to_fixed_price(deps, &env.block, info, config, price)
+        }
+        ExecuteMsg::RevealReserve { reserve, salt } => {
+            execute_reveal_reserve(deps, &env.block, info, config, reserve, salt)
+        }
+        ExecuteMsg::RevealSeed { seed } => {
+            execute_reveal_seed(deps, &env.block, info, config, seed)
+        }
+        ExecuteMsg::AcceptBid {} => execute_accept_bid(deps, env, info, config),
+        ExecuteMsg::Retract {} => execute_retract(deps, &env.block, info, config),
+        ExecuteMsg::WithdrawOffer {} => execute_withdraw_offer(deps, info, config),
+        ExecuteMsg::CreatePool {} => execute_create_pool(deps, &env.block, info, config),
+        ExecuteMsg::PoolBid {
+            pool_id,
+            price,
+            quantity,
+        } => execute_pool_bid(deps, &env.block, info, config, pool_id, price, quantity),
+        ExecuteMsg::SettlePool { pool_id } => execute_settle_pool(deps, env, config, pool_id),
+        ExecuteMsg::WithdrawFromPool { pool_id } => {
+            execute_withdraw_from_pool(deps, &env.block, info, config, pool_id)
+        }
+        ExecuteMsg::ReclaimDeposit {} => execute_reclaim_deposit(deps, &env.block, info, config),
+        ExecuteMsg::ClaimVested {} => execute_claim_vested(deps, &env.block, info, config),
+        ExecuteMsg::EndLease {} => execute_end_lease(deps, &env, config),
+        ExecuteMsg::ClaimBidFees {} => execute_claim_bid_fees(deps, &env.block, info, config),
+        ExecuteMsg::Relist { new_config } => {
+            execute_relist(deps, &env.block, info, config, new_config)
+        }
+    }
+}
+
+// Draws the effective end of a candle auction once the seed is revealed, and rolls the best bid
+// back to the one standing at that block.
+fn resolve_candle(
+    storage: &mut dyn Storage,
+    config: &Config,
+) -> Result<Option<Event>, ContractError> {
+    let window_in_blocks = match config.auction_type {
+        AuctionType::Candle { window_in_blocks } => window_in_blocks.u64(),
+        _ => return Ok(None),
+    };
+    let drawn = match drawn_value(storage)? {
+        Some(drawn) if CANDLE_END.may_load(storage)?.is_none() => drawn,
+        _ => return Ok(None),
+    };
+    let window_start = timeout_height(config)?.u64() - window_in_blocks;
+    let candle_end = window_start + drawn % window_in_blocks;
+    CANDLE_END.save(storage, &candle_end)?;
+
+    let winning_id = CANDLE_SNAPSHOTS
+        .range(
+            storage,
+            None,
+            Some(Bound::inclusive(candle_end)),
+            Order::Descending,
+        )
+        .next()
+        .transpose()?
+        .map(|(_, id)| id);
+    // Bids after the effective end never happened, as far as the sale is concerned.
+    RUNNER_UP.remove(storage);
+    match winning_id {
+        Some(id) => {
+            let best_bid = BestBid {
+                id: Uint64::new(id),
+                bid_record: BID_RECORDS.load(storage, id)?,
+                sold: false,
+            };
+            BEST_BID.save(storage, &best_bid)?;
+        }
+        None => BEST_BID.remove(storage),
     }
+
+    Ok(Some(
+        Event::new(prefixed(config, "candle_resolved"))
+            .add_attribute("candle_end", candle_end.to_string())
+            .add_attribute("bid_id", winning_id.unwrap_or_default().to_string()),
+    ))
 }
 
 pub fn execute_bid(
     deps: DepsMut,
-    block_height: u64,
+    env: Env,
     info: MessageInfo,
+    config: Config,
     price: Uint128,
+    quantity: Option<Uint128>,
 ) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
-    if block_height >= config.timeout.u64() {
-        return Err(ContractError::CustomError {
-            val: format!("Auction closed"),
-        });
+    if config.escrow {
+        return Err(ContractError::EscrowRequired {});
     }
-    if price < config.reserve_price {
-        return Err(ContractError::CustomError {
-            val: format!(
-                "Bid price lower than reserve price, bid price: {:?}, reserve price: {:?}",
-                price, config.reserve_price
-            ),
-        });
+    let status = auction_status(deps.storage, &config, &env.block)?;
+    ensure_status(status, &[AuctionStatus::Active])?;
+    start_cooldown(deps.storage, &env.block, &config, &info.sender)?;
+    ensure_bid_limit(deps.storage, &config, &info.sender)?;
+    let quantity = quantity.unwrap_or_else(|| Uint128::new(1));
+    if config.verify_funds {
+        let total = price.checked_mul(quantity)?;
+        verify_funds(deps.as_ref(), &env, &config, &info.sender, total)?;
     }
+    let phase = hybrid_phase(deps.storage, &config)?;
+    let (next_id, total) = place_bid(
+        deps.storage,
+        &env.block,
+        &config,
+        &info.sender,
+        price,
+        quantity,
+    )?;
+    let (config, english_event) = match phase {
+        Some(HybridPhase::Dutch) => open_english_phase(deps.storage, &env.block, config)?,
+        _ => (config, None),
+    };
+    let extended_event = soft_close(deps.storage, &env.block, &config)?;
 
-    let id = BID_SEQ.load(deps.storage)?;
-    let best_price = if id == 0u64 {
-        config.reserve_price
-    } else {
-        let best_bid = BEST_BID.load(deps.storage)?;
-        if price <= best_bid.bid_record.price {
-            return Err(ContractError::CustomError {
-                val: format!(
-                    "Bid price not greater than best price, bid price: {:?}, best price: {:?}",
-                    price, best_bid.bid_record.price
-                ),
-            });
-        }
-        best_bid.bid_record.price
-    };
-    let increment = price
-        .checked_sub(best_price)
-        .expect("Failed to get bid increment");
-    if increment < config.increment {
-        return Err(ContractError::CustomError {
-            val: format!(
-                "Bid increment too low, increment: {:?}, minimum increment: {:?}",
-                increment, config.increment
-            ),
-        });
+    Ok(Response::new()
+        .add_events(english_event)
+        .add_events(extended_event)
+        .add_attribute("action", prefixed(&config, "execute_bid"))
+        .add_attribute("id", next_id)
+        .add_attribute("buyer", info.sender)
+        .add_attribute("price", price)
+        .add_attribute("quantity", quantity)
+        .add_attribute("total", total))
+}
+
+// A hybrid auction is in its Dutch phase until the first bid, and None for other formats.
+fn hybrid_phase(storage: &dyn Storage, config: &Config) -> StdResult<Option<HybridPhase>> {
+    if !matches!(config.auction_type, AuctionType::Hybrid { .. }) {
+        return Ok(None);
     }
+    Ok(Some(match BEST_BID.may_load(storage)? {
+        Some(_) => HybridPhase::English,
+        None => HybridPhase::Dutch,
+    }))
+}
 
-    let next_id = Uint64::new(id)
-        .checked_add(Uint64::new(1))
-        .expect("Failed to increment the sequence");
-    BID_SEQ.save(deps.storage, &next_id.u64())?;
+// Replaces the timeout with the end of the English window, counted from the first bid.
+fn open_english_phase(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    mut config: Config,
+) -> Result<(Config, Option<Event>), ContractError> {
+    let english_window_in_blocks = match config.auction_type {
+        AuctionType::Hybrid {
+            english_window_in_blocks,
+            ..
+        } => english_window_in_blocks,
+        _ => return Ok((config, None)),
+    };
+    let timeout = Uint64::new(block.height).checked_add(english_window_in_blocks)?;
+    config.timeout = Expiration::AtHeight(timeout.u64());
+    CONFIG.save(storage, &config)?;
+    let event = Event::new(prefixed(&config, "english_phase_opened"))
+        .add_attribute("deadline", config.timeout.to_string());
+    Ok((config, Some(event)))
+}
 
-    let bid_record = BidRecord {
-        buyer: info.sender.clone(),
-        price,
+// A penny bid pays the fee instead of a price: the bidder takes the lead one step above the
+// current price, and the timeout moves out to at least the extension from now.
+pub fn receive_penny_bid(
+    deps: DepsMut,
+    block: &BlockInfo,
+    mut config: Config,
+    amount: Uint128,
+    bidder: Addr,
+) -> Result<Response, ContractError> {
+    let (bid_fee, price_step, extension_in_blocks) = match config.auction_type {
+        AuctionType::Penny {
+            bid_fee,
+            price_step,
+            extension_in_blocks,
+        } => (bid_fee, price_step, extension_in_blocks),
+        _ => return Err(ContractError::UnsupportedByAuctionType {}),
     };
-    BID_RECORDS.save(deps.storage, next_id.u64(), &bid_record)?;
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Active])?;
+    if !config.allow_seller_bids && bidder == config.seller {
+        return Err(ContractError::SellerCannotBid {});
+    }
+    let best_bid = BEST_BID.may_load(deps.storage)?;
+    if let Some(best_bid) = &best_bid {
+        if best_bid.bid_record.buyer == bidder {
+            return Err(ContractError::AlreadyBestBidder {});
+        }
+    }
+    ensure_bundle_deposited(deps.storage, &config)?;
+    ensure_bid_bond(deps.storage, &config, &bidder)?;
+    start_cooldown(deps.storage, block, &config, &bidder)?;
+    ensure_bid_limit(deps.storage, &config, &bidder)?;
+    ensure_payment(&config, amount, bid_fee)?;
 
-    let best_bid = BestBid {
-        id: next_id,
-        bid_record: BidRecord {
-            buyer: info.sender.clone(),
-            price,
-        },
-        sold: false,
+    let current_price = match best_bid {
+        Some(best_bid) => best_bid.bid_record.price,
+        None => config.reserve_price,
     };
-    BEST_BID.save(deps.storage, &best_bid)?;
+    let price = current_price.checked_add(price_step)?;
+    let next_id = save_best_bid(deps.storage, bidder.clone(), price, config.quantity)?;
+    record_bid(deps.storage, &bidder)?;
+    let fee_pool = FEE_POOL.may_load(deps.storage)?.unwrap_or_default();
+    FEE_POOL.save(deps.storage, &fee_pool.checked_add(bid_fee)?)?;
 
-    Ok(Response::new()
-        .add_attribute("action", "execute_bid")
+    let mut res = Response::new();
+    let min_timeout = Expiration::AtHeight(block.height + extension_in_blocks.u64());
+    if config.timeout < min_timeout {
+        let old_timeout = config.timeout;
+        config.timeout = min_timeout;
+        CONFIG.save(deps.storage, &config)?;
+        res = res.add_event(
+            Event::new(prefixed(&config, "deadline_extended"))
+                .add_attribute("old_deadline", old_timeout.to_string())
+                .add_attribute("new_deadline", config.timeout.to_string()),
+        );
+    }
+    let refund = amount - bid_fee;
+    if !refund.is_zero() {
+        let cw20 = Cw20Contract(config.token_addr.clone());
+        res = res
+            .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: bidder.to_string(),
+                amount: refund,
+            })?)
+            .add_event(refund_event(&config, &bidder, refund, "overpaid"));
+    }
+    Ok(res
+        .add_attribute("action", prefixed(&config, "receive_penny_bid"))
         .add_attribute("id", next_id)
-        .add_attribute("buyer", info.sender)
-        .add_attribute("price", price))
+        .add_attribute("buyer", bidder)
+        .add_attribute("price", price)
+        .add_attribute("fee", bid_fee))
 }
 
-pub fn execute_receive(
+// The fees belong to the seller whether or not the winner pays, so they can be claimed as soon as
+// bidding is over.
+pub fn execute_claim_bid_fees(
     deps: DepsMut,
-    block_height: u64,
+    block: &BlockInfo,
     info: MessageInfo,
-    wrapped_msg: Cw20ReceiveMsg,
+    config: Config,
 ) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
-    if block_height < config.timeout.u64() {
-        return Err(ContractError::CustomError {
-            val: format!("Auction not yet closed"),
-        });
+    ensure_seller_or_manager(&config, &info.sender)?;
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(
+        status,
+        &[
+            AuctionStatus::Ended,
+            AuctionStatus::Settled,
+            AuctionStatus::Cancelled,
+            AuctionStatus::Failed,
+        ],
+    )?;
+    let fees = FEE_POOL.may_load(deps.storage)?.unwrap_or_default();
+    if fees.is_zero() {
+        return Err(ContractError::NoBidFees {});
     }
+    FEE_POOL.save(deps.storage, &Uint128::zero())?;
 
-    let msg: ReceiveMsg = from_binary(&wrapped_msg.msg)?;
-    match msg {
-        ReceiveMsg::Buy => receive_buy(deps, config.token_addr, wrapped_msg.amount, info.sender, config.seller),
+    let cw20 = Cw20Contract(config.token_addr.clone());
+    Ok(Response::new()
+        .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+            recipient: config.seller.to_string(),
+            amount: fees,
+        })?)
+        .add_attribute("action", prefixed(&config, "execute_claim_bid_fees"))
+        .add_attribute("seller", config.seller)
+        .add_attribute("amount", fees))
+}
+
+// Pools hold the tokens behind their bids instead of escrowing them, and settle from them.
+fn ensure_pools_supported(config: &Config) -> Result<(), ContractError> {
+    if config.auction_type != AuctionType::English
+        || config.escrow
+        || config.installment_plan.is_some()
+    {
+        return Err(ContractError::PoolsUnsupported {});
     }
+    Ok(())
 }
 
-pub fn receive_buy(
+pub fn execute_create_pool(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    config: Config,
+) -> Result<Response, ContractError> {
+    ensure_pools_supported(&config)?;
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Created, AuctionStatus::Active])?;
+
+    let pool_id = POOL_SEQ.may_load(deps.storage)?.unwrap_or_default() + 1;
+    POOL_SEQ.save(deps.storage, &pool_id)?;
+    let pool = Pool {
+        creator: info.sender.clone(),
+        contributed: Uint128::zero(),
+        spent: Uint128::zero(),
+    };
+    POOLS.save(deps.storage, pool_id, &pool)?;
+
+    Ok(Response::new()
+        .add_attribute("action", prefixed(&config, "execute_create_pool"))
+        .add_attribute("pool_id", pool_id.to_string())
+        .add_attribute("creator", info.sender))
+}
+
+pub fn receive_contribute_to_pool(
     deps: DepsMut,
-    token_addr: Addr,
+    block: &BlockInfo,
+    config: Config,
     amount: Uint128,
-    buyer: Addr,
-    seller: Addr,
+    contributor: Addr,
+    pool_id: u64,
 ) -> Result<Response, ContractError> {
-    let mut best_bid = BEST_BID.load(deps.storage)?;
-    if best_bid.sold {
-        return Err(ContractError::CustomError {
-            val: format!("Item already sold"),
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Created, AuctionStatus::Active])?;
+    let mut pool = POOLS.load(deps.storage, pool_id)?;
+    pool.contributed = pool.contributed.checked_add(amount)?;
+    POOLS.save(deps.storage, pool_id, &pool)?;
+    let contribution = POOL_CONTRIBUTIONS
+        .may_load(deps.storage, (pool_id, &contributor))?
+        .unwrap_or_default()
+        .checked_add(amount)?;
+    POOL_CONTRIBUTIONS.save(deps.storage, (pool_id, &contributor), &contribution)?;
+    CONTRIBUTOR_POOLS.save(deps.storage, (&contributor, pool_id), &Empty {})?;
+
+    Ok(Response::new()
+        .add_attribute("action", prefixed(&config, "receive_contribute_to_pool"))
+        .add_attribute("pool_id", pool_id.to_string())
+        .add_attribute("contributor", contributor)
+        .add_attribute("amount", amount)
+        .add_attribute("contributed", pool.contributed))
+}
+
+// The creator bids for the pool, as long as its contributions cover the bid.
+pub fn execute_pool_bid(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    config: Config,
+    pool_id: u64,
+    price: Uint128,
+    quantity: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    ensure_pools_supported(&config)?;
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Active])?;
+    let pool = POOLS.load(deps.storage, pool_id)?;
+    if info.sender != pool.creator {
+        return Err(ContractError::Unauthorized {});
+    }
+    start_cooldown(deps.storage, block, &config, &info.sender)?;
+    ensure_bid_limit(deps.storage, &config, &info.sender)?;
+    let quantity = quantity.unwrap_or_else(|| Uint128::new(1));
+    let required = price.checked_mul(quantity)?;
+    if pool.contributed < required {
+        return Err(ContractError::InsufficientPayment {
+            amount: pool.contributed,
+            required,
         });
     }
-    if buyer != best_bid.bid_record.buyer {
+    let (next_id, total) = place_bid(deps.storage, block, &config, &info.sender, price, quantity)?;
+    let round = ROUND.may_load(deps.storage)?.unwrap_or_default();
+    POOL_BIDS.save(deps.storage, (round, next_id.u64()), &pool_id)?;
+    let extended_event = soft_close(deps.storage, block, &config)?;
+
+    Ok(Response::new()
+        .add_events(extended_event)
+        .add_attribute("action", prefixed(&config, "execute_pool_bid"))
+        .add_attribute("id", next_id)
+        .add_attribute("pool_id", pool_id.to_string())
+        .add_attribute("price", price)
+        .add_attribute("quantity", quantity)
+        .add_attribute("total", total))
+}
+
+// Anyone can settle a winning pool bid from the pool. The lot goes to the creator, who holds it
+// for the contributors.
+pub fn execute_settle_pool(
+    deps: DepsMut,
+    env: Env,
+    config: Config,
+    pool_id: u64,
+) -> Result<Response, ContractError> {
+    let status = auction_status(deps.storage, &config, &env.block)?;
+    ensure_status(status, &[AuctionStatus::Ended])?;
+    ensure_reserve_revealed(deps.storage, &config)?;
+    let best_bid = BEST_BID
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoWinningBid {})?;
+    let round = ROUND.may_load(deps.storage)?.unwrap_or_default();
+    if POOL_BIDS.may_load(deps.storage, (round, best_bid.id.u64()))? != Some(pool_id) {
         return Err(ContractError::Unauthorized {});
     }
-    if amount < best_bid.bid_record.price {
-        return Err(ContractError::CustomError {
-            val: format!(
-                "Amount lower than bid price, amount: {:?}, bid price: {:?}",
-                amount, best_bid.bid_record.price
-            ),
+    if let Some(deadline) = settlement_deadline(deps.storage, &config)? {
+        if env.block.height >= deadline {
+            return Err(ContractError::SettlementWindowExpired { deadline });
+        }
+    }
+    // A runner-up pool bid may have lost its backing to withdrawals after it was outbid.
+    let mut pool = POOLS.load(deps.storage, pool_id)?;
+    let total = sale_total(deps.storage, &config, &best_bid)?;
+    if pool.contributed < total {
+        return Err(ContractError::InsufficientPayment {
+            amount: pool.contributed,
+            required: total,
         });
     }
+    pool.spent = total;
+    POOLS.save(deps.storage, pool_id, &pool)?;
 
-    best_bid.sold = true;
-    BEST_BID.save(deps.storage, &best_bid)?;
+    let res = settle_purchase(
+        deps,
+        env,
+        config,
+        best_bid,
+        total,
+        total,
+        "execute_settle_pool",
+    )?;
+    Ok(res.add_attribute("pool_id", pool_id.to_string()))
+}
 
-    let cw20 = Cw20Contract(token_addr);
-    let msg = cw20.call(Cw20ExecuteMsg::TransferFrom {
-        owner: buyer.clone().into_string(),
-        recipient: seller.into_string(),
-        amount,
-    })?;
+// Contributors take back their contribution once the pool is outbid or the auction is over, or
+// their share of what is left after a win.
+pub fn execute_withdraw_from_pool(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    config: Config,
+    pool_id: u64,
+) -> Result<Response, ContractError> {
+    let mut pool = POOLS.load(deps.storage, pool_id)?;
+    if pool_bid_standing(deps.storage, &config, block, pool_id)? {
+        return Err(ContractError::PoolBidStanding {});
+    }
+    let contribution = POOL_CONTRIBUTIONS
+        .may_load(deps.storage, (pool_id, &info.sender))?
+        .ok_or(ContractError::NoContribution {})?;
+    let refund = refund_for(&pool, contribution);
+    POOL_CONTRIBUTIONS.remove(deps.storage, (pool_id, &info.sender));
+    CONTRIBUTOR_POOLS.remove(deps.storage, (&info.sender, pool_id));
+    // Shares after a win are taken from the final total, so it stays as it is.
+    if pool.spent.is_zero() {
+        pool.contributed -= contribution;
+        POOLS.save(deps.storage, pool_id, &pool)?;
+    }
 
-    Ok(Response::new()
-        .add_message(msg)
-        .add_attribute("action", "receive_buy")
-        .add_attribute("id", best_bid.id)
-        .add_attribute("buyer", buyer)
-        .add_attribute("amount", amount))
+    let mut res = Response::new();
+    if !refund.is_zero() {
+        let cw20 = Cw20Contract(config.token_addr.clone());
+        res = res
+            .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: info.sender.to_string(),
+                amount: refund,
+            })?)
+            .add_event(refund_event(&config, &info.sender, refund, "pool_withdrawal"));
+    }
+
+    Ok(res
+        .add_attribute("action", prefixed(&config, "execute_withdraw_from_pool"))
+        .add_attribute("pool_id", pool_id.to_string())
+        .add_attribute("contributor", info.sender)
+        .add_attribute("refund", refund))
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetConfig => to_binary(&CONFIG.load(deps.storage)?),
-        QueryMsg::GetBidSeq => to_binary(&BID_SEQ.load(deps.storage)?),
-        QueryMsg::GetBidRecord { id } => to_binary(&query_bid(deps, id)?),
-        QueryMsg::GetBestBid => to_binary(&BEST_BID.load(deps.storage)?),
+// A pool's tokens back its bid while it is the best one and the lot can still sell.
+fn pool_bid_standing(
+    storage: &dyn Storage,
+    config: &Config,
+    block: &BlockInfo,
+    pool_id: u64,
+) -> StdResult<bool> {
+    let best_bid = match BEST_BID.may_load(storage)? {
+        Some(best_bid) if !best_bid.sold => best_bid,
+        _ => return Ok(false),
+    };
+    let round = ROUND.may_load(storage)?.unwrap_or_default();
+    if POOL_BIDS.may_load(storage, (round, best_bid.id.u64()))? != Some(pool_id) {
+        return Ok(false);
     }
+    let status = auction_status(storage, config, block)?;
+    Ok(!matches!(
+        status,
+        AuctionStatus::Failed | AuctionStatus::Cancelled
+    ))
 }
 
-fn query_bid(deps: Deps, id: Uint64) -> StdResult<BidResponse> {
-    let bid_record = BID_RECORDS.load(deps.storage, id.u64())?;
-    Ok(BidResponse {
-        buyer: bid_record.buyer.into_string(),
-        price: bid_record.price,
-    })
+// Without escrow a bid is only a promise, so optionally check the bidder could pay it.
+fn verify_funds(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    bidder: &Addr,
+    required: Uint128,
+) -> Result<(), ContractError> {
+    let balance: BalanceResponse = deps.querier.query_wasm_smart(
+        &config.token_addr,
+        &Cw20QueryMsg::Balance {
+            address: bidder.to_string(),
+        },
+    )?;
+    let allowance: AllowanceResponse = deps.querier.query_wasm_smart(
+        &config.token_addr,
+        &Cw20QueryMsg::Allowance {
+            owner: bidder.to_string(),
+            spender: env.contract.address.to_string(),
+        },
+    )?;
+    if balance.balance < required || allowance.allowance < required {
+        return Err(ContractError::InsufficientFunds {
+            balance: balance.balance,
+            allowance: allowance.allowance,
+            required,
+        });
+    }
+    Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::from_binary;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+// In escrow mode the bid arrives with its payment, and the bidder it outbids is refunded.
+#[allow(clippy::too_many_arguments)]
+pub fn receive_bid(
+    deps: DepsMut,
+    block: &BlockInfo,
+    config: Config,
+    amount: Uint128,
+    bidder: Addr,
+    price: Uint128,
+    max_price: Option<Uint128>,
+    quantity: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    if !config.escrow {
+        return Err(ContractError::EscrowNotEnabled {});
+    }
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Active])?;
+    start_cooldown(deps.storage, block, &config, &bidder)?;
+    ensure_bid_limit(deps.storage, &config, &bidder)?;
+    let quantity = quantity.unwrap_or_else(|| Uint128::new(1));
+    if config.auction_type == AuctionType::Batch {
+        if max_price.is_some() {
+            return Err(ContractError::NotEnglishAuction {});
+        }
+        return receive_batch_bid(deps, block, config, amount, bidder, price, quantity);
+    }
+    let leader = match ESCROWED.may_load(deps.storage)? {
+        Some(escrowed) => Some((BEST_BID.load(deps.storage)?.bid_record, escrowed)),
+        None => None,
+    };
+    let leader_max = PROXY_MAX.may_load(deps.storage)?;
+    // A proxy opens just above the best bid, or just above the leading proxy's ceiling if it can.
+    let price = match max_price {
+        None => price,
+        Some(max_price) => {
+            let opening = match (&leader, leader_max) {
+                (Some((leader, _)), _) if leader.buyer == bidder => {
+                    return Err(ContractError::AlreadyBestBidder {})
+                }
+                (Some(_), Some(leader_max)) => {
+                    leader_max.checked_add(increment_at(&config, leader_max))?
+                }
+                (Some((leader, _)), None) => leader
+                    .price
+                    .checked_add(increment_at(&config, leader.price))?,
+                (None, _) => {
+                    let reserve_price = reserve_price_at(&config, block.height);
+                    reserve_price.checked_add(increment_at(&config, reserve_price))?
+                }
+            };
+            max_price.min(opening)
+        }
+    };
+    let total = max_price.unwrap_or(price).checked_mul(quantity)?;
+    if amount < total {
+        return Err(ContractError::InsufficientPayment {
+            amount,
+            required: total,
+        });
+    }
+    let runner_up = RUNNER_UP.may_load(deps.storage)?;
+    let (next_id, _) = place_bid(deps.storage, block, &config, &bidder, price, quantity)?;
 
-    #[test]
-    fn test_instantiate() {
-        let mut deps = mock_dependencies();
-        let token_addr = String::from("cw20 token");
-        let reserve_price = Uint128::new(100);
-        let increment = Uint128::new(10);
-        let duration_in_blocks = Uint64::new(200);
-        let msg = InstantiateMsg {
-            token_addr,
-            reserve_price,
-            increment,
-            duration_in_blocks,
-        };
-        let info = mock_info("creator", &[]);
-        let mut env = mock_env();
-        env.block.height = 200_000;
-        let res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
-        assert_eq!(res.attributes.len(), 6);
+    // The leading proxy counterbids one increment above while its ceiling covers it, and the
+    // new bid is refunded straight away.
+    let cw20 = Cw20Contract(config.token_addr.clone());
+    let counter_price = price.checked_add(increment_at(&config, price))?;
+    let (refund, proxy_event) = match (leader, leader_max) {
+        (Some((leader, _)), Some(leader_max))
+            if leader.buyer != bidder && counter_price <= leader_max =>
+        {
+            let counter_id =
+                place_proxy_counter(deps.storage, block, &config, &leader, counter_price)?;
+            // The refunded bid holds no escrow, so the runner-up stays as it was.
+            match runner_up {
+                Some(runner_up) => RUNNER_UP.save(deps.storage, &runner_up)?,
+                None => RUNNER_UP.remove(deps.storage),
+            }
+            let proxy_event = Event::new(prefixed(&config, "proxy_bid"))
+                .add_attribute("id", counter_id)
+                .add_attribute("buyer", leader.buyer)
+                .add_attribute("price", counter_price);
+            ((bidder.clone(), amount), Some(proxy_event))
+        }
+        (leader, _) => {
+            ESCROWED.save(deps.storage, &amount)?;
+            match max_price {
+                Some(max_price) => PROXY_MAX.save(deps.storage, &max_price)?,
+                None => PROXY_MAX.remove(deps.storage),
+            }
+            match leader {
+                Some((leader, escrowed)) => ((leader.buyer, escrowed), None),
+                None => ((bidder.clone(), Uint128::zero()), None),
+            }
+        }
+    };
+    let extended_event = soft_close(deps.storage, block, &config)?;
 
-        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetConfig).unwrap();
+    let mut res = Response::new();
+    let (outbid, refund) = refund;
+    if !refund.is_zero() {
+        res = res
+            .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: outbid.to_string(),
+                amount: refund,
+            })?)
+            .add_event(refund_event(&config, &outbid, refund, "outbid"));
+    }
+
+    Ok(res
+        .add_events(proxy_event)
+        .add_events(extended_event)
+        .add_attribute("action", prefixed(&config, "receive_bid"))
+        .add_attribute("id", next_id)
+        .add_attribute("buyer", bidder)
+        .add_attribute("price", price)
+        .add_attribute("quantity", quantity)
+        .add_attribute("total", total)
+        .add_attribute("escrowed", amount))
+}
+
+// The best bidder can raise their escrowed bid, subject to the self-raise and increment rules, by
+// sending what the new price adds to their escrow. A proxy ceiling gives way to the new price.
+pub fn receive_top_up(
+    deps: DepsMut,
+    block: &BlockInfo,
+    config: Config,
+    amount: Uint128,
+    bidder: Addr,
+    new_price: Uint128,
+) -> Result<Response, ContractError> {
+    if !config.escrow {
+        return Err(ContractError::EscrowNotEnabled {});
+    }
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Active])?;
+    start_cooldown(deps.storage, block, &config, &bidder)?;
+    ensure_bid_limit(deps.storage, &config, &bidder)?;
+    let best_bid = match BEST_BID.may_load(deps.storage)? {
+        Some(best_bid) if best_bid.bid_record.buyer == bidder => best_bid,
+        _ => {
+            return Err(ContractError::NoBidOrOffer {
+                buyer: bidder.into_string(),
+            })
+        }
+    };
+    let quantity = best_bid.bid_record.quantity;
+    let escrowed = ESCROWED.load(deps.storage)?.checked_add(amount)?;
+    let total = new_price.checked_mul(quantity)?;
+    if escrowed < total {
+        return Err(ContractError::InsufficientPayment {
+            amount: escrowed,
+            required: total,
+        });
+    }
+    let (next_id, _) = place_bid(deps.storage, block, &config, &bidder, new_price, quantity)?;
+    ESCROWED.save(deps.storage, &escrowed)?;
+    PROXY_MAX.remove(deps.storage);
+    let extended_event = soft_close(deps.storage, block, &config)?;
+
+    Ok(Response::new()
+        .add_events(extended_event)
+        .add_attribute("action", prefixed(&config, "receive_top_up"))
+        .add_attribute("id", next_id)
+        .add_attribute("buyer", bidder)
+        .add_attribute("price", new_price)
+        .add_attribute("quantity", quantity)
+        .add_attribute("total", total)
+        .add_attribute("escrowed", escrowed))
+}
+
+fn place_bid(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    config: &Config,
+    bidder: &Addr,
+    price: Uint128,
+    quantity: Uint128,
+) -> Result<(Uint64, Uint128), ContractError> {
+    ensure_bundle_deposited(storage, config)?;
+    ensure_bid_bond(storage, config, bidder)?;
+    let best_bid = BEST_BID.may_load(storage)?;
+    let total = validate_bid(
+        config,
+        block,
+        best_bid.as_ref().map(|best_bid| &best_bid.bid_record),
+        bidder,
+        price,
+        quantity,
+    )?;
+
+    // A raise by the current best bidder keeps the existing runner-up.
+    if let Some(best_bid) = best_bid {
+        if best_bid.bid_record.buyer != *bidder {
+            RUNNER_UP.save(storage, &best_bid)?;
+        }
+    }
+    let next_id = save_best_bid(storage, bidder.clone(), price, quantity)?;
+    record_bid(storage, bidder)?;
+    if let AuctionType::Candle { .. } = config.auction_type {
+        CANDLE_SNAPSHOTS.save(storage, block.height, &next_id.u64())?;
+        mix_entry(storage, &format!("{}:{}:{}", bidder, next_id, price))?;
+    }
+
+    Ok((next_id, total))
+}
+
+// A counterbid placed by the leading proxy on its owner's behalf. It is checked like any bid but
+// does not count towards the owner's bid limit or stats.
+fn place_proxy_counter(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    config: &Config,
+    leader: &BidRecord,
+    price: Uint128,
+) -> Result<Uint64, ContractError> {
+    let best_bid = BEST_BID.load(storage)?;
+    validate_bid(
+        config,
+        block,
+        Some(&best_bid.bid_record),
+        &leader.buyer,
+        price,
+        leader.quantity,
+    )?;
+    save_best_bid(storage, leader.buyer.clone(), price, leader.quantity)
+}
+
+// Rejects a bid placed before the bidder's cooldown is over, and starts a new one. Counterbids
+// placed by a proxy are not throttled.
+fn start_cooldown(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    config: &Config,
+    bidder: &Addr,
+) -> Result<(), ContractError> {
+    let bid_cooldown = match config.bid_cooldown {
+        Some(bid_cooldown) => bid_cooldown,
+        None => return Ok(()),
+    };
+    if let Some(until) = BID_COOLDOWNS.may_load(storage, bidder)? {
+        if !until.is_expired(block) {
+            return Err(ContractError::BidCooldown { until });
+        }
+    }
+    BID_COOLDOWNS.save(storage, bidder, &bid_cooldown.after(block))?;
+    Ok(())
+}
+
+fn ensure_bid_limit(
+    storage: &dyn Storage,
+    config: &Config,
+    bidder: &Addr,
+) -> Result<(), ContractError> {
+    if let Some(limit) = config.max_bids_per_bidder {
+        if BID_COUNTS.may_load(storage, bidder)?.unwrap_or_default() >= limit {
+            return Err(ContractError::BidLimitReached { limit });
+        }
+    }
+    Ok(())
+}
+
+fn ensure_bid_bond(
+    storage: &dyn Storage,
+    config: &Config,
+    bidder: &Addr,
+) -> Result<(), ContractError> {
+    if let Some(amount) = config.bid_bond {
+        if !BID_BONDS.has(storage, bidder) {
+            return Err(ContractError::BidBondRequired { amount });
+        }
+    }
+    Ok(())
+}
+
+fn record_bid(storage: &mut dyn Storage, bidder: &Addr) -> StdResult<()> {
+    BID_COUNTS.update(storage, bidder, |count| -> StdResult<_> {
+        Ok(count.unwrap_or_default() + 1)
+    })?;
+    #[cfg(feature = "stats")]
+    stats::record_bid(storage, bidder)?;
+    Ok(())
+}
+
+pub fn receive_commit(
+    deps: DepsMut,
+    block: &BlockInfo,
+    config: Config,
+    amount: Uint128,
+    bidder: Addr,
+    commitment: Binary,
+) -> Result<Response, ContractError> {
+    let deposit = match config.auction_type {
+        AuctionType::Sealed { deposit, .. } => deposit,
+        _ => return Err(ContractError::NotSealedAuction {}),
+    };
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Active])?;
+    if !config.allow_seller_bids && bidder == config.seller {
+        return Err(ContractError::SellerCannotBid {});
+    }
+    if COMMITMENTS.has(deps.storage, &bidder) {
+        return Err(ContractError::AlreadyCommitted {});
+    }
+    ensure_bundle_deposited(deps.storage, &config)?;
+    ensure_bid_bond(deps.storage, &config, &bidder)?;
+    if amount < deposit {
+        return Err(ContractError::InsufficientPayment {
+            amount,
+            required: deposit,
+        });
+    }
+    // The whole amount is held, so bidders can choose to back their commitment with more.
+    let commitment = Commitment {
+        commitment,
+        deposit: amount,
+    };
+    COMMITMENTS.save(deps.storage, &bidder, &commitment)?;
+
+    Ok(Response::new()
+        .add_attribute("action", prefixed(&config, "receive_commit"))
+        .add_attribute("bidder", bidder)
+        .add_attribute("deposit", amount))
+}
+
+// A revealed bid leads if it is the highest at or above the reserve so far, and ties go to the
+// earlier reveal. The leading bid's deposit is held until the winner pays and returned once it is
+// outbid; any other revealed bid gets its deposit back straight away.
+pub fn execute_reveal(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    config: Config,
+    price: Uint128,
+    salt: String,
+) -> Result<Response, ContractError> {
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Revealing])?;
+    let commitment = COMMITMENTS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or_else(|| ContractError::NoCommitment {
+            bidder: info.sender.to_string(),
+        })?;
+    if sealed_commitment(price, &salt) != commitment.commitment {
+        return Err(ContractError::InvalidReveal {});
+    }
+    COMMITMENTS.remove(deps.storage, &info.sender);
+
+    let best_bid = BEST_BID.may_load(deps.storage)?;
+    let leading = price >= config.reserve_price
+        && match &best_bid {
+            Some(best_bid) => price > best_bid.bid_record.price,
+            None => true,
+        };
+    // The deposit refunded is the outbid leader's, or this bid's own if it does not lead.
+    let (refunded, refund, reason) = if leading {
+        let outbid = match best_bid {
+            Some(best_bid) => {
+                RUNNER_UP.save(deps.storage, &best_bid)?;
+                let held = LEADING_DEPOSIT.may_load(deps.storage)?.unwrap_or_default();
+                (best_bid.bid_record.buyer, held, "outbid")
+            }
+            None => (info.sender.clone(), Uint128::zero(), "outbid"),
+        };
+        save_best_bid(deps.storage, info.sender.clone(), price, config.quantity)?;
+        record_bid(deps.storage, &info.sender)?;
+        LEADING_DEPOSIT.save(deps.storage, &commitment.deposit)?;
+        outbid
+    } else {
+        (info.sender.clone(), commitment.deposit, "lost")
+    };
+
+    let mut res = Response::new();
+    if !refund.is_zero() {
+        let cw20 = Cw20Contract(config.token_addr.clone());
+        res = res
+            .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: refunded.to_string(),
+                amount: refund,
+            })?)
+            .add_event(refund_event(&config, &refunded, refund, reason));
+    }
+
+    Ok(res
+        .add_attribute("action", prefixed(&config, "execute_reveal"))
+        .add_attribute("bidder", info.sender)
+        .add_attribute("price", price)
+        .add_attribute("leading", leading.to_string()))
+}
+
+// Once the reveal phase is over, anyone can send the deposits of unrevealed bids to the seller.
+// A cancelled auction has already refunded them.
+pub fn execute_slash_unrevealed(
+    deps: DepsMut,
+    block: &BlockInfo,
+    config: Config,
+) -> Result<Response, ContractError> {
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(
+        status,
+        &[
+            AuctionStatus::Ended,
+            AuctionStatus::Settled,
+            AuctionStatus::Failed,
+        ],
+    )?;
+    let unrevealed = COMMITMENTS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut slashed = Uint128::zero();
+    for (bidder, commitment) in unrevealed {
+        COMMITMENTS.remove(deps.storage, &bidder);
+        slashed = slashed.checked_add(commitment.deposit)?;
+    }
+    if slashed.is_zero() {
+        return Err(ContractError::NothingToSlash {});
+    }
+
+    let cw20 = Cw20Contract(config.token_addr.clone());
+    let msg = cw20.call(Cw20ExecuteMsg::Transfer {
+        recipient: config.seller.to_string(),
+        amount: slashed,
+    })?;
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("action", prefixed(&config, "execute_slash_unrevealed"))
+        .add_attribute("slashed", slashed))
+}
+
+// Pays out the deposit held for the leading sealed bid: back to the winner once they pay, or to
+// the seller when they default.
+fn release_leading_deposit(
+    storage: &mut dyn Storage,
+    config: &Config,
+    recipient: &Addr,
+) -> Result<Option<(CosmosMsg, Uint128)>, ContractError> {
+    let deposit = match LEADING_DEPOSIT.may_load(storage)? {
+        Some(deposit) => deposit,
+        None => return Ok(None),
+    };
+    LEADING_DEPOSIT.remove(storage);
+    let msg = Cw20Contract(config.token_addr.clone()).call(Cw20ExecuteMsg::Transfer {
+        recipient: recipient.to_string(),
+        amount: deposit,
+    })?;
+    Ok(Some((msg, deposit)))
+}
+
+// Returns every sealed-bid deposit still held, revealed or not, once the auction is called off.
+fn refund_sealed_deposits(
+    storage: &mut dyn Storage,
+    config: &Config,
+    mut res: Response,
+) -> Result<Response, ContractError> {
+    let mut refunds = COMMITMENTS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(bidder, commitment)| (bidder, commitment.deposit)))
+        .collect::<StdResult<Vec<_>>>()?;
+    if let Some(deposit) = LEADING_DEPOSIT.may_load(storage)? {
+        LEADING_DEPOSIT.remove(storage);
+        refunds.push((BEST_BID.load(storage)?.bid_record.buyer, deposit));
+    }
+    let cw20 = Cw20Contract(config.token_addr.clone());
+    for (bidder, deposit) in refunds {
+        COMMITMENTS.remove(storage, &bidder);
+        res = res
+            .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: bidder.to_string(),
+                amount: deposit,
+            })?)
+            .add_event(refund_event(config, &bidder, deposit, "cancelled"));
+    }
+    Ok(res)
+}
+
+pub fn execute_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    config: Config,
+    wrapped_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // The seller deposits a cw20 lot through the lot token itself.
+    if config.lot_token.as_ref() == Some(&info.sender) {
+        let sender = deps.api.addr_validate(&wrapped_msg.sender)?;
+        return match from_binary(&wrapped_msg.msg)? {
+            ReceiveMsg::DepositLot {} => {
+                receive_deposit_lot(deps, &env.block, config, wrapped_msg.amount, sender)
+            }
+            _ => Err(ContractError::InvalidToken {
+                token: info.sender.into_string(),
+            }),
+        };
+    }
+    // Bundled cw20 amounts are deposited the same way, through their own token.
+    let bundled = config
+        .bundle
+        .iter()
+        .any(|item| matches!(item, BundleItem::Cw20 { address, .. } if *address == info.sender));
+    if bundled {
+        let sender = deps.api.addr_validate(&wrapped_msg.sender)?;
+        let amount = wrapped_msg.amount;
+        return match from_binary(&wrapped_msg.msg)? {
+            ReceiveMsg::DepositLot {} => {
+                deposit_bundle_item(deps.storage, &env.block, &config, sender, |item| {
+                    *item
+                        == BundleItem::Cw20 {
+                            address: info.sender.clone(),
+                            amount,
+                        }
+                })
+            }
+            _ => Err(ContractError::InvalidToken {
+                token: info.sender.into_string(),
+            }),
+        };
+    }
+    // Only the payment token may call Receive, which guarantees the tokens have been sent.
+    if info.sender != config.token_addr {
+        return Err(ContractError::InvalidToken {
+            token: info.sender.into_string(),
+        });
+    }
+
+    let sender = deps.api.addr_validate(&wrapped_msg.sender)?;
+    let msg: ReceiveMsg = from_binary(&wrapped_msg.msg)?;
+    match msg {
+        ReceiveMsg::Buy => receive_buy(deps, env, config, wrapped_msg.amount, sender),
+        ReceiveMsg::Bid { price, quantity } => receive_bid(
+            deps,
+            &env.block,
+            config,
+            wrapped_msg.amount,
+            sender,
+            price,
+            None,
+            quantity,
+        ),
+        ReceiveMsg::ProxyBid {
+            max_price,
+            quantity,
+        } => receive_bid(
+            deps,
+            &env.block,
+            config,
+            wrapped_msg.amount,
+            sender,
+            max_price,
+            Some(max_price),
+            quantity,
+        ),
+        ReceiveMsg::Commit { commitment } => receive_commit(
+            deps,
+            &env.block,
+            config,
+            wrapped_msg.amount,
+            sender,
+            commitment,
+        ),
+        ReceiveMsg::DepositLot {} => Err(ContractError::InvalidToken {
+            token: info.sender.into_string(),
+        }),
+        ReceiveMsg::BuyTickets { count } => {
+            receive_buy_tickets(deps, &env.block, config, wrapped_msg.amount, sender, count)
+        }
+        ReceiveMsg::TopUp { new_price } => receive_top_up(
+            deps,
+            &env.block,
+            config,
+            wrapped_msg.amount,
+            sender,
+            new_price,
+        ),
+        ReceiveMsg::BuyFixedPrice {} => {
+            receive_fixed_price_buy(deps, env, config, wrapped_msg.amount, sender)
+        }
+        ReceiveMsg::ContributeToPool { pool_id } => receive_contribute_to_pool(
+            deps,
+            &env.block,
+            config,
+            wrapped_msg.amount,
+            sender,
+            pool_id,
+        ),
+        ReceiveMsg::MakeOffer { price } => {
+            receive_make_offer(deps, &env.block, config, wrapped_msg.amount, sender, price)
+        }
+        ReceiveMsg::PostDeposit {} => {
+            receive_post_deposit(deps, &env.block, config, wrapped_msg.amount, sender)
+        }
+        ReceiveMsg::PennyBid {} => {
+            receive_penny_bid(deps, &env.block, config, wrapped_msg.amount, sender)
+        }
+        ReceiveMsg::PayPeriod {} => {
+            receive_pay_period(deps, &env.block, config, wrapped_msg.amount, sender)
+        }
+    }
+}
+
+pub fn receive_buy_tickets(
+    deps: DepsMut,
+    block: &BlockInfo,
+    config: Config,
+    amount: Uint128,
+    buyer: Addr,
+    count: Uint64,
+) -> Result<Response, ContractError> {
+    let ticket_price = match config.auction_type {
+        AuctionType::Raffle { ticket_price, .. } => ticket_price,
+        _ => return Err(ContractError::UnsupportedByAuctionType {}),
+    };
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Active])?;
+    if !config.allow_seller_bids && buyer == config.seller {
+        return Err(ContractError::SellerCannotBid {});
+    }
+    if count.is_zero() {
+        return Err(ContractError::ZeroQuantity {});
+    }
+    ensure_bundle_deposited(deps.storage, &config)?;
+    let total = ticket_price.checked_mul(count.into())?;
+    ensure_payment(&config, amount, total)?;
+
+    let first_ticket = TICKET_SEQ.may_load(deps.storage)?.unwrap_or_default();
+    TICKET_SEQ.save(deps.storage, &(first_ticket + count.u64()))?;
+    TICKET_PURCHASES.save(deps.storage, first_ticket, &buyer)?;
+    mix_entry(deps.storage, &format!("{}:{}:{}", buyer, first_ticket, count))?;
+    TICKETS.update(deps.storage, &buyer, |tickets| -> StdResult<_> {
+        Ok(tickets.unwrap_or_default() + count.u64())
+    })?;
+    record_bid(deps.storage, &buyer)?;
+
+    // Anything sent above the tickets' price goes back to the buyer.
+    let refund = amount - total;
+    let mut res = Response::new();
+    if !refund.is_zero() {
+        let cw20 = Cw20Contract(config.token_addr.clone());
+        res = res
+            .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: buyer.to_string(),
+                amount: refund,
+            })?)
+            .add_event(refund_event(&config, &buyer, refund, "overpaid"));
+    }
+
+    Ok(res
+        .add_attribute("action", prefixed(&config, "receive_buy_tickets"))
+        .add_attribute("buyer", buyer)
+        .add_attribute("first_ticket", first_ticket.to_string())
+        .add_attribute("count", count))
+}
+
+// Draws the winning ticket at close. Its owner takes the lot as the best bid, and the seller is
+// paid for every ticket, or only the winner's when losers are refunded.
+fn draw_raffle(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &Config,
+    ticket_price: Uint128,
+    refund_losers: bool,
+) -> Result<Response, ContractError> {
+    // Without a revealed seed, as when governance closes the raffle early, there is nothing to
+    // draw with and every ticket is refunded.
+    let ticket_count = TICKET_SEQ.may_load(storage)?.unwrap_or_default();
+    let drawn = match drawn_value(storage)? {
+        Some(drawn) if ticket_count > 0 => drawn,
+        _ => {
+            STATUS.save(storage, &AuctionStatus::Cancelled)?;
+            let msgs = lot_msgs(storage, env, config, &config.seller)?;
+            return Ok(Response::new().add_messages(msgs));
+        }
+    };
+
+    let winning_ticket = drawn % ticket_count;
+    let (_, winner) = TICKET_PURCHASES
+        .range(
+            storage,
+            None,
+            Some(Bound::inclusive(winning_ticket)),
+            Order::Descending,
+        )
+        .next()
+        .transpose()?
+        .ok_or(ContractError::NoWinningBid {})?;
+    let winner_tickets = TICKETS.load(storage, &winner)?;
+    // The winner's tickets pay for the lot and are never refunded.
+    TICKETS.remove(storage, &winner);
+    save_best_bid(storage, winner.clone(), ticket_price, winner_tickets.into())?;
+    let mut best_bid = BEST_BID.load(storage)?;
+
+    let sold_tickets = if refund_losers {
+        winner_tickets
+    } else {
+        ticket_count
+    };
+    let proceeds = ticket_price.checked_mul(sold_tickets.into())?;
+    let held = settle_sale(storage, env, config, &mut best_bid, proceeds)?;
+    let mut res = Response::new().add_event(
+        Event::new(prefixed(config, "raffle_drawn"))
+            .add_attribute("winning_ticket", winning_ticket.to_string())
+            .add_attribute("winner", winner.clone()),
+    );
+    if !held {
+        let cw20 = Cw20Contract(config.token_addr.clone());
+        res = res.add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+            recipient: config.seller.to_string(),
+            amount: proceeds,
+        })?);
+    }
+    Ok(res.add_messages(lot_msgs(storage, env, config, &winner)?))
+}
+
+// Losing tickets are refunded when the raffle says so, and every ticket is when it is cancelled.
+pub fn execute_claim_ticket_refund(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    config: Config,
+) -> Result<Response, ContractError> {
+    let (ticket_price, refund_losers) = match config.auction_type {
+        AuctionType::Raffle {
+            ticket_price,
+            refund_losers,
+        } => (ticket_price, refund_losers),
+        _ => return Err(ContractError::UnsupportedByAuctionType {}),
+    };
+    let status = auction_status(deps.storage, &config, block)?;
+    let reason = match status {
+        AuctionStatus::Cancelled => "cancelled",
+        AuctionStatus::Failed => "failed",
+        _ => "lost",
+    };
+    if refund_losers {
+        ensure_status(
+            status,
+            &[
+                AuctionStatus::Settled,
+                AuctionStatus::Cancelled,
+                AuctionStatus::Failed,
+            ],
+        )?;
+    } else {
+        ensure_status(status, &[AuctionStatus::Cancelled, AuctionStatus::Failed])?;
+    }
+    let tickets = TICKETS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::NoTicketsToRefund {})?;
+    TICKETS.remove(deps.storage, &info.sender);
+
+    let refund = ticket_price.checked_mul(tickets.into())?;
+    let cw20 = Cw20Contract(config.token_addr.clone());
+    let msg = cw20.call(Cw20ExecuteMsg::Transfer {
+        recipient: info.sender.to_string(),
+        amount: refund,
+    })?;
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_event(refund_event(&config, &info.sender, refund, reason))
+        .add_attribute("action", prefixed(&config, "execute_claim_ticket_refund"))
+        .add_attribute("buyer", info.sender)
+        .add_attribute("refund", refund))
+}
+
+pub fn receive_deposit_lot(
+    deps: DepsMut,
+    block: &BlockInfo,
+    config: Config,
+    amount: Uint128,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    if sender != config.seller {
+        return Err(ContractError::Unauthorized {});
+    }
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Created, AuctionStatus::Active])?;
+    if LOT_DEPOSITED.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::LotAlreadyDeposited {});
+    }
+    if amount != config.quantity {
+        return Err(ContractError::InvalidLotDeposit {
+            amount,
+            quantity: config.quantity,
+        });
+    }
+    LOT_DEPOSITED.save(deps.storage, &amount)?;
+
+    Ok(Response::new()
+        .add_attribute("action", prefixed(&config, "receive_deposit_lot"))
+        .add_attribute("amount", amount))
+}
+
+// Bundled NFTs are deposited by sending them to the contract with `DepositLot`.
+pub fn execute_receive_nft(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    config: Config,
+    wrapped_msg: Cw721ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let sender = deps.api.addr_validate(&wrapped_msg.sender)?;
+    match from_binary(&wrapped_msg.msg)? {
+        ReceiveMsg::DepositLot {} => {
+            let nft = BundleItem::Cw721 {
+                address: info.sender,
+                token_id: wrapped_msg.token_id,
+            };
+            deposit_bundle_item(deps.storage, block, &config, sender, |item| *item == nft)
+        }
+        _ => Err(ContractError::InvalidBundleDeposit {}),
+    }
+}
+
+// Bundled cw1155 amounts are deposited by sending them to the contract with `DepositLot`.
+pub fn execute_receive_cw1155(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    config: Config,
+    wrapped_msg: Cw1155ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let sender = deps
+        .api
+        .addr_validate(wrapped_msg.from.as_ref().unwrap_or(&wrapped_msg.operator))?;
+    match from_binary(&wrapped_msg.msg)? {
+        ReceiveMsg::DepositLot {} => {
+            let tokens = BundleItem::Cw1155 {
+                address: info.sender,
+                token_id: wrapped_msg.token_id,
+                amount: wrapped_msg.amount,
+            };
+            deposit_bundle_item(deps.storage, block, &config, sender, |item| *item == tokens)
+        }
+        _ => Err(ContractError::InvalidBundleDeposit {}),
+    }
+}
+
+// Marks the first undeposited bundle item that `matches` the deposit as held.
+fn deposit_bundle_item(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    config: &Config,
+    sender: Addr,
+    matches: impl Fn(&BundleItem) -> bool,
+) -> Result<Response, ContractError> {
+    if sender != config.seller {
+        return Err(ContractError::Unauthorized {});
+    }
+    let status = auction_status(storage, config, block)?;
+    ensure_status(status, &[AuctionStatus::Created, AuctionStatus::Active])?;
+    let index = (0..config.bundle.len() as u32)
+        .find(|index| {
+            matches(&config.bundle[*index as usize]) && !BUNDLE_DEPOSITED.has(storage, *index)
+        })
+        .ok_or(ContractError::InvalidBundleDeposit {})?;
+    BUNDLE_DEPOSITED.save(storage, index, &true)?;
+
+    Ok(Response::new()
+        .add_attribute("action", prefixed(config, "deposit_bundle_item"))
+        .add_attribute("index", index.to_string()))
+}
+
+fn ensure_bundle_deposited(storage: &dyn Storage, config: &Config) -> Result<(), ContractError> {
+    let deposited = BUNDLE_DEPOSITED
+        .keys(storage, None, None, Order::Ascending)
+        .count();
+    if deposited < config.bundle.len() {
+        return Err(ContractError::LotNotDeposited {});
+    }
+    Ok(())
+}
+
+// Batch bids are all kept, each with its own escrow, and only ranked at close.
+fn receive_batch_bid(
+    deps: DepsMut,
+    block: &BlockInfo,
+    config: Config,
+    amount: Uint128,
+    bidder: Addr,
+    price: Uint128,
+    quantity: Uint128,
+) -> Result<Response, ContractError> {
+    if LOT_DEPOSITED.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::LotNotDeposited {});
+    }
+    if !config.allow_seller_bids && bidder == config.seller {
+        return Err(ContractError::SellerCannotBid {});
+    }
+    validate_quantity(&config, quantity)?;
+    let reserve_price = validate_reserve(&config, block.height, price)?;
+    validate_step(&config, reserve_price, price)?;
+    let total = price.checked_mul(quantity)?;
+    ensure_payment(&config, amount, total)?;
+
+    let id = BID_SEQ.load(deps.storage)? + 1;
+    if id > MAX_BATCH_BIDS {
+        return Err(ContractError::BatchFull {
+            max: MAX_BATCH_BIDS,
+        });
+    }
+    BID_SEQ.save(deps.storage, &id)?;
+    let bid_record = BidRecord {
+        buyer: bidder.clone(),
+        price,
+        quantity,
+    };
+    BID_RECORDS.save(deps.storage, id, &bid_record)?;
+    BATCH_ESCROW.save(deps.storage, (&bidder, id), &amount)?;
+    record_bid(deps.storage, &bidder)?;
+
+    Ok(Response::new()
+        .add_attribute("action", prefixed(&config, "receive_batch_bid"))
+        .add_attribute("id", id.to_string())
+        .add_attribute("buyer", bidder)
+        .add_attribute("price", price)
+        .add_attribute("quantity", quantity)
+        .add_attribute("escrowed", amount))
+}
+
+// Computes the clearing price over every bid and pays the seller for what sold, along with any
+// unsold part of the lot. Bidders claim their fills and refunds afterwards.
+fn clear_batch(storage: &mut dyn Storage, config: &Config) -> Result<Response, ContractError> {
+    let lot_token = match &config.lot_token {
+        Some(lot_token) => Cw20Contract(lot_token.clone()),
+        None => return Err(ContractError::InvalidBatchAuction {}),
+    };
+    let bids = BID_RECORDS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, bid)| bid))
+        .collect::<StdResult<Vec<_>>>()?;
+    if bids.is_empty() {
+        STATUS.save(storage, &AuctionStatus::Cancelled)?;
+        let res = Response::new().add_messages(lot_token_refund(storage, config)?);
+        return Ok(res.add_attribute("sold", Uint128::zero()));
+    }
+
+    let clearing = compute_clearing(&bids, config.quantity, config.reserve_price);
+    let mut sold = Uint128::zero();
+    for bid in &bids {
+        sold = sold.checked_add(fill_for(&clearing, bid))?;
+    }
+    let proceeds = clearing.price.checked_mul(sold)?;
+    BATCH_CLEARING.save(storage, &clearing)?;
+    STATUS.save(storage, &AuctionStatus::Settled)?;
+
+    let mut res = Response::new().add_event(
+        Event::new(prefixed(config, "batch_cleared"))
+            .add_attribute("price", clearing.price)
+            .add_attribute("sold", sold),
+    );
+    if !proceeds.is_zero() {
+        let cw20 = Cw20Contract(config.token_addr.clone());
+        res = res.add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+            recipient: config.seller.to_string(),
+            amount: proceeds,
+        })?);
+    }
+    let unsold = config.quantity.checked_sub(sold)?;
+    if !unsold.is_zero() {
+        res = res.add_message(lot_token.call(Cw20ExecuteMsg::Transfer {
+            recipient: config.seller.to_string(),
+            amount: unsold,
+        })?);
+    }
+    Ok(res.add_attribute("sold", sold))
+}
+
+// Returns the deposited cw20 lot to the seller, at most once.
+fn lot_token_refund(
+    storage: &mut dyn Storage,
+    config: &Config,
+) -> Result<Option<CosmosMsg>, ContractError> {
+    let (lot_token, deposited) = match (&config.lot_token, LOT_DEPOSITED.may_load(storage)?) {
+        (Some(lot_token), Some(deposited)) => (lot_token, deposited),
+        _ => return Ok(None),
+    };
+    LOT_DEPOSITED.remove(storage);
+    let msg = Cw20Contract(lot_token.clone()).call(Cw20ExecuteMsg::Transfer {
+        recipient: config.seller.to_string(),
+        amount: deposited,
+    })?;
+    Ok(Some(msg))
+}
+
+// Each batch bidder collects the lot they were filled with and the rest of their escrow. In a
+// cancelled auction every escrow is refunded in full.
+pub fn execute_claim_batch_fill(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    config: Config,
+) -> Result<Response, ContractError> {
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Settled, AuctionStatus::Cancelled])?;
+    let escrows = BATCH_ESCROW
+        .prefix(&info.sender)
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    if escrows.is_empty() {
+        return Err(ContractError::NoBidOrOffer {
+            buyer: info.sender.into_string(),
+        });
+    }
+    let clearing = BATCH_CLEARING.may_load(deps.storage)?;
+
+    let mut filled = Uint128::zero();
+    let mut refund = Uint128::zero();
+    for (id, escrowed) in escrows {
+        BATCH_ESCROW.remove(deps.storage, (&info.sender, id));
+        let (fill, cost) = match &clearing {
+            Some(clearing) => {
+                let fill = fill_for(clearing, &BID_RECORDS.load(deps.storage, id)?);
+                (fill, clearing.price.checked_mul(fill)?)
+            }
+            None => (Uint128::zero(), Uint128::zero()),
+        };
+        filled = filled.checked_add(fill)?;
+        refund = refund.checked_add(escrowed.checked_sub(cost)?)?;
+    }
+
+    let mut res = Response::new();
+    if let (Some(lot_token), false) = (&config.lot_token, filled.is_zero()) {
+        res = res.add_message(
+            Cw20Contract(lot_token.clone()).call(Cw20ExecuteMsg::Transfer {
+                recipient: info.sender.to_string(),
+                amount: filled,
+            })?,
+        );
+    }
+    if !refund.is_zero() {
+        let cw20 = Cw20Contract(config.token_addr.clone());
+        let reason = match status {
+            AuctionStatus::Cancelled => "cancelled",
+            _ => "unfilled",
+        };
+        res = res
+            .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: info.sender.to_string(),
+                amount: refund,
+            })?)
+            .add_event(refund_event(&config, &info.sender, refund, reason));
+    }
+
+    Ok(res
+        .add_attribute("action", prefixed(&config, "execute_claim_batch_fill"))
+        .add_attribute("buyer", info.sender)
+        .add_attribute("filled", filled)
+        .add_attribute("refund", refund))
+}
+
+pub fn receive_buy(
+    deps: DepsMut,
+    env: Env,
+    config: Config,
+    amount: Uint128,
+    buyer: Addr,
+) -> Result<Response, ContractError> {
+    if let AuctionType::Dutch { .. } = config.auction_type {
+        return receive_dutch_buy(deps, env, config, amount, buyer);
+    }
+    if !has_single_buyer(&config.auction_type) {
+        return Err(ContractError::NotEnglishAuction {});
+    }
+    let status = auction_status(deps.storage, &config, &env.block)?;
+    if let (AuctionStatus::Active, Some(buy_now_price)) = (status, config.buy_now_price) {
+        return receive_buy_now(deps, env, config, buy_now_price, amount, buyer);
+    }
+    ensure_status(status, &[AuctionStatus::Ended])?;
+    ensure_reserve_revealed(deps.storage, &config)?;
+    let best_bid = BEST_BID.load(deps.storage)?;
+    if buyer != best_bid.bid_record.buyer {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_not_escrowed(deps.storage)?;
+    if let Some(deadline) = settlement_deadline(deps.storage, &config)? {
+        if env.block.height >= deadline {
+            return Err(ContractError::SettlementWindowExpired { deadline });
+        }
+    }
+    if let Some(installment_plan) = config.installment_plan.clone() {
+        return receive_installment(deps, env, config, installment_plan, best_bid, amount, buyer);
+    }
+    let total = sale_total(deps.storage, &config, &best_bid)?;
+    settle_purchase(deps, env, config, best_bid, amount, total, "receive_buy")
+}
+
+// Paying the buy-now price wins the whole lot outright and ends the bidding. In escrow mode the
+// current best bidder is refunded.
+fn receive_buy_now(
+    deps: DepsMut,
+    env: Env,
+    config: Config,
+    buy_now_price: Uint128,
+    amount: Uint128,
+    buyer: Addr,
+) -> Result<Response, ContractError> {
+    ensure_bundle_deposited(deps.storage, &config)?;
+    if !config.allow_seller_bids && buyer == config.seller {
+        return Err(ContractError::SellerCannotBid {});
+    }
+    let total = buy_now_price.checked_mul(config.quantity)?;
+    ensure_payment(&config, amount, total)?;
+    let (refund_msg, outbid_event) = match ESCROWED.may_load(deps.storage)? {
+        Some(escrowed) => {
+            ESCROWED.remove(deps.storage);
+            PROXY_MAX.remove(deps.storage);
+            let outbid = BEST_BID.load(deps.storage)?.bid_record.buyer;
+            let cw20 = Cw20Contract(config.token_addr.clone());
+            let refund_msg = cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: outbid.to_string(),
+                amount: escrowed,
+            })?;
+            let event = refund_event(&config, &outbid, escrowed, "outbid");
+            (Some(refund_msg), Some(event))
+        }
+        None => (None, None),
+    };
+    save_best_bid(deps.storage, buyer.clone(), buy_now_price, config.quantity)?;
+    record_bid(deps.storage, &buyer)?;
+
+    let buy_now_event = Event::new(prefixed(&config, "buy_now"))
+        .add_attribute("buyer", buyer)
+        .add_attribute("price", buy_now_price);
+    let best_bid = BEST_BID.load(deps.storage)?;
+    let res = settle_purchase(
+        deps,
+        env,
+        config,
+        best_bid,
+        amount,
+        total,
+        "receive_buy_now",
+    )?;
+    Ok(res
+        .add_messages(refund_msg)
+        .add_events(outbid_event)
+        .add_event(buy_now_event))
+}
+
+// The first buyer to pay the current price takes the whole lot, which settles right away.
+fn receive_dutch_buy(
+    deps: DepsMut,
+    env: Env,
+    config: Config,
+    amount: Uint128,
+    buyer: Addr,
+) -> Result<Response, ContractError> {
+    ensure_bundle_deposited(deps.storage, &config)?;
+    let status = auction_status(deps.storage, &config, &env.block)?;
+    ensure_status(status, &[AuctionStatus::Active])?;
+    if !config.allow_seller_bids && buyer == config.seller {
+        return Err(ContractError::SellerCannotBid {});
+    }
+    let price = dutch_price_at(&config, env.block.height).unwrap_or_default();
+    save_best_bid(deps.storage, buyer.clone(), price, config.quantity)?;
+    record_bid(deps.storage, &buyer)?;
+
+    let best_bid = BEST_BID.load(deps.storage)?;
+    let total = price.checked_mul(config.quantity)?;
+    settle_purchase(
+        deps,
+        env,
+        config,
+        best_bid,
+        amount,
+        total,
+        "receive_dutch_buy",
+    )
+}
+
+// The first buyer to pay the fixed price takes the whole lot of an unsold auction.
+fn receive_fixed_price_buy(
+    deps: DepsMut,
+    env: Env,
+    config: Config,
+    amount: Uint128,
+    buyer: Addr,
+) -> Result<Response, ContractError> {
+    ensure_bundle_deposited(deps.storage, &config)?;
+    let status = auction_status(deps.storage, &config, &env.block)?;
+    ensure_status(status, &[AuctionStatus::Ended])?;
+    let price = FIXED_PRICE
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NotFixedPrice {})?;
+    // An accepted offer or counteroffer takes the lot off sale.
+    if BEST_BID.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::HasWinningBid {});
+    }
+    if !config.allow_seller_bids && buyer == config.seller {
+        return Err(ContractError::SellerCannotBid {});
+    }
+    save_best_bid(deps.storage, buyer.clone(), price, config.quantity)?;
+    record_bid(deps.storage, &buyer)?;
+
+    let best_bid = BEST_BID.load(deps.storage)?;
+    let total = price.checked_mul(config.quantity)?;
+    settle_purchase(
+        deps,
+        env,
+        config,
+        best_bid,
+        amount,
+        total,
+        "receive_fixed_price_buy",
+    )
+}
+
+fn settle_purchase(
+    deps: DepsMut,
+    env: Env,
+    config: Config,
+    mut best_bid: BestBid,
+    amount: Uint128,
+    total: Uint128,
+    action: &str,
+) -> Result<Response, ContractError> {
+    let buyer = best_bid.bid_record.buyer.clone();
+    ensure_payment(&config, amount, total)?;
+
+    let closed_event = close_auction(deps.storage, &config)?;
+
+    // The payment is already held by the contract; the price is forwarded unless the payout is
+    // held, and anything sent above it goes back to the buyer.
+    let refund = amount - total;
+    let held = settle_sale(deps.storage, &env, &config, &mut best_bid, total)?;
+    let cw20 = Cw20Contract(config.token_addr.clone());
+    let mut res = Response::new();
+    if !held {
+        res = res.add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+            recipient: config.seller.clone().into_string(),
+            amount: total,
+        })?);
+    }
+    if !refund.is_zero() {
+        res = res
+            .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: buyer.to_string(),
+                amount: refund,
+            })?)
+            .add_event(refund_event(&config, &buyer, refund, "overpaid"));
+    }
+    if let Some((msg, deposit)) = release_leading_deposit(deps.storage, &config, &buyer)? {
+        res = res
+            .add_message(msg)
+            .add_event(refund_event(&config, &buyer, deposit, "settled"));
+    }
+
+    Ok(res
+        .add_messages(winner_lot_msgs(deps.storage, &env, &config, &buyer)?)
+        .add_events(closed_event)
+        .add_attribute("action", prefixed(&config, action))
+        .add_attribute("id", best_bid.id)
+        .add_attribute("buyer", buyer)
+        .add_attribute("amount", total)
+        .add_attribute("refund", refund))
+}
+
+// Each installment is collected by the contract; the lot and the proceeds are only released once
+// the final one is paid.
+fn receive_installment(
+    deps: DepsMut,
+    env: Env,
+    config: Config,
+    installment_plan: InstallmentPlan,
+    mut best_bid: BestBid,
+    amount: Uint128,
+    buyer: Addr,
+) -> Result<Response, ContractError> {
+    let mut installments = INSTALLMENTS.may_load(deps.storage)?.unwrap_or_default();
+    let due_at = installment_due_at(&config, &installment_plan, installments.paid_count)?;
+    if env.block.height >= due_at {
+        return Err(ContractError::InstallmentOverdue { due_at });
+    }
+    let total = sale_total(deps.storage, &config, &best_bid)?;
+    let amount_due = installment_amount(total, &installment_plan, installments.paid_count);
+    ensure_payment(&config, amount, amount_due)?;
+
+    let closed_event = close_auction(deps.storage, &config)?;
+
+    installments.paid = installments.paid.checked_add(amount)?;
+    installments.paid_count += 1;
+    INSTALLMENTS.save(deps.storage, &installments)?;
+
+    let mut res = Response::new().add_events(closed_event);
+    if installments.paid_count == payment_count(&installment_plan) {
+        let cw20 = Cw20Contract(config.token_addr.clone());
+        let paid = installments.paid;
+        let held = settle_sale(deps.storage, &env, &config, &mut best_bid, paid)?;
+        if !held {
+            res = res.add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: config.seller.clone().into_string(),
+                amount: paid,
+            })?);
+        }
+        if let Some((msg, deposit)) = release_leading_deposit(deps.storage, &config, &buyer)? {
+            res = res
+                .add_message(msg)
+                .add_event(refund_event(&config, &buyer, deposit, "settled"));
+        }
+        res = res.add_messages(winner_lot_msgs(deps.storage, &env, &config, &buyer)?);
+    }
+
+    Ok(res
+        .add_attribute("action", prefixed(&config, "receive_installment"))
+        .add_attribute("id", best_bid.id)
+        .add_attribute("buyer", buyer)
+        .add_attribute("installment", installments.paid_count.to_string())
+        .add_attribute("amount", amount))
+}
+
+// Overpayments are refunded (or credited to the plan) unless the config asks for the exact amount.
+fn ensure_payment(
+    config: &Config,
+    amount: Uint128,
+    required: Uint128,
+) -> Result<(), ContractError> {
+    if config.require_exact_payment && amount != required {
+        return Err(ContractError::IncorrectPaymentAmount { amount, required });
+    }
+    if amount < required {
+        return Err(ContractError::InsufficientPayment { amount, required });
+    }
+    Ok(())
+}
+
+pub fn execute_claim_payout(
+    deps: DepsMut,
+    env: &Env,
+    info: MessageInfo,
+    config: Config,
+    proposal_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    let block = &env.block;
+    // A seller multisig releases the proceeds through a passed proposal, which anyone can present.
+    // The proposal must itself claim the payout from this contract, so an unrelated passed
+    // proposal on the same multisig cannot release it.
+    if config.proposal_confirmation {
+        let proposal_id = proposal_id.ok_or(ContractError::ProposalIdRequired {})?;
+        let proposal = query_proposal(&deps.querier, &config.seller, proposal_id)?;
+        if proposal.status != Status::Passed && proposal.status != Status::Executed {
+            return Err(ContractError::ProposalNotPassed {
+                proposal_id,
+                status: format!("{:?}", proposal.status),
+            });
+        }
+        let claims_payout = proposal.msgs.iter().any(|msg| match msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, msg, .. }) => {
+                *contract_addr == env.contract.address
+                    && matches!(from_binary::<ExecuteMsg>(msg), Ok(ExecuteMsg::ClaimPayout { .. }))
+            }
+            _ => false,
+        });
+        if !claims_payout {
+            return Err(ContractError::ProposalNotForPayout { proposal_id });
+        }
+    } else if info.sender != config.seller {
+        return Err(ContractError::Unauthorized {});
+    }
+    if config.payout_vesting.is_some() {
+        return Err(ContractError::PayoutVests {});
+    }
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Settled])?;
+
+    let mut payout = PAYOUT.load(deps.storage)?;
+    if payout.claimed {
+        return Err(ContractError::PayoutAlreadyClaimed {});
+    }
+    if block.height < payout.claimable_at.u64() {
+        return Err(ContractError::PayoutNotClaimable {
+            claimable_at: payout.claimable_at,
+        });
+    }
+
+    payout.claimed = true;
+    PAYOUT.save(deps.storage, &payout)?;
+
+    let cw20 = Cw20Contract(config.token_addr.clone());
+    let msg = cw20.call(Cw20ExecuteMsg::Transfer {
+        recipient: config.seller.clone().into_string(),
+        amount: payout.amount,
+    })?;
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("action", prefixed(&config, "execute_claim_payout"))
+        .add_attribute("seller", config.seller)
+        .add_attribute("amount", payout.amount))
+}
+
+// Period `index` of a lease starts, and is due, `index` periods after settlement.
+fn lease_period_start(lease_terms: &LeaseTerms, lease: &Lease, index: u32) -> StdResult<u64> {
+    let offset = lease_terms
+        .period_in_blocks
+        .checked_mul(Uint64::from(index))?;
+    Ok(lease.start.checked_add(offset)?.u64())
+}
+
+// Period payments go straight to the seller, one at a time and before the period starts.
+pub fn receive_pay_period(
+    deps: DepsMut,
+    block: &BlockInfo,
+    config: Config,
+    amount: Uint128,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let lease_terms = config.lease.as_ref().ok_or(ContractError::NoLease {})?;
+    let mut lease = LEASE
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoLease {})?;
+    if sender != lease.lessee {
+        return Err(ContractError::Unauthorized {});
+    }
+    if lease.ended || lease.paid_periods >= lease_terms.periods {
+        return Err(ContractError::LeasePaid {});
+    }
+    let due_at = lease_period_start(lease_terms, &lease, lease.paid_periods)?;
+    if block.height >= due_at {
+        return Err(ContractError::LeasePaymentOverdue { due_at });
+    }
+    ensure_payment(&config, amount, lease_terms.period_payment)?;
+    lease.paid_periods += 1;
+    LEASE.save(deps.storage, &lease)?;
+
+    let cw20 = Cw20Contract(config.token_addr.clone());
+    let mut res = Response::new().add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+        recipient: config.seller.to_string(),
+        amount: lease_terms.period_payment,
+    })?);
+    let refund = amount - lease_terms.period_payment;
+    if !refund.is_zero() {
+        res = res
+            .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: sender.to_string(),
+                amount: refund,
+            })?)
+            .add_event(refund_event(&config, &sender, refund, "overpaid"));
+    }
+    Ok(res
+        .add_attribute("action", prefixed(&config, "receive_pay_period"))
+        .add_attribute("lessee", sender)
+        .add_attribute("paid_periods", lease.paid_periods.to_string())
+        .add_attribute("refund", refund))
+}
+
+// Anyone may return the lot to the seller after the last period, or once a payment is missed.
+pub fn execute_end_lease(
+    deps: DepsMut,
+    env: &Env,
+    config: Config,
+) -> Result<Response, ContractError> {
+    let lease_terms = config.lease.as_ref().ok_or(ContractError::NoLease {})?;
+    let mut lease = LEASE
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoLease {})?;
+    if lease.ended {
+        return Err(ContractError::NoLease {});
+    }
+    let ends_at = lease_period_start(lease_terms, &lease, lease_terms.periods)?;
+    let due_at = lease_period_start(lease_terms, &lease, lease.paid_periods)?;
+    let defaulted = lease.paid_periods < lease_terms.periods && env.block.height >= due_at;
+    if !defaulted && env.block.height < ends_at {
+        return Err(ContractError::LeaseActive { ends_at });
+    }
+    lease.ended = true;
+    LEASE.save(deps.storage, &lease)?;
+
+    Ok(Response::new()
+        .add_messages(lot_msgs(deps.storage, env, &config, &config.seller)?)
+        .add_attribute("action", prefixed(&config, "execute_end_lease"))
+        .add_attribute("lessee", lease.lessee)
+        .add_attribute("defaulted", defaulted.to_string()))
+}
+
+// Pays the seller whatever has vested since the last claim.
+pub fn execute_claim_vested(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    config: Config,
+) -> Result<Response, ContractError> {
+    if info.sender != config.seller {
+        return Err(ContractError::Unauthorized {});
+    }
+    let payout_vesting = match &config.payout_vesting {
+        Some(payout_vesting) => payout_vesting,
+        None => return Err(ContractError::NothingVested {}),
+    };
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Settled])?;
+
+    let mut vesting = VESTING.load(deps.storage)?;
+    let amount = vested_amount(payout_vesting, &vesting, block.height) - vesting.claimed;
+    if amount.is_zero() {
+        return Err(ContractError::NothingVested {});
+    }
+    vesting.claimed += amount;
+    VESTING.save(deps.storage, &vesting)?;
+
+    let cw20 = Cw20Contract(config.token_addr.clone());
+    Ok(Response::new()
+        .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+            recipient: config.seller.to_string(),
+            amount,
+        })?)
+        .add_attribute("action", prefixed(&config, "execute_claim_vested"))
+        .add_attribute("seller", config.seller)
+        .add_attribute("amount", amount)
+        .add_attribute("claimed", vesting.claimed))
+}
+
+pub fn execute_offer(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    config: Config,
+    price: Uint128,
+) -> Result<Response, ContractError> {
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Active])?;
+    if price.is_zero() {
+        return Err(ContractError::ZeroOfferPrice {});
+    }
+    let reserve_price = reserve_price_at(&config, block.height);
+    if price >= reserve_price {
+        return Err(ContractError::OfferNotBelowReserve {
+            price,
+            reserve: reserve_price,
+        });
+    }
+
+    OFFERS.save(deps.storage, &info.sender, &price)?;
+
+    Ok(Response::new()
+        .add_attribute("action", prefixed(&config, "execute_offer"))
+        .add_attribute("buyer", info.sender)
+        .add_attribute("price", price))
+}
+
+pub fn execute_accept_offer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    config: Config,
+    buyer: String,
+) -> Result<Response, ContractError> {
+    ensure_seller_or_manager(&config, &info.sender)?;
+    let buyer = deps.api.addr_validate(&buyer)?;
+    if let Some(price) = DIRECT_OFFERS.may_load(deps.storage, &buyer)? {
+        return accept_direct_offer(deps, env, config, buyer, price);
+    }
+    let status = auction_status(deps.storage, &config, &env.block)?;
+    ensure_status(status, &[AuctionStatus::Ended])?;
+
+    // Offers can only be accepted when no bid reached the reserve price, or every winner forfeited.
+    if BEST_BID.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::HasWinningBid {});
+    }
+
+    let price = OFFERS.load(deps.storage, &buyer)?;
+    let closed_event = close_auction(deps.storage, &config)?;
+    OFFERS.remove(deps.storage, &buyer);
+
+    let next_id = save_best_bid(deps.storage, buyer.clone(), price, config.quantity)?;
+
+    Ok(Response::new()
+        .add_events(closed_event)
+        .add_attribute("action", prefixed(&config, "execute_accept_offer"))
+        .add_attribute("id", next_id)
+        .add_attribute("buyer", buyer)
+        .add_attribute("price", price))
+}
+
+// A direct offer arrives with its payment and can be made at any price until the lot is sold.
+// Anything sent above the price, and an earlier offer it replaces, go back to the buyer.
+pub fn receive_make_offer(
+    deps: DepsMut,
+    block: &BlockInfo,
+    config: Config,
+    amount: Uint128,
+    buyer: Addr,
+    price: Uint128,
+) -> Result<Response, ContractError> {
+    if config.auction_type != AuctionType::English {
+        return Err(ContractError::NotEnglishAuction {});
+    }
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(
+        status,
+        &[
+            AuctionStatus::Created,
+            AuctionStatus::Active,
+            AuctionStatus::Ended,
+        ],
+    )?;
+    if !config.allow_seller_bids && buyer == config.seller {
+        return Err(ContractError::SellerCannotBid {});
+    }
+    if price.is_zero() {
+        return Err(ContractError::ZeroOfferPrice {});
+    }
+    let total = price.checked_mul(config.quantity)?;
+    ensure_payment(&config, amount, total)?;
+
+    let replaced = DIRECT_OFFERS
+        .may_load(deps.storage, &buyer)?
+        .map(|old_price| old_price.checked_mul(config.quantity))
+        .transpose()?
+        .unwrap_or_default();
+    DIRECT_OFFERS.save(deps.storage, &buyer, &price)?;
+
+    let refund = amount - total + replaced;
+    let mut res = Response::new();
+    if !refund.is_zero() {
+        let cw20 = Cw20Contract(config.token_addr.clone());
+        let reason = if replaced.is_zero() {
+            "overpaid"
+        } else {
+            "replaced"
+        };
+        res = res
+            .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: buyer.to_string(),
+                amount: refund,
+            })?)
+            .add_event(refund_event(&config, &buyer, refund, reason));
+    }
+
+    Ok(res
+        .add_attribute("action", prefixed(&config, "receive_make_offer"))
+        .add_attribute("buyer", buyer)
+        .add_attribute("price", price)
+        .add_attribute("refund", refund))
+}
+
+// Accepting a direct offer cancels bidding and settles the sale from the escrow right away. An
+// escrowed best bid it displaces is refunded.
+fn accept_direct_offer(
+    deps: DepsMut,
+    env: Env,
+    mut config: Config,
+    buyer: Addr,
+    price: Uint128,
+) -> Result<Response, ContractError> {
+    let status = auction_status(deps.storage, &config, &env.block)?;
+    ensure_status(
+        status,
+        &[
+            AuctionStatus::Created,
+            AuctionStatus::Active,
+            AuctionStatus::Ended,
+        ],
+    )?;
+    let cw20 = Cw20Contract(config.token_addr.clone());
+    let mut res = Response::new();
+    if status == AuctionStatus::Ended {
+        if BEST_BID.may_load(deps.storage)?.is_some() {
+            return Err(ContractError::HasWinningBid {});
+        }
+    } else {
+        if let Some(escrowed) = ESCROWED.may_load(deps.storage)? {
+            let outbid = BEST_BID.load(deps.storage)?.bid_record.buyer;
+            res = res
+                .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+                    recipient: outbid.to_string(),
+                    amount: escrowed,
+                })?)
+                .add_event(refund_event(&config, &outbid, escrowed, "outbid"));
+            ESCROWED.remove(deps.storage);
+            PROXY_MAX.remove(deps.storage);
+        }
+        RUNNER_UP.remove(deps.storage);
+        config.timeout = match config.timeout {
+            Expiration::AtTime(_) => Expiration::AtTime(env.block.time),
+            _ => Expiration::AtHeight(env.block.height),
+        };
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    DIRECT_OFFERS.remove(deps.storage, &buyer);
+    COUNTEROFFER.remove(deps.storage);
+    let closed_event = close_auction(deps.storage, &config)?;
+    let id = save_best_bid(deps.storage, buyer.clone(), price, config.quantity)?;
+    ACCEPTED_OFFER.save(deps.storage, &id.u64())?;
+    let mut best_bid = BEST_BID.load(deps.storage)?;
+    let total = price.checked_mul(config.quantity)?;
+    let held = settle_sale(deps.storage, &env, &config, &mut best_bid, total)?;
+    if !held {
+        res = res.add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+            recipient: config.seller.clone().into_string(),
+            amount: total,
+        })?);
+    }
+
+    Ok(res
+        .add_messages(winner_lot_msgs(deps.storage, &env, &config, &buyer)?)
+        .add_events(closed_event)
+        .add_attribute("action", prefixed(&config, "execute_accept_offer"))
+        .add_attribute("id", best_bid.id)
+        .add_attribute("buyer", buyer)
+        .add_attribute("price", price)
+        .add_attribute("amount", total))
+}
+
+// A direct offer that has not been accepted can be taken back at any time.
+pub fn execute_withdraw_offer(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: Config,
+) -> Result<Response, ContractError> {
+    let price = DIRECT_OFFERS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or_else(|| ContractError::NoBidOrOffer {
+            buyer: info.sender.to_string(),
+        })?;
+    DIRECT_OFFERS.remove(deps.storage, &info.sender);
+    let refund = price.checked_mul(config.quantity)?;
+
+    let cw20 = Cw20Contract(config.token_addr.clone());
+    Ok(Response::new()
+        .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+            recipient: info.sender.to_string(),
+            amount: refund,
+        })?)
+        .add_event(refund_event(&config, &info.sender, refund, "withdrawn"))
+        .add_attribute("action", prefixed(&config, "execute_withdraw_offer"))
+        .add_attribute("buyer", info.sender)
+        .add_attribute("refund", refund))
+}
+
+pub fn receive_post_deposit(
+    deps: DepsMut,
+    block: &BlockInfo,
+    config: Config,
+    amount: Uint128,
+    bidder: Addr,
+) -> Result<Response, ContractError> {
+    let bond = match config.bid_bond {
+        Some(bond) => bond,
+        None => return Err(ContractError::NoBidBond {}),
+    };
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Created, AuctionStatus::Active])?;
+    if BID_BONDS.has(deps.storage, &bidder) {
+        return Err(ContractError::BidBondPosted {});
+    }
+    ensure_payment(&config, amount, bond)?;
+    BID_BONDS.save(deps.storage, &bidder, &bond)?;
+
+    let mut res = Response::new();
+    if amount > bond {
+        let cw20 = Cw20Contract(config.token_addr.clone());
+        res = res
+            .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: bidder.to_string(),
+                amount: amount - bond,
+            })?)
+            .add_event(refund_event(&config, &bidder, amount - bond, "overpaid"));
+    }
+    Ok(res
+        .add_attribute("action", prefixed(&config, "receive_post_deposit"))
+        .add_attribute("bidder", bidder)
+        .add_attribute("bond", bond))
+}
+
+// Bonds come back once bidding is over, except the winner's, which is held until they settle
+// and slashed if they forfeit or default.
+pub fn execute_reclaim_deposit(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    config: Config,
+) -> Result<Response, ContractError> {
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(
+        status,
+        &[
+            AuctionStatus::Ended,
+            AuctionStatus::Settled,
+            AuctionStatus::Cancelled,
+            AuctionStatus::Failed,
+        ],
+    )?;
+    let bond = BID_BONDS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::NoBidBond {})?;
+    if status == AuctionStatus::Ended {
+        if let Some(best_bid) = BEST_BID.may_load(deps.storage)? {
+            if best_bid.bid_record.buyer == info.sender {
+                return Err(ContractError::BidBondHeld {});
+            }
+        }
+    }
+    BID_BONDS.remove(deps.storage, &info.sender);
+
+    let cw20 = Cw20Contract(config.token_addr.clone());
+    Ok(Response::new()
+        .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+            recipient: info.sender.to_string(),
+            amount: bond,
+        })?)
+        .add_event(refund_event(&config, &info.sender, bond, "reclaimed"))
+        .add_attribute("action", prefixed(&config, "execute_reclaim_deposit"))
+        .add_attribute("bidder", info.sender)
+        .add_attribute("bond", bond))
+}
+
+// Transfers a defaulting buyer's bond to the seller.
+fn slash_bid_bond(
+    storage: &mut dyn Storage,
+    config: &Config,
+    buyer: &Addr,
+) -> StdResult<Option<CosmosMsg>> {
+    let bond = match BID_BONDS.may_load(storage, buyer)? {
+        Some(bond) => bond,
+        None => return Ok(None),
+    };
+    BID_BONDS.remove(storage, buyer);
+    let cw20 = Cw20Contract(config.token_addr.clone());
+    Ok(Some(cw20.call(Cw20ExecuteMsg::Transfer {
+        recipient: config.seller.to_string(),
+        amount: bond,
+    })?))
+}
+
+pub fn execute_counteroffer(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    config: Config,
+    buyer: String,
+    price: Uint128,
+    duration_in_blocks: Uint64,
+) -> Result<Response, ContractError> {
+    ensure_seller_or_manager(&config, &info.sender)?;
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Ended])?;
+    ensure_not_escrowed(deps.storage)?;
+
+    // The counteroffer can only be sent to someone who took part in the auction.
+    let buyer = deps.api.addr_validate(&buyer)?;
+    if !OFFERS.has(deps.storage, &buyer) && !BID_COUNTS.has(deps.storage, &buyer) {
+        return Err(ContractError::NoBidOrOffer {
+            buyer: buyer.into_string(),
+        });
+    }
+
+    let closed_event = close_auction(deps.storage, &config)?;
+
+    let expires = Uint64::new(block.height).checked_add(duration_in_blocks)?;
+    let counteroffer = Counteroffer {
+        buyer: buyer.clone(),
+        price,
+        expires,
+    };
+    COUNTEROFFER.save(deps.storage, &counteroffer)?;
+
+    Ok(Response::new()
+        .add_events(closed_event)
+        .add_attribute("action", prefixed(&config, "execute_counteroffer"))
+        .add_attribute("buyer", buyer)
+        .add_attribute("price", price)
+        .add_attribute("expires", expires.to_string()))
+}
+
+pub fn execute_accept_counteroffer(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    config: Config,
+) -> Result<Response, ContractError> {
+    let counteroffer = COUNTEROFFER.load(deps.storage)?;
+    if info.sender != counteroffer.buyer {
+        return Err(ContractError::Unauthorized {});
+    }
+    if block.height >= counteroffer.expires.u64() {
+        return Err(ContractError::CounterofferExpired {});
+    }
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Ended])?;
+
+    COUNTEROFFER.remove(deps.storage);
+    OFFERS.remove(deps.storage, &counteroffer.buyer);
+
+    // The accepted counteroffer becomes the winning bid and is settled through the usual Buy.
+    let next_id = save_best_bid(
+        deps.storage,
+        counteroffer.buyer.clone(),
+        counteroffer.price,
+        config.quantity,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", prefixed(&config, "execute_accept_counteroffer"))
+        .add_attribute("id", next_id)
+        .add_attribute("buyer", counteroffer.buyer)
+        .add_attribute("price", counteroffer.price))
+}
+
+// Once the auction ends unsold, the seller can put the lot up for sale at a fixed price instead.
+// Calling it again changes the price.
+pub fn execute_convert_to_fixed_price(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    config: Config,
+    price: Uint128,
+) -> Result<Response, ContractError> {
+    ensure_seller_or_manager(&config, &info.sender)?;
+    if !has_single_buyer(&config.auction_type) {
+        return Err(ContractError::UnsupportedByAuctionType {});
+    }
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Ended])?;
+    if BEST_BID.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::HasWinningBid {});
+    }
+    if price.is_zero() {
+        return Err(ContractError::ZeroFixedPrice {});
+    }
+
+    let closed_event = close_auction(deps.storage, &config)?;
+    FIXED_PRICE.save(deps.storage, &price)?;
+
+    Ok(Response::new()
+        .add_events(closed_event)
+        .add_attribute(
+            "action",
+            prefixed(&config, "execute_convert_to_fixed_price"),
+        )
+        .add_attribute("price", price))
+}
+
+// The seller reveals the secret reserve after close. A best bid below it fails the auction, its
+// escrow is refunded and the seller can reclaim the lot.
+pub fn execute_reveal_reserve(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    config: Config,
+    reserve: Uint128,
+    salt: String,
+) -> Result<Response, ContractError> {
+    ensure_seller_or_manager(&config, &info.sender)?;
+    let reserve_hash = config
+        .reserve_hash
+        .as_ref()
+        .ok_or(ContractError::NoSecretReserve {})?;
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Ended])?;
+    if SECRET_RESERVE.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::ReserveAlreadyRevealed {});
+    }
+    if sealed_commitment(reserve, &salt) != *reserve_hash {
+        return Err(ContractError::InvalidReveal {});
+    }
+
+    let closed_event = close_auction(deps.storage, &config)?;
+    SECRET_RESERVE.save(deps.storage, &reserve)?;
+    let best_bid = BEST_BID.may_load(deps.storage)?;
+    let reserve_met = matches!(&best_bid, Some(best_bid) if best_bid.bid_record.price >= reserve);
+    let mut res = Response::new();
+    if let (Some(best_bid), false) = (best_bid, reserve_met) {
+        STATUS.save(deps.storage, &AuctionStatus::Failed)?;
+        COUNTEROFFER.remove(deps.storage);
+        let buyer = &best_bid.bid_record.buyer;
+        if let Some(escrowed) = ESCROWED.may_load(deps.storage)? {
+            ESCROWED.remove(deps.storage);
+            PROXY_MAX.remove(deps.storage);
+            let cw20 = Cw20Contract(config.token_addr.clone());
+            res = res
+                .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+                    recipient: buyer.to_string(),
+                    amount: escrowed,
+                })?)
+                .add_event(refund_event(&config, buyer, escrowed, "failed"));
+        }
+        if let Some((msg, deposit)) = release_leading_deposit(deps.storage, &config, buyer)? {
+            res = res
+                .add_message(msg)
+                .add_event(refund_event(&config, buyer, deposit, "failed"));
+        }
+    }
+
+    Ok(res
+        .add_events(closed_event)
+        .add_attribute("action", prefixed(&config, "execute_reveal_reserve"))
+        .add_attribute("reserve", reserve)
+        .add_attribute("reserve_met", reserve_met.to_string()))
+}
+
+// The seller reveals the seed of a drawn format after the timeout. A candle auction resolves its
+// end straight away, and a raffle is drawn at close.
+pub fn execute_reveal_seed(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    config: Config,
+    seed: String,
+) -> Result<Response, ContractError> {
+    ensure_seller_or_manager(&config, &info.sender)?;
+    let seed_hash = config
+        .seed_hash
+        .as_ref()
+        .ok_or(ContractError::UnsupportedByAuctionType {})?;
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Revealing])?;
+    if seed_commitment(&seed) != *seed_hash {
+        return Err(ContractError::InvalidReveal {});
+    }
+    SEED.save(deps.storage, &seed)?;
+    let candle_event = resolve_candle(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_events(candle_event)
+        .add_attribute("action", prefixed(&config, "execute_reveal_seed"))
+        .add_attribute("seed", seed))
+}
+
+pub fn execute_set_manager(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    mut config: Config,
+    manager: Option<String>,
+) -> Result<Response, ContractError> {
+    if info.sender != config.seller {
+        return Err(ContractError::Unauthorized {});
+    }
+    // There is nothing left to manage once the auction has reached a final status.
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(
+        status,
+        &[
+            AuctionStatus::Created,
+            AuctionStatus::Active,
+            AuctionStatus::Ended,
+        ],
+    )?;
+
+    config.manager = manager
+        .map(|manager| deps.api.addr_validate(&manager))
+        .transpose()?;
+    CONFIG.save(deps.storage, &config)?;
+
+    let manager = config
+        .manager
+        .as_ref()
+        .map(|manager| manager.to_string())
+        .unwrap_or_default();
+    Ok(Response::new()
+        .add_attribute("action", prefixed(&config, "execute_set_manager"))
+        .add_attribute("manager", manager))
+}
+
+// Pauses or unpauses `scopes`, or every scope if none are given. Other scopes are left as they are.
+pub fn execute_set_paused(
+    deps: DepsMut,
+    info: MessageInfo,
+    config: Config,
+    scopes: Option<Vec<PauseScope>>,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    if config.admin.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let scopes = scopes.unwrap_or_else(|| PAUSE_SCOPES.to_vec());
+    let was_paused = PAUSED.may_load(deps.storage)?.unwrap_or_default();
+    let now_paused: Vec<_> = PAUSE_SCOPES
+        .iter()
+        .filter(|scope| {
+            if scopes.contains(scope) {
+                paused
+            } else {
+                was_paused.contains(scope)
+            }
+        })
+        .copied()
+        .collect();
+    PAUSED.save(deps.storage, &now_paused)?;
+
+    let action = if paused {
+        "execute_pause"
+    } else {
+        "execute_unpause"
+    };
+    Ok(Response::new().add_attribute("action", prefixed(&config, action)))
+}
+
+// The scope a message is paused with, if any.
+fn pause_scope(msg: &ExecuteMsg) -> Option<PauseScope> {
+    Some(match msg {
+        ExecuteMsg::Receive(TokenReceiveMsg::Cw20(msg)) => {
+            // A hook that does not parse is rejected by its handler anyway.
+            return from_binary(&msg.msg).ok().map(|msg| match msg {
+                ReceiveMsg::Buy | ReceiveMsg::BuyFixedPrice {} | ReceiveMsg::PayPeriod {} => {
+                    PauseScope::Settlement
+                }
+                _ => PauseScope::Bidding,
+            });
+        }
+        ExecuteMsg::Bid { .. }
+        | ExecuteMsg::Receive(TokenReceiveMsg::Cw1155(_))
+        | ExecuteMsg::ReceiveNft(_)
+        | ExecuteMsg::Offer { .. }
+        | ExecuteMsg::CreatePool {}
+        | ExecuteMsg::PoolBid { .. }
+        | ExecuteMsg::Relist { .. } => PauseScope::Bidding,
+        ExecuteMsg::ClaimPayout { .. }
+        | ExecuteMsg::AcceptOffer { .. }
+        | ExecuteMsg::Counteroffer { .. }
+        | ExecuteMsg::AcceptCounteroffer {}
+        | ExecuteMsg::ClaimDefault {}
+        | ExecuteMsg::Close {}
+        | ExecuteMsg::ForfeitWinner {}
+        | ExecuteMsg::Reveal { .. }
+        | ExecuteMsg::SlashUnrevealed {}
+        | ExecuteMsg::ClaimBatchFill {}
+        | ExecuteMsg::ConvertToFixedPrice { .. }
+        | ExecuteMsg::RevealReserve { .. }
+        | ExecuteMsg::RevealSeed { .. }
+        | ExecuteMsg::AcceptBid {}
+        | ExecuteMsg::SettlePool { .. }
+        | ExecuteMsg::ClaimVested {}
+        | ExecuteMsg::ClaimBidFees {} => PauseScope::Settlement,
+        ExecuteMsg::ReclaimLot {}
+        | ExecuteMsg::Cancel {}
+        | ExecuteMsg::ClaimTicketRefund {}
+        | ExecuteMsg::Retract {}
+        | ExecuteMsg::WithdrawOffer {}
+        | ExecuteMsg::WithdrawFromPool { .. }
+        | ExecuteMsg::ReclaimDeposit {}
+        | ExecuteMsg::EndLease {} => PauseScope::Refunds,
+        ExecuteMsg::SetManager { .. }
+        | ExecuteMsg::ExtendDeadline { .. }
+        | ExecuteMsg::Pause { .. }
+        | ExecuteMsg::Unpause { .. } => return None,
+    })
+}
+
+// Anyone can close the auction once it has timed out, without waiting for the winner to pay.
+pub fn execute_close(deps: DepsMut, env: Env, config: Config) -> Result<Response, ContractError> {
+    let status = auction_status(deps.storage, &config, &env.block)?;
+    ensure_status(status, &[AuctionStatus::Ended])?;
+    let res = close_and_settle(deps.storage, &env, &config)?;
+    Ok(res.add_attribute("action", prefixed(&config, "execute_close")))
+}
+
+// An escrowed winning bid is already paid for, so closing also settles it.
+fn close_and_settle(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &Config,
+) -> Result<Response, ContractError> {
+    let closed_event = match close_auction(storage, config)? {
+        Some(closed_event) => closed_event,
+        None => return Err(ContractError::AuctionClosed {}),
+    };
+    match config.auction_type {
+        AuctionType::Batch => return Ok(clear_batch(storage, config)?.add_event(closed_event)),
+        AuctionType::Raffle {
+            ticket_price,
+            refund_losers,
+        } => {
+            let res = draw_raffle(storage, env, config, ticket_price, refund_losers)?;
+            return Ok(res.add_event(closed_event));
+        }
+        _ => {}
+    }
+
+    let mut res = Response::new().add_event(closed_event);
+    if let Some(escrowed) = ESCROWED.may_load(storage)? {
+        ensure_reserve_revealed(storage, config)?;
+        let mut best_bid = BEST_BID.load(storage)?;
+        // Whatever the escrow holds above the sale total, from an overpayment, a top-up, a second
+        // price or an unused proxy ceiling, goes back to the buyer.
+        let total = sale_total(storage, config, &best_bid)?;
+        let held = settle_sale(storage, env, config, &mut best_bid, total)?;
+        let cw20 = Cw20Contract(config.token_addr.clone());
+        if !held {
+            res = res.add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: config.seller.clone().into_string(),
+                amount: total,
+            })?);
+        }
+        let buyer = &best_bid.bid_record.buyer;
+        let refund = escrowed.checked_sub(total)?;
+        if !refund.is_zero() {
+            res = res
+                .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+                    recipient: buyer.to_string(),
+                    amount: refund,
+                })?)
+                .add_event(refund_event(config, buyer, refund, "overpaid"));
+        }
+        res = res.add_messages(winner_lot_msgs(storage, env, config, buyer)?);
+    }
+    Ok(res)
+}
+
+// The seller may push the timeout back while the auction is running, up to `max_extension` in
+// total.
+pub fn execute_extend_deadline(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    mut config: Config,
+    additional: Duration,
+) -> Result<Response, ContractError> {
+    ensure_seller_or_manager(&config, &info.sender)?;
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Active])?;
+    let max_extension = match config.max_extension {
+        Some(max_extension) => extension_amount(&config, max_extension)?,
+        None => return Err(ContractError::ExtensionNotAllowed {}),
+    };
+    let requested = extension_amount(&config, additional)?;
+    let extended = EXTENDED.may_load(deps.storage)?.unwrap_or_default();
+    let remaining = max_extension.saturating_sub(extended);
+    if requested > remaining {
+        return Err(ContractError::ExtensionExceedsLimit {
+            requested,
+            remaining,
+        });
+    }
+
+    let old_timeout = config.timeout;
+    config.timeout = (config.timeout + additional)?;
+    CONFIG.save(deps.storage, &config)?;
+    EXTENDED.save(deps.storage, &(extended + requested))?;
+
+    Ok(Response::new()
+        .add_event(
+            Event::new(prefixed(&config, "deadline_extended"))
+                .add_attribute("old_deadline", old_timeout.to_string())
+                .add_attribute("new_deadline", config.timeout.to_string()),
+        )
+        .add_attribute("action", prefixed(&config, "execute_extend_deadline"))
+        .add_attribute("additional", requested.to_string()))
+}
+
+// Outbid bidders are refunded as soon as they are outbid, so only the best bidder has an escrow
+// to retract. The penalty goes to the seller and bidding starts over from the reserve, since the
+// earlier bids are no longer backed.
+pub fn execute_retract(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    config: Config,
+) -> Result<Response, ContractError> {
+    let penalty_bps = config
+        .retraction_penalty_bps
+        .ok_or(ContractError::RetractionNotAllowed {})?;
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Active])?;
+    let best_bid = match BEST_BID.may_load(deps.storage)? {
+        Some(best_bid) if best_bid.bid_record.buyer == info.sender => best_bid,
+        _ => {
+            return Err(ContractError::NoBidOrOffer {
+                buyer: info.sender.into_string(),
+            })
+        }
+    };
+    let escrowed = ESCROWED.load(deps.storage)?;
+    let (penalty, refund) = split_bps(escrowed, penalty_bps);
+
+    RETRACTED.save(deps.storage, best_bid.id.u64(), &info.sender)?;
+    BEST_BID.remove(deps.storage);
+    RUNNER_UP.remove(deps.storage);
+    ESCROWED.remove(deps.storage);
+    PROXY_MAX.remove(deps.storage);
+
+    let cw20 = Cw20Contract(config.token_addr.clone());
+    let mut res = Response::new();
+    if !penalty.is_zero() {
+        res = res.add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+            recipient: config.seller.to_string(),
+            amount: penalty,
+        })?);
+    }
+    if !refund.is_zero() {
+        res = res
+            .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: info.sender.to_string(),
+                amount: refund,
+            })?)
+            .add_event(refund_event(&config, &info.sender, refund, "retracted"));
+    }
+
+    Ok(res
+        .add_attribute("action", prefixed(&config, "execute_retract"))
+        .add_attribute("id", best_bid.id)
+        .add_attribute("buyer", info.sender)
+        .add_attribute("penalty", penalty)
+        .add_attribute("refund", refund))
+}
+
+// An English auction that ended unsold, or failed, can be run again with new terms. The bids of
+// the round are archived under its index and everything tied to them is reset.
+pub fn execute_relist(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    mut config: Config,
+    new_config: RelistConfig,
+) -> Result<Response, ContractError> {
+    ensure_seller_or_manager(&config, &info.sender)?;
+    if config.auction_type != AuctionType::English {
+        return Err(ContractError::NotEnglishAuction {});
+    }
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Ended, AuctionStatus::Failed])?;
+    if status == AuctionStatus::Ended && BEST_BID.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::HasWinningBid {});
+    }
+    ensure_not_escrowed(deps.storage)?;
+
+    let timeout = auction_timeout(
+        block,
+        new_config.duration_in_blocks,
+        new_config.duration,
+        new_config.expiration,
+    )?;
+    // Extensions, soft close and settlement deadlines were checked against the unit of the
+    // original timeout.
+    if std::mem::discriminant(&timeout) != std::mem::discriminant(&config.timeout) {
+        return Err(ContractError::InvalidExpiration {});
+    }
+    if new_config.increment.is_zero() {
+        return Err(ContractError::ZeroIncrement {});
+    }
+    if let Some(reserve_decay) = &config.reserve_decay {
+        if reserve_decay.floor > new_config.reserve_price {
+            return Err(ContractError::InvalidReserveDecay {});
+        }
+    }
+    if let Some(buy_now_price) = config.buy_now_price {
+        if buy_now_price < new_config.reserve_price {
+            return Err(ContractError::InvalidBuyNowPrice {});
+        }
+    }
+
+    let closed_event = close_auction(deps.storage, &config)?;
+    let round = ROUND.may_load(deps.storage)?.unwrap_or_default();
+    let records = BID_RECORDS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (id, record) in records {
+        ROUND_BIDS.save(deps.storage, (round, id), &record)?;
+        BID_RECORDS.remove(deps.storage, id);
+    }
+    for map in [FORFEITED, RETRACTED] {
+        let ids = map
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for id in ids {
+            map.remove(deps.storage, id);
+        }
+    }
+    let buyers = OFFERS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for buyer in buyers {
+        OFFERS.remove(deps.storage, &buyer);
+    }
+    let bidders = BID_COUNTS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for bidder in bidders {
+        BID_COUNTS.remove(deps.storage, &bidder);
+        BID_COOLDOWNS.remove(deps.storage, &bidder);
+    }
+    let pool_bids = POOL_BIDS
+        .prefix(round)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for id in pool_bids {
+        POOL_BIDS.remove(deps.storage, (round, id));
+    }
+    // Bid bonds and direct offers are kept on purpose. A bond admits its bidder to every round
+    // and is reclaimable once the new round ends, and an offer stands until it is accepted or
+    // withdrawn.
+    BID_SEQ.save(deps.storage, &0u64)?;
+    BEST_BID.remove(deps.storage);
+    RUNNER_UP.remove(deps.storage);
+    PROXY_MAX.remove(deps.storage);
+    SETTLEMENT_DEADLINE.remove(deps.storage);
+    COUNTEROFFER.remove(deps.storage);
+    FIXED_PRICE.remove(deps.storage);
+    SECRET_RESERVE.remove(deps.storage);
+    INSTALLMENTS.remove(deps.storage);
+    EXTENDED.remove(deps.storage);
+    ROUND.save(deps.storage, &(round + 1))?;
+
+    // A revealed secret reserve is public now, so the new round goes by the new reserve alone.
+    config.reserve_price = new_config.reserve_price;
+    config.increment = new_config.increment;
+    config.timeout = timeout;
+    config.start = Uint64::new(block.height);
+    config.reserve_hash = None;
+    CONFIG.save(deps.storage, &config)?;
+    STATUS.save(deps.storage, &AuctionStatus::Created)?;
+
+    Ok(Response::new()
+        .add_events(closed_event)
+        .add_event(
+            Event::new(prefixed(&config, "auction_relisted"))
+                .add_attribute("round", (round + 1).to_string())
+                .add_attribute("timeout", config.timeout.to_string()),
+        )
+        .add_attribute("action", prefixed(&config, "execute_relist"))
+        .add_attribute("reserve_price", config.reserve_price)
+        .add_attribute("increment", config.increment))
+}
+
+// The seller can end bidding early by accepting the best bid, which then settles as if the
+// auction had timed out at this block.
+pub fn execute_accept_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mut config: Config,
+) -> Result<Response, ContractError> {
+    if info.sender != config.seller {
+        return Err(ContractError::Unauthorized {});
+    }
+    if config.auction_type != AuctionType::English {
+        return Err(ContractError::NotEnglishAuction {});
+    }
+    let status = auction_status(deps.storage, &config, &env.block)?;
+    ensure_status(status, &[AuctionStatus::Active])?;
+    let best_bid = BEST_BID
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoWinningBid {})?;
+
+    let old_timeout = config.timeout;
+    config.timeout = match config.timeout {
+        Expiration::AtTime(_) => Expiration::AtTime(env.block.time),
+        _ => Expiration::AtHeight(env.block.height),
+    };
+    CONFIG.save(deps.storage, &config)?;
+    let res = close_and_settle(deps.storage, &env, &config)?;
+
+    Ok(res
+        .add_event(
+            Event::new(prefixed(&config, "bid_accepted"))
+                .add_attribute("old_deadline", old_timeout.to_string())
+                .add_attribute("new_deadline", config.timeout.to_string()),
+        )
+        .add_attribute("action", prefixed(&config, "execute_accept_bid"))
+        .add_attribute("id", best_bid.id)
+        .add_attribute("buyer", best_bid.bid_record.buyer)
+        .add_attribute("price", best_bid.bid_record.price))
+}
+
+// Anyone can forfeit a winner who has not paid within the settlement window. Settlement passes to
+// the runner-up with a fresh window, or without one the lot is left to the seller.
+pub fn execute_forfeit_winner(
+    deps: DepsMut,
+    block: &BlockInfo,
+    config: Config,
+) -> Result<Response, ContractError> {
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Ended])?;
+    let best_bid = match BEST_BID.may_load(deps.storage)? {
+        Some(best_bid) => best_bid,
+        None => return Err(ContractError::NoWinningBid {}),
+    };
+    let deadline = match settlement_deadline(deps.storage, &config)? {
+        Some(deadline) => deadline,
+        None => return Err(ContractError::NoSettlementWindow {}),
+    };
+    if block.height < deadline {
+        return Err(ContractError::SettlementWindowOpen { deadline });
+    }
+    ensure_not_escrowed(deps.storage)?;
+
+    let closed_event = close_auction(deps.storage, &config)?;
+    FORFEITED.save(deps.storage, best_bid.id.u64(), &best_bid.bid_record.buyer)?;
+    let slashed = slash_bid_bond(deps.storage, &config, &best_bid.bid_record.buyer)?;
+
+    let mut res = Response::new()
+        .add_messages(slashed)
+        .add_events(closed_event)
+        .add_attribute("action", prefixed(&config, "execute_forfeit_winner"))
+        .add_attribute("forfeited", best_bid.bid_record.buyer);
+    if let Some((msg, deposit)) = release_leading_deposit(deps.storage, &config, &config.seller)? {
+        res = res.add_message(msg).add_attribute("deposit", deposit);
+    }
+    match RUNNER_UP.may_load(deps.storage)? {
+        Some(runner_up) => {
+            let settlement_window = config.settlement_window.unwrap_or_default();
+            let deadline = Uint64::new(block.height).checked_add(settlement_window)?;
+            BEST_BID.save(deps.storage, &runner_up)?;
+            RUNNER_UP.remove(deps.storage);
+            SETTLEMENT_DEADLINE.save(deps.storage, &deadline)?;
+            res = res
+                .add_attribute("buyer", runner_up.bid_record.buyer)
+                .add_attribute("deadline", deadline);
+        }
+        None => BEST_BID.remove(deps.storage),
+    }
+
+    Ok(res)
+}
+
+// Once an installment is missed, the seller keeps `forfeit_bps` of what was paid and the rest is
+// refunded to the buyer.
+pub fn execute_claim_default(
+    deps: DepsMut,
+    block: &BlockInfo,
+    info: MessageInfo,
+    config: Config,
+) -> Result<Response, ContractError> {
+    ensure_seller_or_manager(&config, &info.sender)?;
+    let installment_plan = match &config.installment_plan {
+        Some(installment_plan) => installment_plan,
+        None => return Err(ContractError::NoInstallmentPlan {}),
+    };
+    let status = auction_status(deps.storage, &config, block)?;
+    ensure_status(status, &[AuctionStatus::Ended])?;
+    let best_bid = match BEST_BID.may_load(deps.storage)? {
+        Some(best_bid) => best_bid,
+        None => return Err(ContractError::NoWinningBid {}),
+    };
+    let mut installments = INSTALLMENTS.may_load(deps.storage)?.unwrap_or_default();
+    let due_at = installment_due_at(&config, installment_plan, installments.paid_count)?;
+    if block.height < due_at {
+        return Err(ContractError::InstallmentNotOverdue { due_at });
+    }
+
+    installments.defaulted = true;
+    INSTALLMENTS.save(deps.storage, &installments)?;
+    STATUS.save(deps.storage, &AuctionStatus::Failed)?;
+
+    // The down payment is kept in full, and `forfeit_bps` of whatever was paid after it.
+    let down_payment = match installment_plan.down_payment_bps {
+        Some(_) if installments.paid_count > 0 => {
+            let total = sale_total(deps.storage, &config, &best_bid)?;
+            installment_amount(total, installment_plan, 0).min(installments.paid)
+        }
+        _ => Uint128::zero(),
+    };
+    let (forfeited, refund) = split_bps(
+        installments.paid - down_payment,
+        installment_plan.forfeit_bps,
+    );
+    let forfeited = forfeited + down_payment;
+    let cw20 = Cw20Contract(config.token_addr.clone());
+    let mut res = Response::new();
+    if !forfeited.is_zero() {
+        res = res.add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+            recipient: config.seller.clone().into_string(),
+            amount: forfeited,
+        })?);
+    }
+    if !refund.is_zero() {
+        let buyer = &best_bid.bid_record.buyer;
+        res = res
+            .add_message(cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: buyer.to_string(),
+                amount: refund,
+            })?)
+            .add_event(refund_event(&config, buyer, refund, "defaulted"));
+    }
+    let slashed = slash_bid_bond(deps.storage, &config, &best_bid.bid_record.buyer)?;
+    if let Some((msg, deposit)) = release_leading_deposit(deps.storage, &config, &config.seller)? {
+        res = res.add_message(msg).add_attribute("deposit", deposit);
+    }
+
+    Ok(res
+        .add_messages(slashed)
+        .add_attribute("action", prefixed(&config, "execute_claim_default"))
+        .add_attribute("buyer", best_bid.bid_record.buyer)
+        .add_attribute("forfeited", forfeited)
+        .add_attribute("refund", refund))
+}
+
+// Returns the native lot to the seller when nothing sold, after which offers can no longer be
+// accepted.
+pub fn execute_reclaim_lot(
+    deps: DepsMut,
+    env: &Env,
+    info: MessageInfo,
+    config: Config,
+) -> Result<Response, ContractError> {
+    if info.sender != config.seller {
+        return Err(ContractError::Unauthorized {});
+    }
+    let status = auction_status(deps.storage, &config, &env.block)?;
+    ensure_status(status, &[AuctionStatus::Ended, AuctionStatus::Failed])?;
+    if config.lot.is_empty() && config.bundle.is_empty() {
+        return Err(ContractError::NoLot {});
+    }
+    if status != AuctionStatus::Failed && BEST_BID.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::HasWinningBid {});
+    }
+
+    let closed_event = close_auction(deps.storage, &config)?;
+    STATUS.save(deps.storage, &AuctionStatus::Cancelled)?;
+    COUNTEROFFER.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_messages(lot_msgs(deps.storage, env, &config, &config.seller)?)
+        .add_events(closed_event)
+        .add_attribute("action", prefixed(&config, "execute_reclaim_lot"))
+        .add_attribute("seller", config.seller))
+}
+
+// Sealed-bid deposits are refunded here. Offers, bonds, batch escrows, tickets and pool
+// contributions are claimed back by their owners once the auction is cancelled.
+pub fn execute_cancel(
+    deps: DepsMut,
+    env: &Env,
+    info: MessageInfo,
+    config: Config,
+) -> Result<Response, ContractError> {
+    ensure_seller_or_manager(&config, &info.sender)?;
+    let status = auction_status(deps.storage, &config, &env.block)?;
+    ensure_status(status, &[AuctionStatus::Created, AuctionStatus::Active])?;
+    if BID_SEQ.load(deps.storage)? != 0 {
+        return Err(ContractError::HasBids {});
+    }
+
+    let closed_event = close_auction(deps.storage, &config)?;
+    STATUS.save(deps.storage, &AuctionStatus::Cancelled)?;
+    COUNTEROFFER.remove(deps.storage);
+
+    let mut res = Response::new().add_events(closed_event);
+    if let Some(lot_msg) = lot_token_refund(deps.storage, &config)? {
+        res = res.add_message(lot_msg);
+    }
+    res = refund_sealed_deposits(deps.storage, &config, res)?;
+    Ok(res
+        .add_messages(lot_msgs(deps.storage, env, &config, &config.seller)?)
+        .add_attribute("action", prefixed(&config, "execute_cancel"))
+        .add_attribute("seller", config.seller))
+}
+
+// Releases the native lot and every bundle item still held to `recipient`, the winner or the
+// seller taking it back.
+fn lot_msgs(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &Config,
+    recipient: &Addr,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut msgs = vec![];
+    if !config.lot.is_empty() {
+        msgs.push(
+            BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: config.lot.clone(),
+            }
+            .into(),
+        );
+    }
+    let deposited = BUNDLE_DEPOSITED
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for index in deposited {
+        BUNDLE_DEPOSITED.remove(storage, index);
+        msgs.push(match &config.bundle[index as usize] {
+            BundleItem::Cw20 { address, amount } => {
+                Cw20Contract(address.clone()).call(Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount: *amount,
+                })?
+            }
+            BundleItem::Cw721 { address, token_id } => {
+                transfer_nft_msg(address.as_str(), recipient.as_str(), token_id)?
+            }
+            BundleItem::Cw1155 {
+                address,
+                token_id,
+                amount,
+            } => send_tokens_msg(
+                address.as_str(),
+                env.contract.address.as_str(),
+                recipient.as_str(),
+                token_id,
+                *amount,
+            )?,
+        });
+    }
+    Ok(msgs)
+}
+
+// A leased lot stays in the contract until the lease ends, and goes back to the seller then.
+fn winner_lot_msgs(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &Config,
+    winner: &Addr,
+) -> StdResult<Vec<CosmosMsg>> {
+    if config.lease.is_some() {
+        return Ok(vec![]);
+    }
+    lot_msgs(storage, env, config, winner)
+}
+
+// What the winner owes for the lot at the clearing price.
+fn sale_total(
+    storage: &dyn Storage,
+    config: &Config,
+    best_bid: &BestBid,
+) -> Result<Uint128, ContractError> {
+    let runner_up = if config.second_price {
+        RUNNER_UP.may_load(storage)?
+    } else {
+        None
+    };
+    let runner_up = runner_up.as_ref().map(|runner_up| &runner_up.bid_record);
+    let price = clearing_price(config, &best_bid.bid_record, runner_up);
+    Ok(price.checked_mul(best_bid.bid_record.quantity)?)
+}
+
+// Marks the lot sold and, with a payout delay, proposal confirmation or vesting schedule, holds
+// the proceeds in the contract until they are claimed. Returns whether the proceeds are held.
+fn settle_sale(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &Config,
+    best_bid: &mut BestBid,
+    amount: Uint128,
+) -> Result<bool, ContractError> {
+    ensure_bundle_deposited(storage, config)?;
+    best_bid.sold = true;
+    BEST_BID.save(storage, best_bid)?;
+    STATUS.save(storage, &AuctionStatus::Settled)?;
+
+    #[cfg(feature = "history")]
+    history::record_settlement(
+        storage,
+        &Settlement {
+            bid_id: best_bid.id,
+            price: best_bid.bid_record.price,
+            quantity: best_bid.bid_record.quantity,
+            height: Uint64::new(env.block.height),
+        },
+    )?;
+    #[cfg(feature = "stats")]
+    stats::record_win(storage, &best_bid.bid_record.buyer, amount)?;
+    if config.lease.is_some() {
+        let lease = Lease {
+            lessee: best_bid.bid_record.buyer.clone(),
+            start: Uint64::new(env.block.height),
+            paid_periods: 1,
+            ended: false,
+        };
+        LEASE.save(storage, &lease)?;
+    }
+
+    if config.payout_vesting.is_some() {
+        let vesting = Vesting {
+            total: amount,
+            start: Uint64::new(env.block.height),
+            claimed: Uint128::zero(),
+        };
+        VESTING.save(storage, &vesting)?;
+        return Ok(true);
+    }
+    let held = config.payout_delay.is_some() || config.proposal_confirmation;
+    if held {
+        let claimable_at =
+            Uint64::new(env.block.height).checked_add(config.payout_delay.unwrap_or_default())?;
+        let payout = Payout {
+            amount,
+            claimable_at,
+            claimed: false,
+        };
+        PAYOUT.save(storage, &payout)?;
+    }
+    Ok(held)
+}
+
+// Installment `index` (zero-based) is due one interval after the previous one, counting from the
+// auction timeout.
+fn installment_due_at(
+    config: &Config,
+    installment_plan: &InstallmentPlan,
+    index: u32,
+) -> Result<u64, ContractError> {
+    let offset = installment_plan
+        .interval_in_blocks
+        .checked_mul(Uint64::from(index + 1))?;
+    Ok(timeout_height(config)?.checked_add(offset)?.u64())
+}
+
+// Payments in the plan, the down payment included.
+fn payment_count(installment_plan: &InstallmentPlan) -> u32 {
+    installment_plan.count + u32::from(installment_plan.down_payment_bps.is_some())
+}
+
+// Payment `index` is the down payment first, if any. The rest of the total is split evenly and
+// the last installment takes the remainder.
+fn installment_amount(total: Uint128, installment_plan: &InstallmentPlan, index: u32) -> Uint128 {
+    let (down_payment, rest) =
+        split_bps(total, installment_plan.down_payment_bps.unwrap_or_default());
+    let index = match installment_plan.down_payment_bps {
+        Some(_) if index == 0 => return down_payment,
+        Some(_) => index - 1,
+        None => index,
+    };
+    let count = Uint128::from(installment_plan.count);
+    let amount = rest / count;
+    if index + 1 == installment_plan.count {
+        rest - amount * (count - Uint128::new(1))
+    } else {
+        amount
+    }
+}
+
+// Lets deployments sharing a code id tell their events and actions apart in indexers.
+fn prefixed(config: &Config, name: &str) -> String {
+    match &config.event_prefix {
+        Some(event_prefix) => format!("{}_{}", event_prefix, name),
+        None => name.to_string(),
+    }
+}
+
+// Emitted with every refund of bid, ticket or offer funds, so wallets need not parse the token's
+// transfer logs. The reason is one of outbid, overpaid, cancelled, failed, retracted, defaulted,
+// unfilled, lost, replaced, withdrawn or pool_withdrawal.
+fn refund_event(config: &Config, bidder: &Addr, amount: Uint128, reason: &str) -> Event {
+    Event::new(prefixed(config, "refund"))
+        .add_attribute("bidder", bidder)
+        .add_attribute("amount", amount)
+        .add_attribute("token", &config.token_addr)
+        .add_attribute("reason", reason)
+}
+
+// The winner must pay within the settlement window after the timeout, or after the reveal phase
+// of a sealed auction, or after taking over from a forfeited winner.
+fn settlement_deadline(
+    storage: &dyn Storage,
+    config: &Config,
+) -> Result<Option<u64>, ContractError> {
+    let settlement_window = match config.settlement_window {
+        Some(settlement_window) => settlement_window,
+        None => return Ok(None),
+    };
+    let deadline = match SETTLEMENT_DEADLINE.may_load(storage)? {
+        Some(deadline) => deadline,
+        None => {
+            let ended_at = match reveal_deadline(config) {
+                Some(reveal_deadline) => Uint64::new(reveal_deadline),
+                None => timeout_height(config)?,
+            };
+            ended_at.checked_add(settlement_window)?
+        }
+    };
+    Ok(Some(deadline.u64()))
+}
+
+// A winning bid is only settled once the secret reserve, if any, is known to be met.
+fn ensure_reserve_revealed(storage: &dyn Storage, config: &Config) -> Result<(), ContractError> {
+    if config.reserve_hash.is_some() && SECRET_RESERVE.may_load(storage)?.is_none() {
+        return Err(ContractError::ReserveNotRevealed {});
+    }
+    Ok(())
+}
+
+fn ensure_not_escrowed(storage: &dyn Storage) -> Result<(), ContractError> {
+    if ESCROWED.may_load(storage)?.is_some() {
+        return Err(ContractError::AlreadyEscrowed {});
+    }
+    Ok(())
+}
+
+// Sellers give either a duration, in blocks or seconds, or an explicit expiration. An auction
+// that never expires can only be ended by a handler that closes it explicitly.
+fn auction_timeout(
+    block: &BlockInfo,
+    duration_in_blocks: Option<Uint64>,
+    duration: Option<Duration>,
+    expiration: Option<Expiration>,
+) -> Result<Expiration, ContractError> {
+    let duration = match (duration_in_blocks, duration) {
+        (Some(duration_in_blocks), None) => Some(Duration::Height(duration_in_blocks.u64())),
+        (None, duration) => duration,
+        _ => return Err(ContractError::InvalidExpiration {}),
+    };
+    let timeout = match (duration, expiration) {
+        (Some(Duration::Height(blocks)), None) => Expiration::AtHeight(
+            Uint64::new(block.height)
+                .checked_add(Uint64::new(blocks))?
+                .u64(),
+        ),
+        (Some(Duration::Time(seconds)), None) => Expiration::AtTime(Timestamp::from_seconds(
+            Uint64::new(block.time.seconds())
+                .checked_add(Uint64::new(seconds))?
+                .u64(),
+        )),
+        (None, Some(expiration)) => expiration,
+        _ => return Err(ContractError::InvalidExpiration {}),
+    };
+    let (duration, min, max) = match timeout {
+        Expiration::AtHeight(height) => (
+            height.saturating_sub(block.height),
+            MIN_DURATION_IN_BLOCKS,
+            MAX_DURATION_IN_BLOCKS,
+        ),
+        Expiration::AtTime(time) => (
+            time.seconds().saturating_sub(block.time.seconds()),
+            MIN_DURATION_IN_SECONDS,
+            MAX_DURATION_IN_SECONDS,
+        ),
+        Expiration::Never {} => return Ok(timeout),
+    };
+    if duration == 0 {
+        return Err(ContractError::ZeroDuration {});
+    }
+    if duration < min || duration > max {
+        return Err(ContractError::DurationOutOfBounds {
+            duration: Uint64::new(duration),
+            min,
+            max,
+        });
+    }
+    Ok(timeout)
+}
+
+// A bid in the last `soft_close_window` pushes the timeout back by `extension`, so late bids can
+// always be answered.
+fn soft_close(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    config: &Config,
+) -> Result<Option<Event>, ContractError> {
+    let (soft_close_window, extension) = match (config.soft_close_window, config.extension) {
+        (Some(soft_close_window), Some(extension)) => (soft_close_window, extension),
+        _ => return Ok(None),
+    };
+    let in_window = match (config.timeout, soft_close_window) {
+        (Expiration::AtHeight(height), Duration::Height(window)) => {
+            height.saturating_sub(block.height) <= window
+        }
+        (Expiration::AtTime(time), Duration::Time(window)) => {
+            time.seconds().saturating_sub(block.time.seconds()) <= window
+        }
+        _ => false,
+    };
+    if !in_window {
+        return Ok(None);
+    }
+
+    let mut config = config.clone();
+    let old_timeout = config.timeout;
+    config.timeout = (config.timeout + extension)?;
+    CONFIG.save(storage, &config)?;
+    Ok(Some(
+        Event::new(prefixed(&config, "deadline_extended"))
+            .add_attribute("old_deadline", old_timeout.to_string())
+            .add_attribute("new_deadline", config.timeout.to_string()),
+    ))
+}
+
+// Extensions are counted in the unit of the timeout, so a time-based auction is extended in
+// seconds and a height-based one in blocks.
+fn extension_amount(config: &Config, extension: Duration) -> Result<u64, ContractError> {
+    match (config.timeout, extension) {
+        (Expiration::AtHeight(_), Duration::Height(amount))
+        | (Expiration::AtTime(_), Duration::Time(amount))
+            if amount > 0 =>
+        {
+            Ok(amount)
+        }
+        _ => Err(ContractError::InvalidExtension {}),
+    }
+}
+
+fn timeout_height(config: &Config) -> Result<Uint64, ContractError> {
+    match config.timeout {
+        Expiration::AtHeight(height) => Ok(Uint64::new(height)),
+        _ => Err(ContractError::HeightExpirationRequired {}),
+    }
+}
+
+// Sealed bids are revealed for a period after the timeout, before the auction ends.
+fn reveal_deadline(config: &Config) -> Option<u64> {
+    match (&config.auction_type, config.timeout) {
+        (
+            AuctionType::Sealed {
+                reveal_period_in_blocks,
+                ..
+            },
+            Expiration::AtHeight(height),
+        ) => Some(height.saturating_add(reveal_period_in_blocks.u64())),
+        _ => None,
+    }
+}
+
+fn seed_deadline(config: &Config) -> Option<u64> {
+    match (&config.seed_hash, config.timeout) {
+        (Some(_), Expiration::AtHeight(height)) => {
+            Some(height.saturating_add(SEED_REVEAL_PERIOD_IN_BLOCKS))
+        }
+        _ => None,
+    }
+}
+
+fn auction_status(
+    storage: &dyn Storage,
+    config: &Config,
+    block: &BlockInfo,
+) -> StdResult<AuctionStatus> {
+    Ok(match STATUS.load(storage)? {
+        AuctionStatus::Created if config.timeout.is_expired(block) => {
+            // A drawn format waits for the seller's seed, and fails if it is not revealed in time.
+            let seed_deadline = match seed_deadline(config) {
+                Some(deadline) if SEED.may_load(storage)?.is_none() => Some(deadline),
+                _ => None,
+            };
+            match (seed_deadline, reveal_deadline(config)) {
+                (Some(deadline), _) if block.height >= deadline => AuctionStatus::Failed,
+                (Some(_), _) => AuctionStatus::Revealing,
+                (None, Some(deadline)) if block.height < deadline => AuctionStatus::Revealing,
+                _ => AuctionStatus::Ended,
+            }
+        }
+        AuctionStatus::Created if block.height >= config.start.u64() => AuctionStatus::Active,
+        status => status,
+    })
+}
+
+// Rejects the message unless the auction is in one of the `allowed` statuses, with the error
+// that best describes the status it is in.
+fn ensure_status(status: AuctionStatus, allowed: &[AuctionStatus]) -> Result<(), ContractError> {
+    if allowed.contains(&status) {
+        return Ok(());
+    }
+    Err(match status {
+        AuctionStatus::Created => ContractError::AuctionNotStarted {},
+        AuctionStatus::Active => ContractError::AuctionStillOpen {},
+        AuctionStatus::Revealing => ContractError::AuctionRevealing {},
+        AuctionStatus::Ended => ContractError::AuctionClosed {},
+        AuctionStatus::Settled => ContractError::AlreadySold {},
+        AuctionStatus::Cancelled => ContractError::LotReclaimed {},
+        AuctionStatus::Failed => ContractError::AuctionFailed {},
+    })
+}
+
+// The manager may run operational actions on behalf of the seller, but proceeds always go to
+// the seller.
+fn ensure_seller_or_manager(config: &Config, sender: &Addr) -> Result<(), ContractError> {
+    if *sender == config.seller || config.manager.as_ref() == Some(sender) {
+        Ok(())
+    } else {
+        Err(ContractError::Unauthorized {})
+    }
+}
+
+// Emits `auction_closed` from the first handler that runs after the timeout.
+fn close_auction(storage: &mut dyn Storage, config: &Config) -> StdResult<Option<Event>> {
+    match STATUS.load(storage)? {
+        AuctionStatus::Created | AuctionStatus::Active => {}
+        _ => return Ok(None),
+    }
+    STATUS.save(storage, &AuctionStatus::Ended)?;
+
+    let best_price = BEST_BID
+        .may_load(storage)?
+        .map(|best_bid| best_bid.bid_record.price)
+        .unwrap_or_default();
+    let reserve_met = BID_SEQ.load(storage)? > last_retracted(storage)?;
+
+    Ok(Some(
+        Event::new(prefixed(config, "auction_closed"))
+            .add_attribute("timeout", config.timeout.to_string())
+            .add_attribute("best_price", best_price)
+            .add_attribute("reserve_met", reserve_met.to_string()),
+    ))
+}
+
+fn last_retracted(storage: &dyn Storage) -> StdResult<u64> {
+    Ok(RETRACTED
+        .keys(storage, None, None, Order::Descending)
+        .next()
+        .transpose()?
+        .unwrap_or_default())
+}
+
+fn save_best_bid(
+    storage: &mut dyn Storage,
+    buyer: Addr,
+    price: Uint128,
+    quantity: Uint128,
+) -> Result<Uint64, ContractError> {
+    let id = BID_SEQ.load(storage)?;
+    let next_id = Uint64::new(id).checked_add(Uint64::new(1))?;
+    BID_SEQ.save(storage, &next_id.u64())?;
+
+    let bid_record = BidRecord {
+        buyer,
+        price,
+        quantity,
+    };
+    BID_RECORDS.save(storage, next_id.u64(), &bid_record)?;
+
+    let best_bid = BestBid {
+        id: next_id,
+        bid_record,
+        sold: false,
+    };
+    BEST_BID.save(storage, &best_bid)?;
+
+    Ok(next_id)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetApiVersion => to_binary(&ApiVersionResponse {
+            api_version: API_VERSION,
+        }),
+        QueryMsg::GetConfig => to_binary(&CONFIG.load(deps.storage)?),
+        QueryMsg::GetDeadline => to_binary(&CONFIG.load(deps.storage)?.timeout),
+        QueryMsg::GetStatus => {
+            let config = CONFIG.load(deps.storage)?;
+            to_binary(&auction_status(deps.storage, &config, &env.block)?)
+        }
+        QueryMsg::GetPaused => to_binary(&PAUSED.may_load(deps.storage)?.unwrap_or_default()),
+        QueryMsg::GetBidSeq => to_binary(&BID_SEQ.load(deps.storage)?),
+        QueryMsg::GetBidRecord { id } => to_binary(&query_bid(deps, id)?),
+        QueryMsg::GetBestBid => to_binary(&BEST_BID.load(deps.storage)?),
+        QueryMsg::GetRound => to_binary(&ROUND.may_load(deps.storage)?.unwrap_or_default()),
+        QueryMsg::GetRoundBidRecord { round, id } => {
+            let bid_record = ROUND_BIDS.load(deps.storage, (round, id.u64()))?;
+            to_binary(&BidResponse {
+                buyer: bid_record.buyer.into_string(),
+                price: bid_record.price,
+                quantity: bid_record.quantity,
+            })
+        }
+        QueryMsg::GetReservePrice => {
+            let config = CONFIG.load(deps.storage)?;
+            to_binary(&reserve_price_at(&config, env.block.height))
+        }
+        QueryMsg::GetTickets { buyer } => {
+            let buyer = deps.api.addr_validate(&buyer)?;
+            to_binary(&TICKETS.may_load(deps.storage, &buyer)?.unwrap_or_default())
+        }
+        QueryMsg::GetBatchClearing => to_binary(&BATCH_CLEARING.may_load(deps.storage)?),
+        QueryMsg::GetCandleEnd => to_binary(&CANDLE_END.may_load(deps.storage)?),
+        QueryMsg::GetCurrentPrice => to_binary(&query_current_price(deps, &env)?),
+        QueryMsg::GetPayout => to_binary(&PAYOUT.load(deps.storage)?),
+        QueryMsg::GetLease => to_binary(&query_lease(deps, &env)?),
+        QueryMsg::GetFeePool => to_binary(&FEE_POOL.may_load(deps.storage)?.unwrap_or_default()),
+        QueryMsg::GetHybridPhase => {
+            let config = CONFIG.load(deps.storage)?;
+            to_binary(&hybrid_phase(deps.storage, &config)?)
+        }
+        QueryMsg::GetVesting => to_binary(&query_vesting(deps, &env)?),
+        QueryMsg::GetOffers { start_after, limit } => {
+            to_binary(&query_offers(deps, OFFERS, start_after, limit)?)
+        }
+        QueryMsg::GetDirectOffers { start_after, limit } => {
+            to_binary(&query_offers(deps, DIRECT_OFFERS, start_after, limit)?)
+        }
+        QueryMsg::GetCounteroffer => to_binary(&COUNTEROFFER.load(deps.storage)?),
+        QueryMsg::GetBundle => to_binary(&query_bundle(deps)?),
+        QueryMsg::GetFixedPrice => to_binary(&FIXED_PRICE.may_load(deps.storage)?),
+        QueryMsg::GetRevealedReserve => to_binary(&SECRET_RESERVE.may_load(deps.storage)?),
+        QueryMsg::GetCommitment { bidder } => {
+            let bidder = deps.api.addr_validate(&bidder)?;
+            to_binary(&COMMITMENTS.may_load(deps.storage, &bidder)?)
+        }
+        QueryMsg::GetRunnerUp => to_binary(&RUNNER_UP.may_load(deps.storage)?),
+        QueryMsg::GetInstallments => {
+            to_binary(&INSTALLMENTS.may_load(deps.storage)?.unwrap_or_default())
+        }
+        QueryMsg::GetPool { pool_id } => to_binary(&POOLS.load(deps.storage, pool_id)?),
+        QueryMsg::GetPoolContribution {
+            pool_id,
+            contributor,
+        } => to_binary(&query_pool_contribution(deps, pool_id, contributor)?),
+        QueryMsg::GetRefundOwed { address } => to_binary(&query_refund_owed(deps, &env, address)?),
+        QueryMsg::GetInstallmentBalance => to_binary(
+            &query_installment_balance(deps)
+                .map_err(|err| StdError::generic_err(err.to_string()))?,
+        ),
+        #[cfg(feature = "stats")]
+        QueryMsg::GetBidderStats { bidder } => to_binary(&stats::query_bidder_stats(deps, bidder)?),
+        QueryMsg::GetBidderPosition { bidder } => to_binary(&query_bidder_position(deps, bidder)?),
+        QueryMsg::GetBidCountByBidder { bidder } => {
+            to_binary(&query_bid_count_by_bidder(deps, bidder)?)
+        }
+        QueryMsg::GetRemainingBids { bidder } => {
+            let config = CONFIG.load(deps.storage)?;
+            let remaining = match config.max_bids_per_bidder {
+                Some(limit) => Some(limit.saturating_sub(query_bid_count_by_bidder(deps, bidder)?)),
+                None => None,
+            };
+            to_binary(&remaining)
+        }
+        QueryMsg::GetBidBond { bidder } => {
+            let bidder = deps.api.addr_validate(&bidder)?;
+            to_binary(&BID_BONDS.may_load(deps.storage, &bidder)?)
+        }
+        #[cfg(feature = "debug")]
+        QueryMsg::GetInvariants => to_binary(&debug::query_invariants(deps)?),
+        #[cfg(feature = "history")]
+        QueryMsg::GetSettlements { start_after, limit } => {
+            let config = CONFIG.load(deps.storage)?;
+            let limit = page_limit(&config, limit);
+            to_binary(&history::query_settlements(deps, start_after, limit)?)
+        }
+    }
+}
+
+// The price a Dutch auction sells at right now, or the price to beat in an English one.
+fn query_current_price(deps: Deps, env: &Env) -> StdResult<Uint128> {
+    let config = CONFIG.load(deps.storage)?;
+    if hybrid_phase(deps.storage, &config)? != Some(HybridPhase::English) {
+        if let Some(price) = dutch_price_at(&config, env.block.height) {
+            return Ok(price);
+        }
+    }
+    Ok(match BEST_BID.may_load(deps.storage)? {
+        Some(best_bid) => best_bid.bid_record.price,
+        None => reserve_price_at(&config, env.block.height),
+    })
+}
+
+fn query_bid(deps: Deps, id: Uint64) -> StdResult<BidResponse> {
+    let bid_record = BID_RECORDS.load(deps.storage, id.u64())?;
+    Ok(BidResponse {
+        buyer: bid_record.buyer.into_string(),
+        price: bid_record.price,
+        quantity: bid_record.quantity,
+    })
+}
+
+// Ranks bidders by their highest bid, 1 being the leader.
+// Bids looked at to rank a bidder, newest first. Prices rise from bid to bid, so the newest ones
+// hold every bidder's best that could still lead, and a batch auction never has this many.
+const MAX_POSITION_SCAN: usize = 1_000;
+
+// Ranks bidders by their best bid that still stands: retracted and forfeited bids are left out,
+// and so are bids after the end of a resolved candle auction.
+fn query_bidder_position(deps: Deps, bidder: String) -> StdResult<BidderPositionResponse> {
+    let bidder = deps.api.addr_validate(&bidder)?;
+    let max = match CANDLE_END.may_load(deps.storage)? {
+        Some(candle_end) => {
+            let last_id = CANDLE_SNAPSHOTS
+                .range(
+                    deps.storage,
+                    None,
+                    Some(Bound::inclusive(candle_end)),
+                    Order::Descending,
+                )
+                .next()
+                .transpose()?
+                .map(|(_, id)| id);
+            Some(Bound::inclusive(last_id.unwrap_or_default()))
+        }
+        None => None,
+    };
+    let bids = BID_RECORDS
+        .range(deps.storage, None, max, Order::Descending)
+        .take(MAX_POSITION_SCAN);
+    let mut best_prices: BTreeMap<Addr, Uint128> = BTreeMap::new();
+    for item in bids {
+        let (id, bid_record) = item?;
+        if RETRACTED.has(deps.storage, id) || FORFEITED.has(deps.storage, id) {
+            continue;
+        }
+        let best_price = best_prices.entry(bid_record.buyer).or_default();
+        *best_price = (*best_price).max(bid_record.price);
+    }
+
+    let best_price = match best_prices.get(&bidder) {
+        Some(best_price) => *best_price,
+        None => {
+            return Ok(BidderPositionResponse {
+                rank: None,
+                best_price: None,
+                gap: Uint128::zero(),
+            })
+        }
+    };
+    let leading_price = best_prices.values().max().copied().unwrap_or_default();
+    let rank = best_prices
+        .values()
+        .filter(|price| **price > best_price)
+        .count() as u32
+        + 1;
+
+    Ok(BidderPositionResponse {
+        rank: Some(rank),
+        best_price: Some(best_price),
+        gap: leading_price - best_price,
+    })
+}
+
+fn query_bid_count_by_bidder(deps: Deps, bidder: String) -> StdResult<u64> {
+    let bidder = deps.api.addr_validate(&bidder)?;
+    Ok(BID_COUNTS
+        .may_load(deps.storage, &bidder)?
+        .unwrap_or_default())
+}
+
+fn page_limit(config: &Config, limit: Option<u32>) -> usize {
+    limit.unwrap_or(config.default_limit).min(config.max_limit) as usize
+}
+
+fn query_pool_contribution(
+    deps: Deps,
+    pool_id: u64,
+    contributor: String,
+) -> StdResult<PoolContributionResponse> {
+    let contributor = deps.api.addr_validate(&contributor)?;
+    let pool = POOLS.load(deps.storage, pool_id)?;
+    let contribution = POOL_CONTRIBUTIONS
+        .may_load(deps.storage, (pool_id, &contributor))?
+        .unwrap_or_default();
+    Ok(PoolContributionResponse {
+        contribution,
+        share_bps: share_bps(&pool, contribution),
+        refund: refund_for(&pool, contribution),
+    })
+}
+
+// Escrow behind a standing bid is not owed, and outbid escrow bids are refunded straight away, so
+// what is left to claim is batch escrow above the fills, refunded raffle tickets, a direct offer,
+// a bond that is no longer held and the pools the address can withdraw from.
+fn query_refund_owed(deps: Deps, env: &Env, address: String) -> StdResult<RefundOwedResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let config = CONFIG.load(deps.storage)?;
+    let status = auction_status(deps.storage, &config, &env.block)?;
+
+    let mut batch = Uint128::zero();
+    if matches!(status, AuctionStatus::Settled | AuctionStatus::Cancelled) {
+        let clearing = BATCH_CLEARING.may_load(deps.storage)?;
+        let escrows = BATCH_ESCROW
+            .prefix(&address)
+            .range(deps.storage, None, None, Order::Ascending);
+        for item in escrows {
+            let (id, escrowed) = item?;
+            let cost = match &clearing {
+                Some(clearing) => {
+                    let fill = fill_for(clearing, &BID_RECORDS.load(deps.storage, id)?);
+                    clearing.price.checked_mul(fill)?
+                }
+                None => Uint128::zero(),
+            };
+            batch = batch.checked_add(escrowed.checked_sub(cost)?)?;
+        }
+    }
+
+    let tickets = match config.auction_type {
+        AuctionType::Raffle {
+            ticket_price,
+            refund_losers,
+        } => {
+            let refunded = match status {
+                AuctionStatus::Cancelled | AuctionStatus::Failed => true,
+                AuctionStatus::Settled => refund_losers,
+                _ => false,
+            };
+            match TICKETS.may_load(deps.storage, &address)? {
+                Some(tickets) if refunded => ticket_price.checked_mul(tickets.into())?,
+                _ => Uint128::zero(),
+            }
+        }
+        _ => Uint128::zero(),
+    };
+
+    let offer = match DIRECT_OFFERS.may_load(deps.storage, &address)? {
+        Some(price) => price.checked_mul(config.quantity)?,
+        None => Uint128::zero(),
+    };
+
+    let bond_released = match status {
+        AuctionStatus::Settled | AuctionStatus::Cancelled | AuctionStatus::Failed => true,
+        AuctionStatus::Ended => match BEST_BID.may_load(deps.storage)? {
+            Some(best_bid) => best_bid.bid_record.buyer != address,
+            None => true,
+        },
+        _ => false,
+    };
+    let bond = match BID_BONDS.may_load(deps.storage, &address)? {
+        Some(bond) if bond_released => bond,
+        _ => Uint128::zero(),
+    };
+
+    let mut amount = batch
+        .checked_add(tickets)?
+        .checked_add(offer)?
+        .checked_add(bond)?;
+    let mut pools = vec![];
+    let pool_ids = CONTRIBUTOR_POOLS
+        .prefix(&address)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for pool_id in pool_ids {
+        if pool_bid_standing(deps.storage, &config, &env.block, pool_id)? {
+            continue;
+        }
+        let pool = POOLS.load(deps.storage, pool_id)?;
+        let contribution = POOL_CONTRIBUTIONS.load(deps.storage, (pool_id, &address))?;
+        let refund = refund_for(&pool, contribution);
+        amount = amount.checked_add(refund)?;
+        pools.push(PoolRefund { pool_id, refund });
+    }
+
+    Ok(RefundOwedResponse {
+        amount,
+        batch,
+        tickets,
+        offer,
+        bond,
+        pools,
+    })
+}
+
+fn query_installment_balance(deps: Deps) -> Result<InstallmentBalanceResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let installment_plan = config
+        .installment_plan
+        .as_ref()
+        .ok_or(ContractError::NoInstallmentPlan {})?;
+    let best_bid = BEST_BID.load(deps.storage)?;
+    let total = sale_total(deps.storage, &config, &best_bid)?;
+    let installments = INSTALLMENTS.may_load(deps.storage)?.unwrap_or_default();
+    let remaining_payments = payment_count(installment_plan) - installments.paid_count;
+    let (next_amount, next_due_at) = if remaining_payments == 0 || installments.defaulted {
+        (Uint128::zero(), None)
+    } else {
+        let due_at = installment_due_at(&config, installment_plan, installments.paid_count)?;
+        (
+            installment_amount(total, installment_plan, installments.paid_count),
+            Some(Uint64::new(due_at)),
+        )
+    };
+    Ok(InstallmentBalanceResponse {
+        total,
+        paid: installments.paid,
+        outstanding: total.saturating_sub(installments.paid),
+        remaining_payments,
+        next_amount,
+        next_due_at,
+    })
+}
+
+fn query_bundle(deps: Deps) -> StdResult<BundleResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let items = (0..config.bundle.len() as u32)
+        .zip(config.bundle)
+        .map(|(index, item)| BundleItemResponse {
+            item,
+            deposited: BUNDLE_DEPOSITED.has(deps.storage, index),
+        })
+        .collect();
+    Ok(BundleResponse {
+        api_version: API_VERSION,
+        items,
+    })
+}
+
+fn query_lease(deps: Deps, env: &Env) -> StdResult<Option<LeaseResponse>> {
+    let config = CONFIG.load(deps.storage)?;
+    let (lease_terms, lease) = match (config.lease, LEASE.may_load(deps.storage)?) {
+        (Some(lease_terms), Some(lease)) => (lease_terms, lease),
+        _ => return Ok(None),
+    };
+    let ends_at = lease_period_start(&lease_terms, &lease, lease_terms.periods)?;
+    let next_due_at = if lease.paid_periods < lease_terms.periods {
+        let due_at = lease_period_start(&lease_terms, &lease, lease.paid_periods)?;
+        Some(due_at)
+    } else {
+        None
+    };
+    let height = env.block.height;
+    let overdue = matches!(next_due_at, Some(due_at) if height >= due_at);
+    let active = !lease.ended && height < ends_at && !overdue;
+    Ok(Some(LeaseResponse {
+        lessee: lease.lessee.into_string(),
+        paid_periods: lease.paid_periods,
+        next_due_at,
+        ends_at,
+        active,
+    }))
+}
+
+fn query_vesting(deps: Deps, env: &Env) -> StdResult<Option<VestingResponse>> {
+    let config = CONFIG.load(deps.storage)?;
+    let (payout_vesting, vesting) = match (config.payout_vesting, VESTING.may_load(deps.storage)?) {
+        (Some(payout_vesting), Some(vesting)) => (payout_vesting, vesting),
+        _ => return Ok(None),
+    };
+    let vested = vested_amount(&payout_vesting, &vesting, env.block.height);
+    Ok(Some(VestingResponse {
+        total: vesting.total,
+        vested,
+        claimed: vesting.claimed,
+        claimable: vested - vesting.claimed,
+    }))
+}
+
+fn query_offers(
+    deps: Deps,
+    offers: Map<&Addr, Uint128>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<OffersResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let limit = page_limit(&config, limit);
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let offers = offers
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (buyer, price) = item?;
+            Ok(BidResponse {
+                buyer: buyer.into_string(),
+                price,
+                quantity: config.quantity,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(OffersResponse {
+        api_version: API_VERSION,
+        offers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cw3::{Cw3QueryMsg, ProposalResponse};
+    use crate::state::{IncrementTier, Installments, PriceCurve, ReserveDecay};
+    use crate::vesting::VestingSchedule;
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+        MOCK_CONTRACT_ADDR,
+    };
+    use cosmwasm_std::{
+        coins, from_binary, ContractResult, CosmosMsg, OwnedDeps, QuerierResult, SystemError,
+        SystemResult, WasmQuery,
+    };
+    use cw_utils::PaymentError;
+
+    fn mock_token_info() -> QuerierResult {
+        let res = TokenInfoResponse {
+            name: String::from("Token"),
+            symbol: String::from("TKN"),
+            decimals: 6,
+            total_supply: Uint128::new(1_000_000),
+        };
+        SystemResult::Ok(ContractResult::Ok(to_binary(&res).unwrap()))
+    }
+
+    // "broke" holds no tokens and "stingy" has not approved the contract.
+    fn mock_token_query(msg: &Binary) -> QuerierResult {
+        let res = match from_binary(msg).unwrap() {
+            Cw20QueryMsg::TokenInfo {} => return mock_token_info(),
+            Cw20QueryMsg::Balance { address } => {
+                let balance = if address == "broke" { 0 } else { 1_000 };
+                to_binary(&BalanceResponse {
+                    balance: Uint128::new(balance),
+                })
+            }
+            Cw20QueryMsg::Allowance { owner, .. } => {
+                let allowance = if owner == "stingy" { 0 } else { 1_000 };
+                to_binary(&AllowanceResponse {
+                    allowance: Uint128::new(allowance),
+                    expires: Default::default(),
+                })
+            }
+            _ => panic!("unexpected query"),
+        };
+        SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+    }
+
+    fn mock_dependencies_with_token() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "cw20 token" => {
+                mock_token_query(msg)
+            }
+            WasmQuery::Smart { contract_addr, .. } => {
+                SystemResult::Err(SystemError::NoSuchContract {
+                    addr: contract_addr.clone(),
+                })
+            }
+            _ => panic!("unexpected query"),
+        });
+        deps
+    }
+
+    fn mock_instantiate_msg() -> InstantiateMsg {
+        InstantiateMsg {
+            token_addr: String::from("cw20 token"),
+            reserve_price: Uint128::new(100),
+            increment: Uint128::new(10),
+            duration_in_blocks: Some(Uint64::new(200)),
+            duration: None,
+            expiration: None,
+            payout_delay_in_blocks: None,
+            quantity: None,
+            max_bid_multiplier: None,
+            default_limit: None,
+            max_limit: None,
+            proposal_confirmation: None,
+            reserve_decay: None,
+            allow_self_raise: None,
+            installment_plan: None,
+            event_prefix: None,
+            escrow: None,
+            allow_seller_bids: None,
+            verify_funds: None,
+            settlement_window_in_blocks: None,
+            max_extension: None,
+            soft_close_window: None,
+            extension: None,
+            strict_increments: None,
+            increment_tiers: None,
+            bid_cooldown: None,
+            max_bids_per_bidder: None,
+            retraction_penalty_bps: None,
+            require_exact_payment: None,
+            admin: None,
+            auction_type: None,
+            second_price: None,
+            buy_now_price: None,
+            lot_token: None,
+            reserve_hash: None,
+            seed_hash: None,
+            bundle: None,
+            bid_bond: None,
+            payout_vesting: None,
+            lease: None,
+        }
+    }
+
+    #[test]
+    fn test_instantiate() {
+        let mut deps = mock_dependencies_with_token();
+        let token_addr = String::from("cw20 token");
+        let reserve_price = Uint128::new(100);
+        let increment = Uint128::new(10);
+        let duration_in_blocks = Some(Uint64::new(200));
+        let msg = InstantiateMsg {
+            token_addr,
+            reserve_price,
+            increment,
+            duration_in_blocks,
+            ..mock_instantiate_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(res.attributes.len(), 8);
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetConfig).unwrap();
+        let config: Config = from_binary(&res).unwrap();
+        assert_eq!(config.seller, "creator");
+        assert_eq!(config.token_addr, "cw20 token");
+        assert_eq!(config.reserve_price, reserve_price);
+        assert_eq!(config.increment, increment);
+        assert_eq!(config.timeout, Expiration::AtHeight(200_200));
+        assert_eq!(config.payout_delay, None);
+        assert_eq!(config.default_limit, DEFAULT_LIMIT);
+        assert_eq!(config.max_limit, MAX_LIMIT);
+        assert_eq!(config.token_decimals, 6);
+        assert_eq!(config.token_symbol, "TKN");
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetBidSeq).unwrap();
+        let bid_seq: u64 = from_binary(&res).unwrap();
+        assert_eq!(bid_seq, 0u64);
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetApiVersion).unwrap();
+        let api_version: ApiVersionResponse = from_binary(&res).unwrap();
+        assert_eq!(api_version.api_version, API_VERSION);
+
+        let msg = InstantiateMsg {
+            token_addr: String::from("not a token"),
+            ..mock_instantiate_msg()
+        };
+        let err = instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg)
+            .unwrap_err();
+        match err {
+            ContractError::NotACw20Token { addr } => assert_eq!(addr, "not a token"),
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = InstantiateMsg {
+            duration_in_blocks: Some(Uint64::zero()),
+            ..mock_instantiate_msg()
+        };
+        let err = instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg)
+            .unwrap_err();
+        match err {
+            ContractError::ZeroDuration {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = InstantiateMsg {
+            duration_in_blocks: Some(Uint64::new(MAX_DURATION_IN_BLOCKS + 1)),
+            ..mock_instantiate_msg()
+        };
+        let err = instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg)
+            .unwrap_err();
+        match err {
+            ContractError::DurationOutOfBounds { duration, .. } => {
+                assert_eq!(duration, Uint64::new(MAX_DURATION_IN_BLOCKS + 1))
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = InstantiateMsg {
+            increment: Uint128::zero(),
+            ..mock_instantiate_msg()
+        };
+        let err = instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg)
+            .unwrap_err();
+        match err {
+            ContractError::ZeroIncrement {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let mut overflow_env = env.clone();
+        overflow_env.block.height = u64::MAX - 100;
+        let err = instantiate(
+            deps.as_mut(),
+            overflow_env,
+            mock_info("creator", &[]),
+            mock_instantiate_msg(),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::Overflow(_) => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = InstantiateMsg {
+            reserve_price,
+            increment,
+            duration_in_blocks,
+            default_limit: Some(50),
+            max_limit: Some(20),
+            ..mock_instantiate_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let err = instantiate(deps.as_mut(), env, info, msg).unwrap_err();
+        match err {
+            ContractError::InvalidPaginationLimits { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_bid() {
+        let mut deps = mock_dependencies_with_token();
+        let msg = mock_instantiate_msg();
+        let info = mock_info("creator", &[]);
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(80),
+            quantity: None,
+        };
+        let info = mock_info("buyer", &[]);
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::BidBelowReserve { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(109),
+            quantity: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::IncrementTooLow { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let bid_price = Uint128::new(110);
+        let msg = ExecuteMsg::Bid {
+            price: bid_price,
+            quantity: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(res.attributes.len(), 6);
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetBidSeq).unwrap();
+        let bid_seq: u64 = from_binary(&res).unwrap();
+        assert_eq!(bid_seq, 1u64);
+
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::GetBidRecord {
+                id: Uint64::new(bid_seq),
+            },
+        )
+        .unwrap();
+        let bid_record: BidRecord = from_binary(&res).unwrap();
+        assert_eq!(bid_record.buyer, "buyer");
+        assert_eq!(bid_record.price, bid_price);
+
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::GetBidCountByBidder {
+                bidder: String::from("buyer"),
+            },
+        )
+        .unwrap();
+        let bid_count: u64 = from_binary(&res).unwrap();
+        assert_eq!(bid_count, 1u64);
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetBestBid).unwrap();
+        let best_bid: BestBid = from_binary(&res).unwrap();
+        assert_eq!(best_bid.id, Uint64::new(1));
+        assert_eq!(best_bid.bid_record.buyer, "buyer");
+        assert_eq!(best_bid.bid_record.price, bid_price);
+        assert!(!best_bid.sold);
+
+        let err = execute(deps.as_mut(), env, mock_info("another", &[]), msg).unwrap_err();
+        match err {
+            ContractError::BidNotAboveBest { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(130),
+            quantity: None,
+        };
+        let mut env = mock_env();
+        env.block.height = 200_200;
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        match err {
+            ContractError::AuctionClosed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_buy() {
+        let mut deps = mock_dependencies_with_token();
+        let msg = mock_instantiate_msg();
+        let info = mock_info("creator", &[]);
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        let buyer_info = mock_info("buyer", &[]);
+        execute(deps.as_mut(), env.clone(), buyer_info.clone(), msg).unwrap();
+
+        let token_info = mock_info("cw20 token", &[]);
+
+        let proper_msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(110),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            token_info.clone(),
+            proper_msg.clone(),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::AuctionStillOpen {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("anyone"),
+            amount: Uint128::new(110),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        let mut env = mock_env();
+        env.block.height = 200_300;
+        let err = execute(deps.as_mut(), env.clone(), token_info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // A Receive forged by anyone but the payment token is rejected.
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            buyer_info.clone(),
+            proper_msg.clone(),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::InvalidToken { token } => assert_eq!(token, "buyer"),
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(105),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        let err = execute(deps.as_mut(), env.clone(), token_info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::InsufficientPayment { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            token_info.clone(),
+            proper_msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.attributes.len(), 5);
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(res.events[0].ty, "auction_closed");
+        assert_eq!(res.events[0].attributes[1].value, "110");
+        assert_eq!(res.events[0].attributes[2].value, "true");
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetBestBid).unwrap();
+        let best_bid: BestBid = from_binary(&res).unwrap();
+        assert!(best_bid.sold);
+
+        #[cfg(feature = "stats")]
+        {
+            let res = query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::GetBidderStats {
+                    bidder: String::from("buyer"),
+                },
+            )
+            .unwrap();
+            let stats: stats::BidderStats = from_binary(&res).unwrap();
+            assert_eq!(stats.bids_placed, 1);
+            assert_eq!(stats.auctions_won, 1);
+            assert_eq!(stats.total_spent, Uint128::new(110));
+        }
+
+        #[cfg(feature = "history")]
+        {
+            let res = query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::GetSettlements {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+            let settlements: crate::msg::SettlementsResponse = from_binary(&res).unwrap();
+            assert_eq!(settlements.settlements.len(), 1);
+            assert_eq!(settlements.settlements[0].bid_id, Uint64::new(1));
+            assert_eq!(settlements.settlements[0].price, Uint128::new(110));
+            assert_eq!(settlements.settlements[0].height, Uint64::new(200_300));
+        }
+
+        #[cfg(feature = "debug")]
+        {
+            let res = query(deps.as_ref(), env.clone(), QueryMsg::GetInvariants).unwrap();
+            let invariants: debug::InvariantsResponse = from_binary(&res).unwrap();
+            assert!(invariants.violations.is_empty());
+
+            BID_RECORDS.remove(&mut deps.storage, 1);
+            let res = query(deps.as_ref(), env.clone(), QueryMsg::GetInvariants).unwrap();
+            let invariants: debug::InvariantsResponse = from_binary(&res).unwrap();
+            assert_eq!(invariants.violations.len(), 2);
+            assert!(invariants.violations[0].contains("does not match bid seq"));
+            assert!(invariants.violations[1].contains("without any bid record"));
+            BID_RECORDS
+                .save(&mut deps.storage, 1, &best_bid.bid_record)
+                .unwrap();
+        }
+
+        let err = execute(deps.as_mut(), env, token_info, proper_msg).unwrap_err();
+        match err {
+            ContractError::AlreadySold {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_claim_payout() {
+        let mut deps = mock_dependencies_with_token();
+        let msg = InstantiateMsg {
+            payout_delay_in_blocks: Some(Uint64::new(100)),
+            ..mock_instantiate_msg()
+        };
+        let seller_info = mock_info("creator", &[]);
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        let buyer_info = mock_info("buyer", &[]);
+        execute(deps.as_mut(), env.clone(), buyer_info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(110),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        env.block.height = 200_300;
+        let res = execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+        assert!(res.messages.is_empty());
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetPayout).unwrap();
+        let payout: Payout = from_binary(&res).unwrap();
+        assert_eq!(payout.amount, Uint128::new(110));
+        assert_eq!(payout.claimable_at, Uint64::new(200_400));
+        assert!(!payout.claimed);
+
+        let info = mock_info("anyone", &[]);
+        let msg = ExecuteMsg::ClaimPayout { proposal_id: None };
+        let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            seller_info.clone(),
+            ExecuteMsg::ClaimPayout { proposal_id: None },
+        )
+        .unwrap_err();
+        match err {
+            ContractError::PayoutNotClaimable { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        env.block.height = 200_400;
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            seller_info.clone(),
+            ExecuteMsg::ClaimPayout { proposal_id: None },
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.attributes.len(), 3);
+
+        let msg = ExecuteMsg::ClaimPayout { proposal_id: None };
+        let err = execute(deps.as_mut(), env, seller_info, msg).unwrap_err();
+        match err {
+            ContractError::PayoutAlreadyClaimed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_accept_offer() {
+        let mut deps = mock_dependencies_with_token();
+        let msg = mock_instantiate_msg();
+        let seller_info = mock_info("creator", &[]);
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::Offer {
+            price: Uint128::new(100),
+        };
+        let buyer_info = mock_info("buyer", &[]);
+        let err = execute(deps.as_mut(), env.clone(), buyer_info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::OfferNotBelowReserve { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = ExecuteMsg::Offer {
+            price: Uint128::new(80),
+        };
+        execute(deps.as_mut(), env.clone(), buyer_info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::Offer {
+            price: Uint128::new(90),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("another", &[]), msg).unwrap();
+
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::GetOffers {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+        let offers: OffersResponse = from_binary(&res).unwrap();
+        assert_eq!(offers.api_version, API_VERSION);
+        assert_eq!(offers.offers.len(), 2);
+        assert_eq!(offers.offers[0].buyer, "another");
+        assert_eq!(offers.offers[1].buyer, "buyer");
+
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::GetOffers {
+                start_after: Some(String::from("another")),
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+        let offers: OffersResponse = from_binary(&res).unwrap();
+        assert_eq!(offers.offers.len(), 1);
+        assert_eq!(offers.offers[0].buyer, "buyer");
+
+        let msg = ExecuteMsg::AcceptOffer {
+            buyer: String::from("buyer"),
+        };
+        let err =
+            execute(deps.as_mut(), env.clone(), seller_info.clone(), msg.clone()).unwrap_err();
+        match err {
+            ContractError::AuctionStillOpen {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        env.block.height = 200_300;
+        let err = execute(deps.as_mut(), env.clone(), buyer_info, msg.clone()).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let res = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg.clone()).unwrap();
+        assert_eq!(res.attributes.len(), 4);
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(res.events[0].attributes[2].value, "false");
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetBestBid).unwrap();
+        let best_bid: BestBid = from_binary(&res).unwrap();
+        assert_eq!(best_bid.id, Uint64::new(1));
+        assert_eq!(best_bid.bid_record.buyer, "buyer");
+        assert_eq!(best_bid.bid_record.price, Uint128::new(80));
+
+        let err = execute(deps.as_mut(), env, seller_info, msg).unwrap_err();
+        match err {
+            ContractError::HasWinningBid {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_counteroffer() {
+        let mut deps = mock_dependencies_with_token();
+        let msg = mock_instantiate_msg();
+        let seller_info = mock_info("creator", &[]);
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        let buyer_info = mock_info("buyer", &[]);
+        execute(deps.as_mut(), env.clone(), buyer_info.clone(), msg).unwrap();
+
+        env.block.height = 200_300;
+        let msg = ExecuteMsg::Counteroffer {
+            buyer: String::from("anyone"),
+            price: Uint128::new(150),
+            duration_in_blocks: Uint64::new(50),
+        };
+        let err = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::NoBidOrOffer { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = ExecuteMsg::Counteroffer {
+            buyer: String::from("buyer"),
+            price: Uint128::new(150),
+            duration_in_blocks: Uint64::new(50),
+        };
+        let res = execute(deps.as_mut(), env.clone(), seller_info, msg).unwrap();
+        assert_eq!(res.attributes.len(), 4);
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetCounteroffer).unwrap();
+        let counteroffer: Counteroffer = from_binary(&res).unwrap();
+        assert_eq!(counteroffer.buyer, "buyer");
+        assert_eq!(counteroffer.price, Uint128::new(150));
+        assert_eq!(counteroffer.expires, Uint64::new(200_350));
+
+        let info = mock_info("anyone", &[]);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::AcceptCounteroffer {},
+        )
+        .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            buyer_info.clone(),
+            ExecuteMsg::AcceptCounteroffer {},
+        )
+        .unwrap();
+        assert_eq!(res.attributes.len(), 4);
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetBestBid).unwrap();
+        let best_bid: BestBid = from_binary(&res).unwrap();
+        assert_eq!(best_bid.id, Uint64::new(2));
+        assert_eq!(best_bid.bid_record.price, Uint128::new(150));
+
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(110),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        let err =
+            execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap_err();
+        match err {
+            ContractError::InsufficientPayment { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let err = execute(
+            deps.as_mut(),
+            env,
+            buyer_info,
+            ExecuteMsg::AcceptCounteroffer {},
+        )
+        .unwrap_err();
+        match err {
+            ContractError::Std(_) => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_bid_quantity() {
+        let mut deps = mock_dependencies_with_token();
+        let msg = InstantiateMsg {
+            quantity: Some(Uint128::new(3)),
+            ..mock_instantiate_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: Some(Uint128::new(4)),
+        };
+        let buyer_info = mock_info("buyer", &[]);
+        let err = execute(deps.as_mut(), env.clone(), buyer_info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::InvalidBidQuantity { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: Some(Uint128::new(2)),
+        };
+        execute(deps.as_mut(), env.clone(), buyer_info.clone(), msg).unwrap();
+
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::GetBidRecord { id: Uint64::new(1) },
+        )
+        .unwrap();
+        let bid: BidResponse = from_binary(&res).unwrap();
+        assert_eq!(bid.price, Uint128::new(110));
+        assert_eq!(bid.quantity, Uint128::new(2));
+
+        env.block.height = 200_300;
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(110),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        let err =
+            execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap_err();
+        match err {
+            ContractError::InsufficientPayment { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(220),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        let res = execute(deps.as_mut(), env, mock_info("cw20 token", &[]), msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+    }
+
+    #[test]
+    fn test_bid_price_band() {
+        let mut deps = mock_dependencies_with_token();
+        let msg = InstantiateMsg {
+            max_bid_multiplier: Some(Uint128::new(3)),
+            ..mock_instantiate_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(1_000),
+            quantity: None,
+        };
+        let buyer_info = mock_info("buyer", &[]);
+        let err = execute(deps.as_mut(), env.clone(), buyer_info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::BidOutsidePriceBand { price, max_price } => {
+                assert_eq!(price, Uint128::new(1_000));
+                assert_eq!(max_price, Uint128::new(300));
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(300),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env.clone(), buyer_info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(900),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env, mock_info("another", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn test_claim_payout_with_proposal() {
+        let mut deps = mock_dependencies_with_token();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == "cw20 token" => {
+                mock_token_info()
+            }
+            WasmQuery::Smart { msg, .. } => {
+                let Cw3QueryMsg::Proposal { proposal_id } = from_binary(msg).unwrap();
+                let status = if proposal_id == 1 {
+                    Status::Open
+                } else {
+                    Status::Passed
+                };
+                // Proposal 3 passed but only moves the multisig's own funds.
+                let msg: CosmosMsg = if proposal_id == 3 {
+                    BankMsg::Send {
+                        to_address: String::from("someone"),
+                        amount: coins(10, "ucosm"),
+                    }
+                    .into()
+                } else {
+                    WasmMsg::Execute {
+                        contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                        msg: to_binary(&ExecuteMsg::ClaimPayout {
+                            proposal_id: Some(proposal_id),
+                        })
+                        .unwrap(),
+                        funds: vec![],
+                    }
+                    .into()
+                };
+                let res = ProposalResponse {
+                    id: proposal_id,
+                    msgs: vec![msg],
+                    status,
+                };
+                SystemResult::Ok(ContractResult::Ok(to_binary(&res).unwrap()))
+            }
+            _ => panic!("unexpected query"),
+        });
+        let msg = InstantiateMsg {
+            proposal_confirmation: Some(true),
+            ..mock_instantiate_msg()
+        };
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), mock_info("multisig", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        let buyer_info = mock_info("buyer", &[]);
+        execute(deps.as_mut(), env.clone(), buyer_info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(110),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        env.block.height = 200_300;
+        execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+
+        let info = mock_info("anyone", &[]);
+        let msg = ExecuteMsg::ClaimPayout { proposal_id: None };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::ProposalIdRequired {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = ExecuteMsg::ClaimPayout {
+            proposal_id: Some(1),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::ProposalNotPassed { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = ExecuteMsg::ClaimPayout {
+            proposal_id: Some(3),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::ProposalNotForPayout { proposal_id: 3 } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = ExecuteMsg::ClaimPayout {
+            proposal_id: Some(2),
+        };
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+    }
+
+    #[test]
+    fn test_set_manager() {
+        let mut deps = mock_dependencies_with_token();
+        let msg = mock_instantiate_msg();
+        let seller_info = mock_info("creator", &[]);
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::SetManager {
+            manager: Some(String::from("manager")),
+        };
+        let manager_info = mock_info("manager", &[]);
+        let err =
+            execute(deps.as_mut(), env.clone(), manager_info.clone(), msg.clone()).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        execute(deps.as_mut(), env.clone(), seller_info, msg).unwrap();
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetConfig).unwrap();
         let config: Config = from_binary(&res).unwrap();
-        assert_eq!(config.seller, "creator");
-        assert_eq!(config.token_addr, "cw20 token");
-        assert_eq!(config.reserve_price, reserve_price);
-        assert_eq!(config.increment, increment);
-        assert_eq!(config.timeout, Uint64::new(200_200));
+        assert_eq!(config.manager, Some(Addr::unchecked("manager")));
+
+        let msg = ExecuteMsg::Offer {
+            price: Uint128::new(80),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), msg).unwrap();
+
+        env.block.height = 200_300;
+        let msg = ExecuteMsg::AcceptOffer {
+            buyer: String::from("buyer"),
+        };
+        execute(deps.as_mut(), env.clone(), manager_info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::SetManager { manager: None };
+        let err = execute(deps.as_mut(), env, manager_info, msg).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut deps = mock_dependencies_with_token();
+        let seller_info = mock_info("creator", &coins(10_000, "uatom"));
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), seller_info, mock_instantiate_msg()).unwrap();
+        let msg = ExecuteMsg::SetManager {
+            manager: Some(String::from("manager")),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("buyer", &[]),
+            ExecuteMsg::Cancel {},
+        )
+        .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("manager", &[]),
+            ExecuteMsg::Cancel {},
+        )
+        .unwrap();
+        assert_eq!(res.events[0].ty, "auction_closed");
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("creator"),
+                amount: coins(10_000, "uatom"),
+            })
+        );
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        let err = execute(deps.as_mut(), env, mock_info("buyer", &[]), msg).unwrap_err();
+        match err {
+            ContractError::LotReclaimed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // Once someone has bid the auction runs its course.
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let seller_info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), seller_info.clone(), mock_instantiate_msg())
+            .unwrap();
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), msg).unwrap();
+
+        let err = execute(deps.as_mut(), env, seller_info, ExecuteMsg::Cancel {}).unwrap_err();
+        match err {
+            ContractError::HasBids {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_reserve_decay() {
+        let mut deps = mock_dependencies_with_token();
+        let msg = InstantiateMsg {
+            duration_in_blocks: Some(Uint64::new(10_000)),
+            reserve_decay: Some(ReserveDecay {
+                interval_in_blocks: Uint64::new(1_000),
+                decay_bps: 500,
+                floor: Uint128::new(80),
+            }),
+            ..mock_instantiate_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        env.block.height = 201_999;
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetReservePrice).unwrap();
+        let reserve_price: Uint128 = from_binary(&res).unwrap();
+        assert_eq!(reserve_price, Uint128::new(95));
+
+        env.block.height = 202_000;
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetReservePrice).unwrap();
+        let reserve_price: Uint128 = from_binary(&res).unwrap();
+        assert_eq!(reserve_price, Uint128::new(90));
+
+        env.block.height = 209_000;
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetReservePrice).unwrap();
+        let reserve_price: Uint128 = from_binary(&res).unwrap();
+        assert_eq!(reserve_price, Uint128::new(80));
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(90),
+            quantity: None,
+        };
+        let res = execute(deps.as_mut(), env, mock_info("buyer", &[]), msg).unwrap();
+        assert_eq!(res.attributes.len(), 6);
+    }
+
+    #[test]
+    fn test_self_raise() {
+        let mut deps = mock_dependencies_with_token();
+        let msg = mock_instantiate_msg();
+        let info = mock_info("creator", &[]);
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        let buyer_info = mock_info("buyer", &[]);
+        execute(deps.as_mut(), env.clone(), buyer_info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(120),
+            quantity: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), buyer_info, msg.clone()).unwrap_err();
+        match err {
+            ContractError::AlreadyBestBidder {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        execute(deps.as_mut(), env.clone(), mock_info("another", &[]), msg).unwrap();
+
+        let mut deps = mock_dependencies_with_token();
+        let msg = InstantiateMsg {
+            allow_self_raise: Some(true),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        for price in [110, 120] {
+            let msg = ExecuteMsg::Bid {
+                price: Uint128::new(price),
+                quantity: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), msg).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_seller_bid() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), mock_instantiate_msg())
+            .unwrap();
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg.clone())
+            .unwrap_err();
+        match err {
+            ContractError::SellerCannotBid {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let mut deps = mock_dependencies_with_token();
+        let msg = InstantiateMsg {
+            allow_seller_bids: Some(true),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env, mock_info("creator", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn test_validate_bid() {
+        let mut deps = mock_dependencies_with_token();
+        let env = mock_env();
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), mock_instantiate_msg())
+            .unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        let block = &env.block;
+        let buyer = Addr::unchecked("buyer");
+
+        let total = validate_bid(&config, block, None, &buyer, Uint128::new(110), Uint128::new(1))
+            .unwrap();
+        assert_eq!(total, Uint128::new(110));
+
+        let err = validate_bid(&config, block, None, &buyer, Uint128::new(105), Uint128::new(1))
+            .unwrap_err();
+        match err {
+            ContractError::IncrementTooLow { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let best_bid = BidRecord {
+            buyer: Addr::unchecked("another"),
+            price: Uint128::new(110),
+            quantity: Uint128::new(1),
+        };
+        let err = validate_bid(
+            &config,
+            block,
+            Some(&best_bid),
+            &buyer,
+            Uint128::new(110),
+            Uint128::new(1),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::BidNotAboveBest { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let mut closed_env = env.clone();
+        closed_env.block.height += 200;
+        let err = validate_bid(
+            &config,
+            &closed_env.block,
+            Some(&best_bid),
+            &buyer,
+            Uint128::new(120),
+            Uint128::new(1),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::AuctionClosed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_bidder_position() {
+        let mut deps = mock_dependencies_with_token();
+        let env = mock_env();
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), mock_instantiate_msg())
+            .unwrap();
+
+        for (buyer, price) in [("alice", 110), ("bob", 120), ("carol", 130), ("alice", 140)] {
+            let msg = ExecuteMsg::Bid {
+                price: Uint128::new(price),
+                quantity: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(buyer, &[]), msg).unwrap();
+        }
+
+        let position = |deps: Deps, bidder: &str| -> BidderPositionResponse {
+            let msg = QueryMsg::GetBidderPosition {
+                bidder: String::from(bidder),
+            };
+            from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+        };
+        let alice = position(deps.as_ref(), "alice");
+        assert_eq!(alice.rank, Some(1));
+        assert_eq!(alice.best_price, Some(Uint128::new(140)));
+        assert_eq!(alice.gap, Uint128::zero());
+        let bob = position(deps.as_ref(), "bob");
+        assert_eq!(bob.rank, Some(3));
+        assert_eq!(bob.gap, Uint128::new(20));
+        let dave = position(deps.as_ref(), "dave");
+        assert_eq!(dave.rank, None);
+        assert_eq!(dave.best_price, None);
+
+        // A retracted bid no longer counts towards the bidder's position.
+        RETRACTED
+            .save(&mut deps.storage, 4, &Addr::unchecked("alice"))
+            .unwrap();
+        let alice = position(deps.as_ref(), "alice");
+        assert_eq!(alice.rank, Some(3));
+        assert_eq!(alice.best_price, Some(Uint128::new(110)));
+        assert_eq!(alice.gap, Uint128::new(20));
+
+        // Neither do bids after the end of a candle auction.
+        CANDLE_SNAPSHOTS
+            .save(&mut deps.storage, 12_345, &2)
+            .unwrap();
+        CANDLE_END.save(&mut deps.storage, &12_345).unwrap();
+        assert_eq!(position(deps.as_ref(), "bob").rank, Some(1));
+        assert_eq!(position(deps.as_ref(), "carol").rank, None);
+    }
+
+    #[test]
+    fn test_native_lot() {
+        let mut deps = mock_dependencies_with_token();
+        let seller_info = mock_info("creator", &coins(10_000, "uatom"));
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), seller_info, mock_instantiate_msg()).unwrap();
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), msg).unwrap();
+
+        env.block.height = 200_300;
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::ReclaimLot {},
+        )
+        .unwrap_err();
+        match err {
+            ContractError::HasWinningBid {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(110),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        let res = execute(deps.as_mut(), env, mock_info("cw20 token", &[]), msg).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("buyer"),
+                amount: coins(10_000, "uatom"),
+            })
+        );
+
+        // Nothing sold, so the seller takes the lot back.
+        let mut deps = mock_dependencies_with_token();
+        let seller_info = mock_info("creator", &coins(10_000, "uatom"));
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), seller_info, mock_instantiate_msg()).unwrap();
+        let msg = ExecuteMsg::Offer {
+            price: Uint128::new(80),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), msg).unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::ReclaimLot {},
+        )
+        .unwrap_err();
+        match err {
+            ContractError::AuctionStillOpen {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        env.block.height = 200_300;
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("buyer", &[]),
+            ExecuteMsg::ReclaimLot {},
+        )
+        .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::ReclaimLot {},
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.events[0].ty, "auction_closed");
+
+        let msg = ExecuteMsg::AcceptOffer {
+            buyer: String::from("buyer"),
+        };
+        let err = execute(deps.as_mut(), env, mock_info("creator", &[]), msg).unwrap_err();
+        match err {
+            ContractError::LotReclaimed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_bundle() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let bundle = vec![
+            BundleItemMsg::Cw20 {
+                address: String::from("gem token"),
+                amount: Uint128::new(500),
+            },
+            BundleItemMsg::Cw721 {
+                address: String::from("nft"),
+                token_id: String::from("1"),
+            },
+        ];
+        let msg = InstantiateMsg {
+            bundle: Some(vec![bundle[1].clone(), bundle[1].clone()]),
+            ..mock_instantiate_msg()
+        };
+        let err = instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg)
+            .unwrap_err();
+        match err {
+            ContractError::InvalidBundle {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let msg = InstantiateMsg {
+            bundle: Some(bundle),
+            buy_now_price: Some(Uint128::new(500)),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let bid = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), bid.clone())
+            .unwrap_err();
+        match err {
+            ContractError::LotNotDeposited {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(500),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        let err = execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg)
+            .unwrap_err();
+        match err {
+            ContractError::LotNotDeposited {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let deposit = |amount: u128| {
+            ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+                sender: String::from("creator"),
+                amount: Uint128::new(amount),
+                msg: to_binary(&ReceiveMsg::DepositLot {}).unwrap(),
+            }))
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("gem token", &[]),
+            deposit(400),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::InvalidBundleDeposit {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("gem token", &[]),
+            deposit(500),
+        )
+        .unwrap();
+        let msg = ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: String::from("creator"),
+            token_id: String::from("1"),
+            msg: to_binary(&ReceiveMsg::DepositLot {}).unwrap(),
+        });
+        execute(deps.as_mut(), env.clone(), mock_info("nft", &[]), msg).unwrap();
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetBundle).unwrap();
+        let bundle: BundleResponse = from_binary(&res).unwrap();
+        assert!(bundle.items.iter().all(|item| item.deposited));
+
+        execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), bid).unwrap();
+        env.block.height = 200_300;
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(110),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        let res = execute(deps.as_mut(), env, mock_info("cw20 token", &[]), msg).unwrap();
+        assert_eq!(res.messages.len(), 3);
+        let transfer = Cw20Contract(Addr::unchecked("gem token"))
+            .call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from("buyer"),
+                amount: Uint128::new(500),
+            })
+            .unwrap();
+        assert_eq!(res.messages[1].msg, transfer);
+        assert_eq!(
+            res.messages[2].msg,
+            transfer_nft_msg("nft", "buyer", "1").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_cw1155_bundle() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let tokens = BundleItemMsg::Cw1155 {
+            address: String::from("items"),
+            token_id: String::from("sword"),
+            amount: Uint128::new(5),
+        };
+        let msg = InstantiateMsg {
+            bundle: Some(vec![tokens.clone(), tokens.clone()]),
+            ..mock_instantiate_msg()
+        };
+        let err = instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg)
+            .unwrap_err();
+        match err {
+            ContractError::InvalidBundle {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let msg = InstantiateMsg {
+            bundle: Some(vec![tokens]),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let deposit = |amount: u128| {
+            ExecuteMsg::Receive(TokenReceiveMsg::Cw1155(Cw1155ReceiveMsg {
+                operator: String::from("creator"),
+                from: Some(String::from("creator")),
+                token_id: String::from("sword"),
+                amount: Uint128::new(amount),
+                msg: to_binary(&ReceiveMsg::DepositLot {}).unwrap(),
+            }))
+        };
+        // A cw1155 token calls the same `Receive` as a cw20 token.
+        let json = to_binary(&deposit(5)).unwrap();
+        assert_eq!(from_binary::<ExecuteMsg>(&json).unwrap(), deposit(5));
+        let err = execute(deps.as_mut(), env.clone(), mock_info("items", &[]), deposit(4))
+            .unwrap_err();
+        match err {
+            ContractError::InvalidBundleDeposit {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let bid = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), bid.clone())
+            .unwrap_err();
+        match err {
+            ContractError::LotNotDeposited {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        execute(deps.as_mut(), env.clone(), mock_info("items", &[]), deposit(5)).unwrap();
+
+        execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), bid).unwrap();
+        env.block.height = 200_300;
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(110),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        let json = to_binary(&msg).unwrap();
+        assert_eq!(from_binary::<ExecuteMsg>(&json).unwrap(), msg);
+        let res = execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        let send = send_tokens_msg(
+            "items",
+            env.contract.address.as_str(),
+            "buyer",
+            "sword",
+            Uint128::new(5),
+        )
+        .unwrap();
+        assert_eq!(res.messages[1].msg, send);
+    }
+
+    #[test]
+    fn test_installments() {
+        let mut deps = mock_dependencies_with_token();
+        let msg = InstantiateMsg {
+            installment_plan: Some(InstallmentPlan {
+                count: 3,
+                interval_in_blocks: Uint64::new(100),
+                forfeit_bps: 5_000,
+                down_payment_bps: None,
+            }),
+            ..mock_instantiate_msg()
+        };
+        let seller_info = mock_info("creator", &coins(10_000, "uatom"));
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), seller_info, msg.clone()).unwrap();
+
+        let bid = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), bid.clone()).unwrap();
+
+        let pay = |amount: u128| {
+            ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+                sender: String::from("buyer"),
+                amount: Uint128::new(amount),
+                msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+            }))
+        };
+        let token_info = mock_info("cw20 token", &[]);
+        env.block.height = 200_250;
+        let err = execute(deps.as_mut(), env.clone(), token_info.clone(), pay(30)).unwrap_err();
+        match err {
+            ContractError::InsufficientPayment { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let res = execute(deps.as_mut(), env.clone(), token_info.clone(), pay(36)).unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(res.events[0].ty, "auction_closed");
+
+        env.block.height = 200_350;
+        let res = execute(deps.as_mut(), env.clone(), token_info.clone(), pay(36)).unwrap();
+        assert!(res.messages.is_empty());
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::ClaimDefault {},
+        )
+        .unwrap_err();
+        match err {
+            ContractError::InstallmentNotOverdue { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        env.block.height = 200_450;
+        let res = execute(deps.as_mut(), env.clone(), token_info, pay(38)).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetInstallments).unwrap();
+        let installments: Installments = from_binary(&res).unwrap();
+        assert_eq!(installments.paid, Uint128::new(110));
+        assert_eq!(installments.paid_count, 3);
+        let res = query(deps.as_ref(), env, QueryMsg::GetBestBid).unwrap();
+        let best_bid: BestBid = from_binary(&res).unwrap();
+        assert!(best_bid.sold);
+
+        // The buyer misses the second installment.
+        let mut deps = mock_dependencies_with_token();
+        let seller_info = mock_info("creator", &coins(10_000, "uatom"));
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), seller_info, msg).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), bid).unwrap();
+        env.block.height = 200_250;
+        execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), pay(36)).unwrap();
+
+        env.block.height = 200_400;
+        let err =
+            execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), pay(36)).unwrap_err();
+        match err {
+            ContractError::InstallmentOverdue { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::ClaimDefault {},
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(res.attributes[2].value, "18");
+        assert_eq!(res.attributes[3].value, "18");
+
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("creator", &[]),
+            ExecuteMsg::ReclaimLot {},
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
+    }
+
+    #[test]
+    fn test_installment_down_payment() {
+        let mut deps = mock_dependencies_with_token();
+        let msg = InstantiateMsg {
+            installment_plan: Some(InstallmentPlan {
+                count: 2,
+                interval_in_blocks: Uint64::new(100),
+                forfeit_bps: 5_000,
+                down_payment_bps: Some(2_000),
+            }),
+            ..mock_instantiate_msg()
+        };
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let bid = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), bid).unwrap();
+
+        let pay = |amount: u128| {
+            ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+                sender: String::from("buyer"),
+                amount: Uint128::new(amount),
+                msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+            }))
+        };
+        env.block.height = 200_250;
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetInstallmentBalance).unwrap();
+        let balance: InstallmentBalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(balance.remaining_payments, 3);
+        assert_eq!(balance.next_amount, Uint128::new(22));
+        assert_eq!(balance.next_due_at, Some(Uint64::new(200_300)));
+        execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), pay(22)).unwrap();
+
+        env.block.height = 200_350;
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetInstallmentBalance).unwrap();
+        let balance: InstallmentBalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(balance.outstanding, Uint128::new(88));
+        assert_eq!(balance.next_amount, Uint128::new(44));
+        execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), pay(44)).unwrap();
+
+        // The second installment is missed, so the seller keeps the down payment and half of the
+        // first installment.
+        env.block.height = 200_500;
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::ClaimDefault {},
+        )
+        .unwrap();
+        assert_eq!(res.attributes[2].value, "44");
+        assert_eq!(res.attributes[3].value, "22");
+        let res = query(deps.as_ref(), env, QueryMsg::GetInstallmentBalance).unwrap();
+        let balance: InstallmentBalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(balance.remaining_payments, 1);
+        assert_eq!(balance.next_due_at, None);
+    }
+
+    #[test]
+    fn test_event_prefix() {
+        let mut deps = mock_dependencies_with_token();
+        let msg = InstantiateMsg {
+            event_prefix: Some(String::from("market a")),
+            ..mock_instantiate_msg()
+        };
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let err = instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg)
+            .unwrap_err();
+        match err {
+            ContractError::InvalidEventPrefix { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = InstantiateMsg {
+            event_prefix: Some(String::from("market_a")),
+            ..mock_instantiate_msg()
+        };
+        let res = instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(res.attributes[0].value, "market_a_instantiate");
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), msg).unwrap();
+        assert_eq!(res.attributes[0].value, "market_a_execute_bid");
+
+        env.block.height = 200_300;
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(110),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        let res = execute(deps.as_mut(), env, mock_info("cw20 token", &[]), msg).unwrap();
+        assert_eq!(res.events[0].ty, "market_a_auction_closed");
+        assert_eq!(res.attributes[0].value, "market_a_receive_buy");
+    }
+
+    #[test]
+    fn test_close() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), mock_instantiate_msg())
+            .unwrap();
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), msg.clone()).unwrap();
+
+        let anyone_info = mock_info("anyone", &[]);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            anyone_info.clone(),
+            ExecuteMsg::Close {},
+        )
+        .unwrap_err();
+        match err {
+            ContractError::AuctionStillOpen {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        env.block.height = 200_200;
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            anyone_info.clone(),
+            ExecuteMsg::Close {},
+        )
+        .unwrap();
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(res.events[0].ty, "auction_closed");
+        assert_eq!(res.events[0].attributes[2].value, "true");
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            anyone_info,
+            ExecuteMsg::Close {},
+        )
+        .unwrap_err();
+        match err {
+            ContractError::AuctionClosed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let err = execute(deps.as_mut(), env.clone(), mock_info("another", &[]), msg).unwrap_err();
+        match err {
+            ContractError::AuctionClosed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(110),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        let res = execute(deps.as_mut(), env, mock_info("cw20 token", &[]), msg).unwrap();
+        assert!(res.events.is_empty());
+    }
+
+    #[test]
+    fn test_escrow_bid() {
+        let mut deps = mock_dependencies_with_token();
+        let msg = InstantiateMsg {
+            escrow: Some(true),
+            ..mock_instantiate_msg()
+        };
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap_err();
+        match err {
+            ContractError::EscrowRequired {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let bid = |sender: &str, price: u128, amount: u128| {
+            ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+                sender: String::from(sender),
+                amount: Uint128::new(amount),
+                msg: to_binary(&ReceiveMsg::Bid {
+                    price: Uint128::new(price),
+                    quantity: None,
+                })
+                .unwrap(),
+            }))
+        };
+        let token_info = mock_info("cw20 token", &[]);
+        let err = execute(deps.as_mut(), env.clone(), token_info.clone(), bid("alice", 110, 100))
+            .unwrap_err();
+        match err {
+            ContractError::InsufficientPayment { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let res = execute(deps.as_mut(), env.clone(), token_info.clone(), bid("alice", 110, 110))
+            .unwrap();
+        assert!(res.messages.is_empty());
+
+        // Bob outbids Alice, whose escrow is returned in the same transaction.
+        let res = execute(deps.as_mut(), env.clone(), token_info.clone(), bid("bob", 120, 120))
+            .unwrap();
+        let cw20 = Cw20Contract(Addr::unchecked("cw20 token"));
+        let refund = cw20
+            .call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from("alice"),
+                amount: Uint128::new(110),
+            })
+            .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, refund);
+        let event = res.events.iter().find(|event| event.ty == "refund").unwrap();
+        let values: Vec<_> = event.attributes.iter().map(|attr| attr.value.as_str()).collect();
+        assert_eq!(values, ["alice", "110", "cw20 token", "outbid"]);
+
+        env.block.height = 200_300;
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("bob"),
+            amount: Uint128::new(120),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        let err = execute(deps.as_mut(), env.clone(), token_info, msg).unwrap_err();
+        match err {
+            ContractError::AlreadyEscrowed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::Close {},
+        )
+        .unwrap();
+        let payment = cw20
+            .call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from("creator"),
+                amount: Uint128::new(120),
+            })
+            .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, payment);
+        let res = query(deps.as_ref(), env, QueryMsg::GetBestBid).unwrap();
+        let best_bid: BestBid = from_binary(&res).unwrap();
+        assert_eq!(best_bid.bid_record.buyer, "bob");
+        assert!(best_bid.sold);
+    }
+
+    #[test]
+    fn test_escrow_bid_overpayment() {
+        let mut deps = mock_dependencies_with_token();
+        let msg = InstantiateMsg {
+            escrow: Some(true),
+            ..mock_instantiate_msg()
+        };
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("alice"),
+            amount: Uint128::new(150),
+            msg: to_binary(&ReceiveMsg::Bid {
+                price: Uint128::new(110),
+                quantity: None,
+            })
+            .unwrap(),
+        }));
+        execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+
+        // The seller gets the bid and the rest of the escrow goes back to Alice.
+        env.block.height = 200_300;
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            ExecuteMsg::Close {},
+        )
+        .unwrap();
+        let cw20 = Cw20Contract(Addr::unchecked("cw20 token"));
+        let payment = cw20
+            .call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from("creator"),
+                amount: Uint128::new(110),
+            })
+            .unwrap();
+        let refund = cw20
+            .call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from("alice"),
+                amount: Uint128::new(40),
+            })
+            .unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(res.messages[0].msg, payment);
+        assert_eq!(res.messages[1].msg, refund);
+    }
+
+    #[test]
+    fn test_buy_overpayment() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), mock_instantiate_msg())
+            .unwrap();
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), msg).unwrap();
+
+        env.block.height = 200_300;
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(150),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        let res = execute(deps.as_mut(), env, mock_info("cw20 token", &[]), msg).unwrap();
+        let cw20 = Cw20Contract(Addr::unchecked("cw20 token"));
+        let payment = cw20
+            .call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from("creator"),
+                amount: Uint128::new(110),
+            })
+            .unwrap();
+        let refund = cw20
+            .call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from("buyer"),
+                amount: Uint128::new(40),
+            })
+            .unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(res.messages[0].msg, payment);
+        assert_eq!(res.messages[1].msg, refund);
+        assert_eq!(res.attributes[3].value, "110");
+        assert_eq!(res.attributes[4].value, "40");
+    }
+
+    #[test]
+    fn test_require_exact_payment() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            require_exact_payment: Some(true),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), msg).unwrap();
+
+        env.block.height = 200_300;
+        let buy = |amount: u128| {
+            ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+                sender: String::from("buyer"),
+                amount: Uint128::new(amount),
+                msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+            }))
+        };
+        for amount in [100, 150] {
+            let info = mock_info("cw20 token", &[]);
+            let err = execute(deps.as_mut(), env.clone(), info, buy(amount)).unwrap_err();
+            match err {
+                ContractError::IncorrectPaymentAmount { required, .. } => {
+                    assert_eq!(required, Uint128::new(110))
+                }
+                e => panic!("unexpected error: {}", e),
+            }
+        }
+        let res = execute(deps.as_mut(), env, mock_info("cw20 token", &[]), buy(110)).unwrap();
+        assert_eq!(res.messages.len(), 1);
+    }
+
+    #[test]
+    fn test_verify_funds() {
+        let mut deps = mock_dependencies_with_token();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            verify_funds: Some(true),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("broke", &[]), msg.clone())
+            .unwrap_err();
+        match err {
+            ContractError::InsufficientFunds {
+                balance, required, ..
+            } => {
+                assert_eq!(balance, Uint128::zero());
+                assert_eq!(required, Uint128::new(110));
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+        let err = execute(deps.as_mut(), env.clone(), mock_info("stingy", &[]), msg.clone())
+            .unwrap_err();
+        match err {
+            ContractError::InsufficientFunds { allowance, .. } => {
+                assert_eq!(allowance, Uint128::zero())
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+        execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(1_010),
+            quantity: None,
+        };
+        let err = execute(deps.as_mut(), env, mock_info("another", &[]), msg).unwrap_err();
+        match err {
+            ContractError::InsufficientFunds { required, .. } => {
+                assert_eq!(required, Uint128::new(1_010))
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_settlement_window() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            settlement_window_in_blocks: Some(Uint64::new(20)),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(120),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("another", &[]), msg).unwrap();
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetRunnerUp).unwrap();
+        let runner_up: Option<BestBid> = from_binary(&res).unwrap();
+        assert_eq!(runner_up.unwrap().bid_record.buyer, "buyer");
+
+        env.block.height = 200_210;
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::ForfeitWinner {},
+        )
+        .unwrap_err();
+        match err {
+            ContractError::SettlementWindowOpen { deadline } => assert_eq!(deadline, 200_220),
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // The winner can no longer pay once the window has passed.
+        env.block.height = 200_220;
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("another"),
+            amount: Uint128::new(120),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        let err = execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg)
+            .unwrap_err();
+        match err {
+            ContractError::SettlementWindowExpired { deadline } => assert_eq!(deadline, 200_220),
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::ForfeitWinner {},
+        )
+        .unwrap();
+        assert_eq!(res.attributes[1].value, "another");
+        assert_eq!(res.attributes[2].value, "buyer");
+        assert_eq!(res.attributes[3].value, "200240");
+        let best_bid = BEST_BID.load(&deps.storage).unwrap();
+        assert_eq!(best_bid.bid_record.buyer, "buyer");
+        assert_eq!(RUNNER_UP.may_load(&deps.storage).unwrap(), None);
+
+        #[cfg(feature = "debug")]
+        {
+            let res = query(deps.as_ref(), env.clone(), QueryMsg::GetInvariants).unwrap();
+            let invariants: debug::InvariantsResponse = from_binary(&res).unwrap();
+            assert!(invariants.violations.is_empty());
+        }
+
+        // The runner-up gets a fresh window to pay.
+        env.block.height = 200_230;
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(110),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+        assert!(BEST_BID.load(&deps.storage).unwrap().sold);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::ForfeitWinner {},
+        )
+        .unwrap_err();
+        match err {
+            ContractError::AlreadySold {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // Without a runner-up the winning bid is dropped and offers can be accepted again.
+        let mut deps = mock_dependencies_with_token();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            settlement_window_in_blocks: Some(Uint64::new(20)),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Offer {
+            price: Uint128::new(90),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("bargain", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), msg).unwrap();
+        env.block.height = 200_220;
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::ForfeitWinner {},
+        )
+        .unwrap();
+        assert_eq!(BEST_BID.may_load(&deps.storage).unwrap(), None);
+        let msg = ExecuteMsg::AcceptOffer {
+            buyer: String::from("bargain"),
+        };
+        execute(deps.as_mut(), env, mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(BEST_BID.load(&deps.storage).unwrap().bid_record.buyer, "bargain");
+    }
+
+    #[test]
+    fn test_dutch_auction() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            auction_type: Some(AuctionType::Dutch {
+                start_price: Uint128::new(1_000),
+                floor_price: Uint128::new(200),
+                curve: PriceCurve::Linear,
+            }),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        env.block.height = 200_100;
+        let price: Uint128 =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::GetCurrentPrice).unwrap())
+                .unwrap();
+        assert_eq!(price, Uint128::new(600));
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(600),
+            quantity: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), msg).unwrap_err();
+        match err {
+            ContractError::NotEnglishAuction {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(700),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        let res = execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg.clone())
+            .unwrap();
+        let cw20 = Cw20Contract(Addr::unchecked("cw20 token"));
+        let payment = cw20
+            .call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from("creator"),
+                amount: Uint128::new(600),
+            })
+            .unwrap();
+        assert_eq!(res.messages[0].msg, payment);
+        assert_eq!(res.attributes[4].value, "100");
+        let status: AuctionStatus =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::GetStatus).unwrap()).unwrap();
+        assert_eq!(status, AuctionStatus::Settled);
+
+        let err = execute(deps.as_mut(), env, mock_info("cw20 token", &[]), msg).unwrap_err();
+        match err {
+            ContractError::AlreadySold {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_hybrid_auction() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            auction_type: Some(AuctionType::Hybrid {
+                start_price: Uint128::new(1_000),
+                floor_price: Uint128::new(200),
+                curve: PriceCurve::Linear,
+                english_window_in_blocks: Uint64::new(50),
+            }),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let phase = |deps: &OwnedDeps<_, _, _>| {
+            let res = query(deps.as_ref(), mock_env(), QueryMsg::GetHybridPhase).unwrap();
+            from_binary::<Option<HybridPhase>>(&res).unwrap()
+        };
+        assert_eq!(phase(&deps), Some(HybridPhase::Dutch));
+
+        // The first bid only has to meet the descending price.
+        env.block.height = 200_100;
+        let bid = |price: u128| ExecuteMsg::Bid {
+            price: Uint128::new(price),
+            quantity: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), bid(590))
+            .unwrap_err();
+        match err {
+            ContractError::BidBelowReserve { reserve, .. } => {
+                assert_eq!(reserve, Uint128::new(600))
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+        let res = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), bid(600)).unwrap();
+        assert_eq!(res.events[0].ty, "english_phase_opened");
+        assert_eq!(res.events[0].attributes[0].value, "expiration height: 200150");
+        assert_eq!(phase(&deps), Some(HybridPhase::English));
+
+        // From then on bids ascend and the price no longer falls.
+        env.block.height = 200_140;
+        let price: Uint128 =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::GetCurrentPrice).unwrap())
+                .unwrap();
+        assert_eq!(price, Uint128::new(600));
+        let err = execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), bid(550)).unwrap_err();
+        match err {
+            ContractError::BidNotAboveBest { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), bid(700)).unwrap();
+
+        env.block.height = 200_150;
+        let err = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), bid(800))
+            .unwrap_err();
+        match err {
+            ContractError::AuctionClosed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("bob"),
+            amount: Uint128::new(700),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+        let status: AuctionStatus =
+            from_binary(&query(deps.as_ref(), env, QueryMsg::GetStatus).unwrap()).unwrap();
+        assert_eq!(status, AuctionStatus::Settled);
+    }
+
+    #[test]
+    fn test_penny_auction() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            auction_type: Some(AuctionType::Penny {
+                bid_fee: Uint128::new(5),
+                price_step: Uint128::new(1),
+                extension_in_blocks: Uint64::new(20),
+            }),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap_err();
+        match err {
+            ContractError::NotEnglishAuction {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let penny_bid = |bidder: &str| {
+            ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+                sender: String::from(bidder),
+                amount: Uint128::new(5),
+                msg: to_binary(&ReceiveMsg::PennyBid {}).unwrap(),
+            }))
+        };
+        let token_info = mock_info("cw20 token", &[]);
+        let res = execute(deps.as_mut(), env.clone(), token_info.clone(), penny_bid("alice"))
+            .unwrap();
+        assert!(res.events.is_empty());
+        assert_eq!(res.attributes[3].value, "101");
+        let err = execute(deps.as_mut(), env.clone(), token_info.clone(), penny_bid("alice"))
+            .unwrap_err();
+        match err {
+            ContractError::AlreadyBestBidder {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // A bid close to the end pushes the timeout out.
+        env.block.height = 200_190;
+        let res = execute(deps.as_mut(), env.clone(), token_info.clone(), penny_bid("bob"))
+            .unwrap();
+        assert_eq!(res.events[0].ty, "deadline_extended");
+        assert_eq!(res.attributes[3].value, "102");
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetDeadline).unwrap();
+        assert_eq!(
+            from_binary::<Expiration>(&res).unwrap(),
+            Expiration::AtHeight(200_210)
+        );
+
+        env.block.height = 200_210;
+        let err = execute(deps.as_mut(), env.clone(), token_info.clone(), penny_bid("alice"))
+            .unwrap_err();
+        match err {
+            ContractError::AuctionClosed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("bob"),
+            amount: Uint128::new(102),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        execute(deps.as_mut(), env.clone(), token_info, msg).unwrap();
+
+        // The fees of every bid, the loser's included, go to the seller on top of the price.
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetFeePool).unwrap();
+        assert_eq!(from_binary::<Uint128>(&res).unwrap(), Uint128::new(10));
+        let msg = ExecuteMsg::ClaimBidFees {};
+        let res = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg.clone())
+            .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            Cw20Contract(Addr::unchecked("cw20 token"))
+                .call(Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("creator"),
+                    amount: Uint128::new(10),
+                })
+                .unwrap()
+        );
+        let err = execute(deps.as_mut(), env, mock_info("creator", &[]), msg).unwrap_err();
+        match err {
+            ContractError::NoBidFees {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_sealed_auction() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            auction_type: Some(AuctionType::Sealed {
+                reveal_period_in_blocks: Uint64::new(100),
+                deposit: Uint128::new(50),
+            }),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let commit = |bidder: &str, price: u128, salt: &str| {
+            ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+                sender: String::from(bidder),
+                amount: Uint128::new(50),
+                msg: to_binary(&ReceiveMsg::Commit {
+                    commitment: sealed_commitment(Uint128::new(price), salt),
+                })
+                .unwrap(),
+            }))
+        };
+        for (bidder, price, salt) in [("alice", 150, "a"), ("bob", 130, "b"), ("carol", 200, "c")] {
+            let msg = commit(bidder, price, salt);
+            execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+        }
+        let reveal = |price: u128, salt: &str| ExecuteMsg::Reveal {
+            price: Uint128::new(price),
+            salt: String::from(salt),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), reveal(130, "b"))
+            .unwrap_err();
+        match err {
+            ContractError::AuctionStillOpen {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        env.block.height = 200_250;
+        let status: AuctionStatus =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::GetStatus).unwrap()).unwrap();
+        assert_eq!(status, AuctionStatus::Revealing);
+        let err = execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), reveal(130, "x"))
+            .unwrap_err();
+        match err {
+            ContractError::InvalidReveal {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        // The leading bid's deposit is held, and returned once it is outbid.
+        let res =
+            execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), reveal(130, "b")).unwrap();
+        assert!(res.messages.is_empty());
+        let res =
+            execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), reveal(150, "a")).unwrap();
+        let cw20 = Cw20Contract(Addr::unchecked("cw20 token"));
+        let refund = |recipient: &str| {
+            cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from(recipient),
+                amount: Uint128::new(50),
+            })
+            .unwrap()
+        };
+        assert_eq!(res.messages[0].msg, refund("bob"));
+        let best_bid: BestBid =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::GetBestBid).unwrap()).unwrap();
+        assert_eq!(best_bid.bid_record.buyer, Addr::unchecked("alice"));
+
+        env.block.height = 200_300;
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::SlashUnrevealed {},
+        )
+        .unwrap();
+        assert_eq!(res.messages[0].msg, refund("creator"));
+
+        // The winner gets the deposit back along with the lot on payment.
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("alice"),
+            amount: Uint128::new(150),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        let res = execute(deps.as_mut(), env, mock_info("cw20 token", &[]), msg).unwrap();
+        assert_eq!(res.messages[1].msg, refund("alice"));
+
+        // A winner who does not pay in time forfeits the deposit to the seller.
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            auction_type: Some(AuctionType::Sealed {
+                reveal_period_in_blocks: Uint64::new(100),
+                deposit: Uint128::new(50),
+            }),
+            settlement_window_in_blocks: Some(Uint64::new(150)),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let msg = commit("alice", 150, "a");
+        execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+        env.block.height = 200_250;
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), reveal(150, "a")).unwrap();
+        // The window runs from the end of the reveal phase.
+        env.block.height = 200_449;
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::ForfeitWinner {},
+        )
+        .unwrap_err();
+        match err {
+            ContractError::SettlementWindowOpen { deadline } => assert_eq!(deadline, 200_450),
+            e => panic!("unexpected error: {}", e),
+        }
+        env.block.height = 200_450;
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            ExecuteMsg::ForfeitWinner {},
+        )
+        .unwrap();
+        assert_eq!(res.messages[0].msg, refund("creator"));
+
+        // Cancelling the auction refunds every commitment, and leaves nothing to slash.
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            auction_type: Some(AuctionType::Sealed {
+                reveal_period_in_blocks: Uint64::new(100),
+                deposit: Uint128::new(50),
+            }),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let msg = commit("alice", 150, "a");
+        execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+        let res = sudo(deps.as_mut(), env.clone(), SudoMsg::ForceCancelAndRefund {}).unwrap();
+        assert_eq!(res.messages[0].msg, refund("alice"));
+        env.block.height = 200_300;
+        let err = execute(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            ExecuteMsg::SlashUnrevealed {},
+        )
+        .unwrap_err();
+        match err {
+            ContractError::LotReclaimed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_second_price() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            second_price: Some(true),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        for (bidder, price) in [("alice", 150), ("bob", 200)] {
+            let msg = ExecuteMsg::Bid {
+                price: Uint128::new(price),
+                quantity: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(bidder, &[]), msg).unwrap();
+        }
+
+        env.block.height = 200_300;
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("bob"),
+            amount: Uint128::new(200),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        let res = execute(deps.as_mut(), env, mock_info("cw20 token", &[]), msg).unwrap();
+        let cw20 = Cw20Contract(Addr::unchecked("cw20 token"));
+        let payment = cw20
+            .call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from("creator"),
+                amount: Uint128::new(160),
+            })
+            .unwrap();
+        let refund = cw20
+            .call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from("bob"),
+                amount: Uint128::new(40),
+            })
+            .unwrap();
+        assert_eq!(res.messages[0].msg, payment);
+        assert_eq!(res.messages[1].msg, refund);
+    }
+
+    #[test]
+    fn test_buy_now() {
+        let mut deps = mock_dependencies_with_token();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            escrow: Some(true),
+            buy_now_price: Some(Uint128::new(500)),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("alice"),
+            amount: Uint128::new(150),
+            msg: to_binary(&ReceiveMsg::Bid {
+                price: Uint128::new(150),
+                quantity: None,
+            })
+            .unwrap(),
+        }));
+        execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+
+        let buy = |amount: u128| {
+            ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+                sender: String::from("bob"),
+                amount: Uint128::new(amount),
+                msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+            }))
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), buy(400))
+            .unwrap_err();
+        match err {
+            ContractError::InsufficientPayment { required, .. } => {
+                assert_eq!(required, Uint128::new(500))
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+        let res =
+            execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), buy(500)).unwrap();
+        let cw20 = Cw20Contract(Addr::unchecked("cw20 token"));
+        let payment = cw20
+            .call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from("creator"),
+                amount: Uint128::new(500),
+            })
+            .unwrap();
+        let refund = cw20
+            .call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from("alice"),
+                amount: Uint128::new(150),
+            })
+            .unwrap();
+        assert_eq!(res.messages[0].msg, payment);
+        assert_eq!(res.messages[1].msg, refund);
+        assert!(res.events.iter().any(|event| event.ty == "buy_now"));
+
+        let status: AuctionStatus =
+            from_binary(&query(deps.as_ref(), env, QueryMsg::GetStatus).unwrap()).unwrap();
+        assert_eq!(status, AuctionStatus::Settled);
+    }
+
+    #[test]
+    fn test_candle_auction() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            auction_type: Some(AuctionType::Candle {
+                window_in_blocks: Uint64::new(100),
+            }),
+            seed_hash: Some(seed_commitment("candle seed")),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        for (bidder, price, height) in [
+            ("alice", 150, 200_000),
+            ("bob", 200, 200_150),
+            ("carol", 300, 200_199),
+        ] {
+            env.block.height = height;
+            let msg = ExecuteMsg::Bid {
+                price: Uint128::new(price),
+                quantity: None,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(bidder, &[]), msg).unwrap();
+        }
+
+        // The end is only drawn once the seller reveals the seed.
+        env.block.height = 200_200;
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::Close {},
+        )
+        .unwrap_err();
+        match err {
+            ContractError::AuctionRevealing {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let candle_end: Option<u64> =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::GetCandleEnd).unwrap())
+                .unwrap();
+        assert_eq!(candle_end, None);
+        let msg = ExecuteMsg::RevealSeed {
+            seed: String::from("candle seed"),
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        assert!(res.events.iter().any(|event| event.ty == "candle_resolved"));
+        let candle_end: Option<u64> =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::GetCandleEnd).unwrap())
+                .unwrap();
+        let candle_end = candle_end.unwrap();
+        assert!((200_100..200_200).contains(&candle_end));
+        let winner = match candle_end {
+            200_199 => "carol",
+            200_150..=200_198 => "bob",
+            _ => "alice",
+        };
+        let best_bid: BestBid =
+            from_binary(&query(deps.as_ref(), env, QueryMsg::GetBestBid).unwrap()).unwrap();
+        assert_eq!(best_bid.bid_record.buyer, Addr::unchecked(winner));
+    }
+
+    #[test]
+    fn test_batch_auction() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            auction_type: Some(AuctionType::Batch),
+            escrow: Some(true),
+            lot_token: Some(String::from("lot token")),
+            quantity: Some(Uint128::new(100)),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let bid = |bidder: &str, price: u128, quantity: u128| {
+            ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+                sender: String::from(bidder),
+                amount: Uint128::new(price * quantity),
+                msg: to_binary(&ReceiveMsg::Bid {
+                    price: Uint128::new(price),
+                    quantity: Some(Uint128::new(quantity)),
+                })
+                .unwrap(),
+            }))
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("cw20 token", &[]),
+            bid("alice", 150, 60),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::LotNotDeposited {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("creator"),
+            amount: Uint128::new(100),
+            msg: to_binary(&ReceiveMsg::DepositLot {}).unwrap(),
+        }));
+        execute(deps.as_mut(), env.clone(), mock_info("lot token", &[]), msg).unwrap();
+        for (bidder, price, quantity) in [
+            ("alice", 150, 60),
+            ("bob", 120, 50),
+            ("carol", 120, 30),
+            ("dave", 110, 10),
+        ] {
+            let msg = bid(bidder, price, quantity);
+            execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+        }
+
+        env.block.height = 200_300;
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::Close {},
+        )
+        .unwrap();
+        let cw20 = Cw20Contract(Addr::unchecked("cw20 token"));
+        let lot_token = Cw20Contract(Addr::unchecked("lot token"));
+        let transfer = |token: &Cw20Contract, recipient: &str, amount: u128| {
+            token
+                .call(Cw20ExecuteMsg::Transfer {
+                    recipient: String::from(recipient),
+                    amount: Uint128::new(amount),
+                })
+                .unwrap()
+        };
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, transfer(&cw20, "creator", 12_000));
+
+        // Bob and Carol share what is left at the clearing price of 120 pro rata.
+        for (bidder, filled, refund) in [("alice", 60, 1_800), ("bob", 25, 3_000)] {
+            let msg = ExecuteMsg::ClaimBatchFill {};
+            let res = execute(deps.as_mut(), env.clone(), mock_info(bidder, &[]), msg).unwrap();
+            assert_eq!(res.messages[0].msg, transfer(&lot_token, bidder, filled));
+            assert_eq!(res.messages[1].msg, transfer(&cw20, bidder, refund));
+        }
+        let msg = ExecuteMsg::ClaimBatchFill {};
+        let res = execute(deps.as_mut(), env.clone(), mock_info("dave", &[]), msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, transfer(&cw20, "dave", 1_100));
+        let msg = ExecuteMsg::ClaimBatchFill {};
+        let err = execute(deps.as_mut(), env, mock_info("dave", &[]), msg).unwrap_err();
+        match err {
+            ContractError::NoBidOrOffer { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_batch_auction_limits() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            auction_type: Some(AuctionType::Batch),
+            escrow: Some(true),
+            lot_token: Some(String::from("lot token")),
+            quantity: Some(Uint128::new(100)),
+            ..mock_instantiate_msg()
+        };
+        let decaying = InstantiateMsg {
+            reserve_decay: Some(ReserveDecay {
+                interval_in_blocks: Uint64::new(10),
+                decay_bps: 100,
+                floor: Uint128::new(50),
+            }),
+            ..msg.clone()
+        };
+        let err = instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), decaying)
+            .unwrap_err();
+        match err {
+            ContractError::UnsupportedByAuctionType {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("creator"),
+            amount: Uint128::new(100),
+            msg: to_binary(&ReceiveMsg::DepositLot {}).unwrap(),
+        }));
+        execute(deps.as_mut(), env.clone(), mock_info("lot token", &[]), msg).unwrap();
+
+        BID_SEQ.save(&mut deps.storage, &MAX_BATCH_BIDS).unwrap();
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("alice"),
+            amount: Uint128::new(150),
+            msg: to_binary(&ReceiveMsg::Bid {
+                price: Uint128::new(150),
+                quantity: None,
+            })
+            .unwrap(),
+        }));
+        let err = execute(deps.as_mut(), env, mock_info("cw20 token", &[]), msg).unwrap_err();
+        match err {
+            ContractError::BatchFull { max } => assert_eq!(max, MAX_BATCH_BIDS),
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_raffle() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            auction_type: Some(AuctionType::Raffle {
+                ticket_price: Uint128::new(10),
+                refund_losers: true,
+            }),
+            ..mock_instantiate_msg()
+        };
+        let err = instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg.clone())
+            .unwrap_err();
+        match err {
+            ContractError::SeedHashRequired {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let msg = InstantiateMsg {
+            seed_hash: Some(seed_commitment("raffle seed")),
+            ..msg
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let buy = |buyer: &str, amount: u128, count: u64| {
+            ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+                sender: String::from(buyer),
+                amount: Uint128::new(amount),
+                msg: to_binary(&ReceiveMsg::BuyTickets {
+                    count: Uint64::new(count),
+                })
+                .unwrap(),
+            }))
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("cw20 token", &[]),
+            buy("alice", 20, 3),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::InsufficientPayment { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        for (buyer, amount, count) in [("alice", 30, 3), ("bob", 10, 1), ("alice", 20, 2)] {
+            let msg = buy(buyer, amount, count);
+            execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+        }
+        let msg = QueryMsg::GetTickets {
+            buyer: String::from("alice"),
+        };
+        let tickets: u64 = from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(tickets, 5);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("bob", &[]),
+            ExecuteMsg::ClaimTicketRefund {},
+        )
+        .unwrap_err();
+        match err {
+            ContractError::AuctionStillOpen {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // Nothing is drawn until the seller reveals the seed.
+        env.block.height = 200_300;
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::Close {},
+        )
+        .unwrap_err();
+        match err {
+            ContractError::AuctionRevealing {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let reveal = |seed: &str| ExecuteMsg::RevealSeed {
+            seed: String::from(seed),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), reveal("other"))
+            .unwrap_err();
+        match err {
+            ContractError::InvalidReveal {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let msg = reveal("raffle seed");
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::Close {},
+        )
+        .unwrap();
+        let best_bid: BestBid =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::GetBestBid).unwrap())
+                .unwrap();
+        assert!(best_bid.sold);
+        let (winner, loser, winner_tickets, loser_tickets) =
+            if best_bid.bid_record.buyer == Addr::unchecked("alice") {
+                ("alice", "bob", 5, 1)
+            } else {
+                ("bob", "alice", 1, 5)
+            };
+        assert_eq!(best_bid.bid_record.quantity, Uint128::new(winner_tickets));
+        let cw20 = Cw20Contract(Addr::unchecked("cw20 token"));
+        let transfer = |recipient: &str, amount: u128| {
+            cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from(recipient),
+                amount: Uint128::new(amount),
+            })
+            .unwrap()
+        };
+        assert_eq!(res.messages[0].msg, transfer("creator", winner_tickets * 10));
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(winner, &[]),
+            ExecuteMsg::ClaimTicketRefund {},
+        )
+        .unwrap_err();
+        match err {
+            ContractError::NoTicketsToRefund {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info(loser, &[]),
+            ExecuteMsg::ClaimTicketRefund {},
+        )
+        .unwrap();
+        assert_eq!(res.messages[0].msg, transfer(loser, loser_tickets * 10));
+    }
+
+    #[test]
+    fn test_raffle_unrevealed_seed() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            auction_type: Some(AuctionType::Raffle {
+                ticket_price: Uint128::new(10),
+                refund_losers: false,
+            }),
+            seed_hash: Some(seed_commitment("raffle seed")),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("alice"),
+            amount: Uint128::new(30),
+            msg: to_binary(&ReceiveMsg::BuyTickets {
+                count: Uint64::new(3),
+            })
+            .unwrap(),
+        }));
+        execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+
+        // Without the seed the raffle fails and every ticket is refunded, winners or not.
+        env.block.height = 200_200 + SEED_REVEAL_PERIOD_IN_BLOCKS;
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetStatus).unwrap();
+        let status: AuctionStatus = from_binary(&res).unwrap();
+        assert_eq!(status, AuctionStatus::Failed);
+        let msg = ExecuteMsg::RevealSeed {
+            seed: String::from("raffle seed"),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap_err();
+        match err {
+            ContractError::AuctionFailed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let msg = ExecuteMsg::ClaimTicketRefund {};
+        let res = execute(deps.as_mut(), env, mock_info("alice", &[]), msg).unwrap();
+        let refund = Cw20Contract(Addr::unchecked("cw20 token"))
+            .call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from("alice"),
+                amount: Uint128::new(30),
+            })
+            .unwrap();
+        assert_eq!(res.messages[0].msg, refund);
+    }
+
+    #[test]
+    fn test_convert_to_fixed_price() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            mock_instantiate_msg(),
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::ConvertToFixedPrice {
+            price: Uint128::new(80),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg.clone())
+            .unwrap_err();
+        match err {
+            ContractError::AuctionStillOpen {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        env.block.height = 200_300;
+        let buy = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("alice"),
+            amount: Uint128::new(80),
+            msg: to_binary(&ReceiveMsg::BuyFixedPrice {}).unwrap(),
+        }));
+        let err = execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), buy.clone())
+            .unwrap_err();
+        match err {
+            ContractError::NotFixedPrice {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let err = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg.clone())
+            .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetFixedPrice).unwrap();
+        let fixed_price: Option<Uint128> = from_binary(&res).unwrap();
+        assert_eq!(fixed_price, Some(Uint128::new(80)));
+
+        let res = execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), buy.clone())
+            .unwrap();
+        let transfer = Cw20Contract(Addr::unchecked("cw20 token"))
+            .call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from("creator"),
+                amount: Uint128::new(80),
+            })
+            .unwrap();
+        assert_eq!(res.messages[0].msg, transfer);
+        let best_bid: BestBid =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::GetBestBid).unwrap())
+                .unwrap();
+        assert_eq!(best_bid.bid_record.buyer, Addr::unchecked("alice"));
+        assert!(best_bid.sold);
+
+        let err = execute(deps.as_mut(), env, mock_info("cw20 token", &[]), buy).unwrap_err();
+        match err {
+            ContractError::AlreadySold {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_proxy_bid() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            escrow: Some(true),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let cw20 = Cw20Contract(Addr::unchecked("cw20 token"));
+        let transfer = |recipient: &str, amount: u128| {
+            cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from(recipient),
+                amount: Uint128::new(amount),
+            })
+            .unwrap()
+        };
+        let receive = |bidder: &str, amount: u128, msg: ReceiveMsg| {
+            ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+                sender: String::from(bidder),
+                amount: Uint128::new(amount),
+                msg: to_binary(&msg).unwrap(),
+            }))
+        };
+        let proxy_bid = |max_price: u128| ReceiveMsg::ProxyBid {
+            max_price: Uint128::new(max_price),
+            quantity: None,
+        };
+        let bid = |price: u128| ReceiveMsg::Bid {
+            price: Uint128::new(price),
+            quantity: None,
+        };
+        let best_price = |deps: &OwnedDeps<_, _, _>| {
+            let res = query(deps.as_ref(), mock_env(), QueryMsg::GetBestBid).unwrap();
+            let best_bid: BestBid = from_binary(&res).unwrap();
+            (best_bid.bid_record.buyer, best_bid.bid_record.price)
+        };
+
+        // Alice's proxy opens one increment above the reserve and answers Bob's bid the same way.
+        let msg = receive("alice", 200, proxy_bid(200));
+        execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+        assert_eq!(best_price(&deps), (Addr::unchecked("alice"), Uint128::new(110)));
+        let msg = receive("bob", 150, bid(150));
+        let res = execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+        assert_eq!(res.messages[0].msg, transfer("bob", 150));
+        assert_eq!(best_price(&deps), (Addr::unchecked("alice"), Uint128::new(160)));
+        // The refunded bid is not the runner-up, and the counterbid is not counted as Alice's.
+        assert_eq!(RUNNER_UP.may_load(&deps.storage).unwrap(), None);
+        let alice_bids = BID_COUNTS.load(&deps.storage, &Addr::unchecked("alice"));
+        assert_eq!(alice_bids.unwrap(), 1);
+
+        // Carol's bid leaves no room under Alice's ceiling, so Alice is refunded in full.
+        let msg = receive("carol", 195, bid(195));
+        let res = execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+        assert_eq!(res.messages[0].msg, transfer("alice", 200));
+        assert_eq!(best_price(&deps), (Addr::unchecked("carol"), Uint128::new(195)));
+
+        let msg = receive("dave", 300, proxy_bid(300));
+        let res = execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+        assert_eq!(res.messages[0].msg, transfer("carol", 195));
+        assert_eq!(best_price(&deps), (Addr::unchecked("dave"), Uint128::new(205)));
+
+        // Only the winning price is paid and the rest of the ceiling is refunded.
+        env.block.height = 200_300;
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            ExecuteMsg::Close {},
+        )
+        .unwrap();
+        assert_eq!(res.messages[0].msg, transfer("creator", 205));
+        assert_eq!(res.messages[1].msg, transfer("dave", 95));
+    }
+
+    #[test]
+    fn test_secret_reserve() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            reserve_hash: Some(sealed_commitment(Uint128::new(150), "salt")),
+            escrow: Some(true),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("alice"),
+            amount: Uint128::new(120),
+            msg: to_binary(&ReceiveMsg::Bid {
+                price: Uint128::new(120),
+                quantity: None,
+            })
+            .unwrap(),
+        }));
+        execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+
+        env.block.height = 200_300;
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::Close {},
+        )
+        .unwrap_err();
+        match err {
+            ContractError::ReserveNotRevealed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let reveal = |salt: &str| ExecuteMsg::RevealReserve {
+            reserve: Uint128::new(150),
+            salt: String::from(salt),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), reveal("pepper"))
+            .unwrap_err();
+        match err {
+            ContractError::InvalidReveal {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // The best bid is below the revealed reserve, so it is refunded and the auction fails.
+        let res = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), reveal("salt"))
+            .unwrap();
+        let refund = Cw20Contract(Addr::unchecked("cw20 token"))
+            .call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from("alice"),
+                amount: Uint128::new(120),
+            })
+            .unwrap();
+        assert_eq!(res.messages[0].msg, refund);
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetRevealedReserve).unwrap();
+        let reserve: Option<Uint128> = from_binary(&res).unwrap();
+        assert_eq!(reserve, Some(Uint128::new(150)));
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetStatus).unwrap();
+        let status: AuctionStatus = from_binary(&res).unwrap();
+        assert_eq!(status, AuctionStatus::Failed);
+        let err = execute(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            ExecuteMsg::Close {},
+        )
+        .unwrap_err();
+        match err {
+            ContractError::AuctionFailed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_accept_bid() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            mock_instantiate_msg(),
+        )
+        .unwrap();
+
+        let accept = ExecuteMsg::AcceptBid {};
+        let err = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), accept.clone())
+            .unwrap_err();
+        match err {
+            ContractError::NoWinningBid {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let bid = |price: u128| ExecuteMsg::Bid {
+            price: Uint128::new(price),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), bid(120)).unwrap();
+        env.block.height = 200_010;
+        let err = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), accept.clone())
+            .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), accept).unwrap();
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetStatus).unwrap();
+        let status: AuctionStatus = from_binary(&res).unwrap();
+        assert_eq!(status, AuctionStatus::Ended);
+        let err =
+            execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), bid(150)).unwrap_err();
+        match err {
+            ContractError::AuctionClosed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("alice"),
+            amount: Uint128::new(120),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        execute(deps.as_mut(), env, mock_info("cw20 token", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn test_bid_cooldown() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            bid_cooldown: Some(Duration::Height(5)),
+            allow_self_raise: Some(true),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let bid = |price: u128| ExecuteMsg::Bid {
+            price: Uint128::new(price),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), bid(110)).unwrap();
+        env.block.height = 200_004;
+        let err =
+            execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), bid(120)).unwrap_err();
+        match err {
+            ContractError::BidCooldown { until } => {
+                assert_eq!(until, Expiration::AtHeight(200_005))
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+        // The cooldown is per address, so others can still bid.
+        execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), bid(120)).unwrap();
+        env.block.height = 200_005;
+        execute(deps.as_mut(), env, mock_info("alice", &[]), bid(130)).unwrap();
+    }
+
+    #[test]
+    fn test_bid_limit() {
+        let mut deps = mock_dependencies_with_token();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            max_bids_per_bidder: Some(2),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let bid = |price: u128| ExecuteMsg::Bid {
+            price: Uint128::new(price),
+            quantity: None,
+        };
+        let remaining = |deps: &OwnedDeps<_, _, _>| {
+            let msg = QueryMsg::GetRemainingBids {
+                bidder: String::from("alice"),
+            };
+            let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+            from_binary::<Option<u64>>(&res).unwrap()
+        };
+        assert_eq!(remaining(&deps), Some(2));
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), bid(110)).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), bid(120)).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), bid(130)).unwrap();
+        assert_eq!(remaining(&deps), Some(0));
+        execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), bid(140)).unwrap();
+        let err = execute(deps.as_mut(), env, mock_info("alice", &[]), bid(150)).unwrap_err();
+        match err {
+            ContractError::BidLimitReached { limit } => assert_eq!(limit, 2),
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_retract() {
+        let mut deps = mock_dependencies_with_token();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            escrow: Some(true),
+            retraction_penalty_bps: Some(1_000),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let bid = |bidder: &str, price: u128| {
+            ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+                sender: String::from(bidder),
+                amount: Uint128::new(price),
+                msg: to_binary(&ReceiveMsg::Bid {
+                    price: Uint128::new(price),
+                    quantity: None,
+                })
+                .unwrap(),
+            }))
+        };
+        for (bidder, price) in [("bob", 120), ("alice", 150)] {
+            let msg = bid(bidder, price);
+            execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+        }
+        let err = execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), ExecuteMsg::Retract {})
+            .unwrap_err();
+        match err {
+            ContractError::NoBidOrOffer { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("alice", &[]),
+            ExecuteMsg::Retract {},
+        )
+        .unwrap();
+        let cw20 = Cw20Contract(Addr::unchecked("cw20 token"));
+        let transfer = |recipient: &str, amount: u128| {
+            cw20.call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from(recipient),
+                amount: Uint128::new(amount),
+            })
+            .unwrap()
+        };
+        assert_eq!(res.messages[0].msg, transfer("creator", 15));
+        assert_eq!(res.messages[1].msg, transfer("alice", 135));
+        assert!(query(deps.as_ref(), env.clone(), QueryMsg::GetBestBid).is_err());
+
+        // Bidding starts over from the reserve.
+        let msg = bid("carol", 110);
+        execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+
+        #[cfg(feature = "debug")]
+        {
+            let res = query(deps.as_ref(), env, QueryMsg::GetInvariants).unwrap();
+            let invariants: debug::InvariantsResponse = from_binary(&res).unwrap();
+            assert!(invariants.violations.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_top_up() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            escrow: Some(true),
+            allow_self_raise: Some(true),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let receive = |bidder: &str, amount: u128, msg: ReceiveMsg| {
+            ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+                sender: String::from(bidder),
+                amount: Uint128::new(amount),
+                msg: to_binary(&msg).unwrap(),
+            }))
+        };
+        let top_up = |new_price: u128| ReceiveMsg::TopUp {
+            new_price: Uint128::new(new_price),
+        };
+        let bid = ReceiveMsg::Bid {
+            price: Uint128::new(120),
+            quantity: None,
+        };
+        let msg = receive("alice", 120, bid);
+        execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+        let msg = receive("bob", 30, top_up(150));
+        let err =
+            execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap_err();
+        match err {
+            ContractError::NoBidOrOffer { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let msg = receive("alice", 20, top_up(150));
+        let err =
+            execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap_err();
+        match err {
+            ContractError::InsufficientPayment { amount, required } => {
+                assert_eq!(amount, Uint128::new(140));
+                assert_eq!(required, Uint128::new(150));
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+        let msg = receive("alice", 30, top_up(150));
+        let res = execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+        assert!(res.messages.is_empty());
+
+        env.block.height = 200_300;
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            ExecuteMsg::Close {},
+        )
+        .unwrap();
+        let transfer = Cw20Contract(Addr::unchecked("cw20 token"))
+            .call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from("creator"),
+                amount: Uint128::new(150),
+            })
+            .unwrap();
+        assert_eq!(res.messages[0].msg, transfer);
+    }
+
+    #[test]
+    fn test_pool() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            mock_instantiate_msg(),
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::CreatePool {};
+        execute(deps.as_mut(), env.clone(), mock_info("carol", &[]), msg).unwrap();
+        let contribute = |contributor: &str, amount: u128| {
+            ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+                sender: String::from(contributor),
+                amount: Uint128::new(amount),
+                msg: to_binary(&ReceiveMsg::ContributeToPool { pool_id: 1 }).unwrap(),
+            }))
+        };
+        let token_info = mock_info("cw20 token", &[]);
+        execute(deps.as_mut(), env.clone(), token_info.clone(), contribute("alice", 120)).unwrap();
+        execute(deps.as_mut(), env.clone(), token_info.clone(), contribute("bob", 80)).unwrap();
+
+        let pool_bid = |price: u128| ExecuteMsg::PoolBid {
+            pool_id: 1,
+            price: Uint128::new(price),
+            quantity: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), pool_bid(120))
+            .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let err = execute(deps.as_mut(), env.clone(), mock_info("carol", &[]), pool_bid(210))
+            .unwrap_err();
+        match err {
+            ContractError::InsufficientPayment { amount, required } => {
+                assert_eq!(amount, Uint128::new(200));
+                assert_eq!(required, Uint128::new(210));
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+        execute(deps.as_mut(), env.clone(), mock_info("carol", &[]), pool_bid(120)).unwrap();
+        let withdraw = ExecuteMsg::WithdrawFromPool { pool_id: 1 };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), withdraw.clone())
+            .unwrap_err();
+        match err {
+            ContractError::PoolBidStanding {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let refund_owed = |deps: Deps, env: Env, address: &str| {
+            let msg = QueryMsg::GetRefundOwed {
+                address: String::from(address),
+            };
+            from_binary::<RefundOwedResponse>(&query(deps, env, msg).unwrap()).unwrap()
+        };
+        let owed = refund_owed(deps.as_ref(), env.clone(), "alice");
+        assert_eq!(owed.amount, Uint128::zero());
+        assert!(owed.pools.is_empty());
 
-        let res = query(deps.as_ref(), env, QueryMsg::GetBidSeq).unwrap();
-        let bid_seq: u64 = from_binary(&res).unwrap();
-        assert_eq!(bid_seq, 0u64);
+        let bid = ExecuteMsg::Bid {
+            price: Uint128::new(130),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("dave", &[]), bid).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info("carol", &[]), pool_bid(150)).unwrap();
+
+        // The pool pays from its contributions, and the contributors split what is left.
+        env.block.height = 200_300;
+        let msg = ExecuteMsg::SettlePool { pool_id: 1 };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+        let transfer = |recipient: &str, amount: u128| {
+            Cw20Contract(Addr::unchecked("cw20 token"))
+                .call(Cw20ExecuteMsg::Transfer {
+                    recipient: String::from(recipient),
+                    amount: Uint128::new(amount),
+                })
+                .unwrap()
+        };
+        assert_eq!(res.messages[0].msg, transfer("creator", 150));
+
+        let msg = QueryMsg::GetPoolContribution {
+            pool_id: 1,
+            contributor: String::from("alice"),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let contribution: PoolContributionResponse = from_binary(&res).unwrap();
+        assert_eq!(contribution.share_bps, 6_000);
+        assert_eq!(contribution.refund, Uint128::new(30));
+        let owed = refund_owed(deps.as_ref(), env.clone(), "alice");
+        assert_eq!(owed.amount, Uint128::new(30));
+        assert_eq!(
+            owed.pools,
+            vec![PoolRefund {
+                pool_id: 1,
+                refund: Uint128::new(30),
+            }]
+        );
+        let res = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), withdraw.clone())
+            .unwrap();
+        assert_eq!(res.messages[0].msg, transfer("alice", 30));
+        let owed = refund_owed(deps.as_ref(), env.clone(), "alice");
+        assert!(owed.amount.is_zero() && owed.pools.is_empty());
+        let res = execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), withdraw.clone())
+            .unwrap();
+        assert_eq!(res.messages[0].msg, transfer("bob", 20));
+        let err = execute(deps.as_mut(), env, mock_info("bob", &[]), withdraw).unwrap_err();
+        match err {
+            ContractError::NoContribution {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
     }
 
     #[test]
-    fn test_bid() {
-        let mut deps = mock_dependencies();
+    fn test_pool_bid_after_relist() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
         let msg = InstantiateMsg {
-            token_addr: String::from("cw20 token"),
-            reserve_price: Uint128::new(100),
-            increment: Uint128::new(10),
-            duration_in_blocks: Uint64::new(200),
+            reserve_hash: Some(sealed_commitment(Uint128::new(150), "salt")),
+            ..mock_instantiate_msg()
         };
-        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::CreatePool {};
+        execute(deps.as_mut(), env.clone(), mock_info("carol", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("alice"),
+            amount: Uint128::new(120),
+            msg: to_binary(&ReceiveMsg::ContributeToPool { pool_id: 1 }).unwrap(),
+        }));
+        execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+        let msg = ExecuteMsg::PoolBid {
+            pool_id: 1,
+            price: Uint128::new(120),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("carol", &[]), msg).unwrap();
+
+        // The pool bid misses the secret reserve and the auction is relisted.
+        env.block.height = 200_300;
+        let msg = ExecuteMsg::RevealReserve {
+            reserve: Uint128::new(150),
+            salt: String::from("salt"),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Relist {
+            new_config: RelistConfig {
+                reserve_price: Uint128::new(80),
+                increment: Uint128::new(5),
+                duration_in_blocks: Some(Uint64::new(100)),
+                duration: None,
+                expiration: None,
+            },
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        assert!(!POOL_BIDS.has(&deps.storage, (0, 1)));
+
+        // Bob's bid takes the id the pool bid had last round, but is not the pool's to pay for.
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(85),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), msg).unwrap();
+        env.block.height = 200_400;
+        let msg = ExecuteMsg::SettlePool { pool_id: 1 };
+        let err = execute(deps.as_mut(), env, mock_info("anyone", &[]), msg).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_lease() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            lease: Some(LeaseTerms {
+                period_in_blocks: Uint64::new(100),
+                period_payment: Uint128::new(20),
+                periods: 3,
+            }),
+            ..mock_instantiate_msg()
+        };
+        let seller_info = mock_info("creator", &coins(10_000, "uatom"));
+        instantiate(deps.as_mut(), env.clone(), seller_info, msg).unwrap();
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(110),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        env.block.height = 200_300;
+        let res = execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+        // Only the seller is paid, the lot stays in the contract.
+        assert_eq!(res.messages.len(), 1);
+
+        let lease = |deps: &OwnedDeps<_, _, _>, env: &Env| {
+            let res = query(deps.as_ref(), env.clone(), QueryMsg::GetLease).unwrap();
+            from_binary::<Option<LeaseResponse>>(&res).unwrap().unwrap()
+        };
+        assert_eq!(
+            lease(&deps, &env),
+            LeaseResponse {
+                lessee: String::from("buyer"),
+                paid_periods: 1,
+                next_due_at: Some(200_400),
+                ends_at: 200_600,
+                active: true,
+            }
+        );
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::EndLease {},
+        )
+        .unwrap_err();
+        match err {
+            ContractError::LeaseActive { ends_at } => assert_eq!(ends_at, 200_600),
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let pay = |sender: &str| {
+            ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+                sender: String::from(sender),
+                amount: Uint128::new(20),
+                msg: to_binary(&ReceiveMsg::PayPeriod {}).unwrap(),
+            }))
+        };
+        let token_info = mock_info("cw20 token", &[]);
+        let err = execute(deps.as_mut(), env.clone(), token_info.clone(), pay("anyone"))
+            .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        env.block.height = 200_399;
+        let res = execute(deps.as_mut(), env.clone(), token_info.clone(), pay("buyer")).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            Cw20Contract(Addr::unchecked("cw20 token"))
+                .call(Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("creator"),
+                    amount: Uint128::new(20),
+                })
+                .unwrap()
+        );
+
+        // Missing the third period ends the lease early and returns the lot to the seller.
+        env.block.height = 200_500;
+        let err = execute(deps.as_mut(), env.clone(), token_info, pay("buyer")).unwrap_err();
+        match err {
+            ContractError::LeasePaymentOverdue { due_at } => assert_eq!(due_at, 200_500),
+            e => panic!("unexpected error: {}", e),
+        }
+        assert!(!lease(&deps, &env).active);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::EndLease {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("creator"),
+                amount: coins(10_000, "uatom"),
+            })
+        );
+        assert_eq!(res.attributes[2].value, "true");
+        let err = execute(deps.as_mut(), env, mock_info("anyone", &[]), ExecuteMsg::EndLease {})
+            .unwrap_err();
+        match err {
+            ContractError::NoLease {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_payout_vesting() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let schedule = VestingSchedule {
+            cliff_in_blocks: Uint64::new(20),
+            duration_in_blocks: Uint64::new(100),
+        };
+        let msg = InstantiateMsg {
+            payout_vesting: Some(schedule.clone()),
+            payout_delay_in_blocks: Some(Uint64::new(10)),
+            ..mock_instantiate_msg()
+        };
+        let err = instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg)
+            .unwrap_err();
+        match err {
+            ContractError::InvalidVestingSchedule {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let msg = InstantiateMsg {
+            payout_vesting: Some(schedule),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(110),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        env.block.height = 200_300;
+        let res = execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+        assert!(res.messages.is_empty());
+
+        let seller_info = mock_info("creator", &[]);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            seller_info.clone(),
+            ExecuteMsg::ClaimPayout { proposal_id: None },
+        )
+        .unwrap_err();
+        match err {
+            ContractError::PayoutVests {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // Nothing is released before the cliff, then the proceeds vest from settlement.
+        let claim = ExecuteMsg::ClaimVested {};
+        env.block.height = 200_319;
+        let err = execute(deps.as_mut(), env.clone(), seller_info.clone(), claim.clone())
+            .unwrap_err();
+        match err {
+            ContractError::NothingVested {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        env.block.height = 200_350;
+        let res = execute(deps.as_mut(), env.clone(), seller_info.clone(), claim.clone())
+            .unwrap();
+        assert_eq!(res.attributes[2].value, "55");
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetVesting).unwrap();
+        let vesting: Option<VestingResponse> = from_binary(&res).unwrap();
+        assert_eq!(
+            vesting,
+            Some(VestingResponse {
+                total: Uint128::new(110),
+                vested: Uint128::new(55),
+                claimed: Uint128::new(55),
+                claimable: Uint128::zero(),
+            })
+        );
+
+        env.block.height = 200_500;
+        let res = execute(deps.as_mut(), env.clone(), seller_info.clone(), claim.clone())
+            .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            Cw20Contract(Addr::unchecked("cw20 token"))
+                .call(Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("creator"),
+                    amount: Uint128::new(55),
+                })
+                .unwrap()
+        );
+        let err = execute(deps.as_mut(), env, seller_info, claim).unwrap_err();
+        match err {
+            ContractError::NothingVested {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_bid_bond() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            bid_bond: Some(Uint128::zero()),
+            ..mock_instantiate_msg()
+        };
+        let err = instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg)
+            .unwrap_err();
+        match err {
+            ContractError::ZeroBidBond {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let msg = InstantiateMsg {
+            bid_bond: Some(Uint128::new(10)),
+            settlement_window_in_blocks: Some(Uint64::new(20)),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let bid = |price: u128| ExecuteMsg::Bid {
+            price: Uint128::new(price),
+            quantity: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), bid(110))
+            .unwrap_err();
+        match err {
+            ContractError::BidBondRequired { amount } => assert_eq!(amount, Uint128::new(10)),
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let post = |bidder: &str, amount: u128| {
+            ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+                sender: String::from(bidder),
+                amount: Uint128::new(amount),
+                msg: to_binary(&ReceiveMsg::PostDeposit {}).unwrap(),
+            }))
+        };
+        let token_info = mock_info("cw20 token", &[]);
+        let res =
+            execute(deps.as_mut(), env.clone(), token_info.clone(), post("alice", 15)).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            Cw20Contract(Addr::unchecked("cw20 token"))
+                .call(Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("alice"),
+                    amount: Uint128::new(5),
+                })
+                .unwrap()
+        );
+        let err = execute(deps.as_mut(), env.clone(), token_info.clone(), post("alice", 10))
+            .unwrap_err();
+        match err {
+            ContractError::BidBondPosted {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        execute(deps.as_mut(), env.clone(), token_info, post("bob", 10)).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), bid(110)).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), bid(120)).unwrap();
+
+        let reclaim = ExecuteMsg::ReclaimDeposit {};
+        let err = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), reclaim.clone())
+            .unwrap_err();
+        match err {
+            ContractError::AuctionStillOpen {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // Bidders other than the winner get their bond back once bidding is over.
+        env.block.height = 200_210;
+        let err = execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), reclaim.clone())
+            .unwrap_err();
+        match err {
+            ContractError::BidBondHeld {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let refund_owed = |deps: Deps, env: Env, address: &str| {
+            let msg = QueryMsg::GetRefundOwed {
+                address: String::from(address),
+            };
+            from_binary::<RefundOwedResponse>(&query(deps, env, msg).unwrap()).unwrap()
+        };
+        assert_eq!(refund_owed(deps.as_ref(), env.clone(), "bob").bond, Uint128::zero());
+        let owed = refund_owed(deps.as_ref(), env.clone(), "alice");
+        assert_eq!((owed.amount, owed.bond), (Uint128::new(10), Uint128::new(10)));
+
+        // A winner who lets the settlement window pass loses the bond to the seller, and the
+        // runner-up's bond is held in turn.
+        env.block.height = 200_220;
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::ForfeitWinner {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            Cw20Contract(Addr::unchecked("cw20 token"))
+                .call(Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("creator"),
+                    amount: Uint128::new(10),
+                })
+                .unwrap()
+        );
+        let bond = |deps: &OwnedDeps<_, _, _>, bidder: &str| {
+            let msg = QueryMsg::GetBidBond {
+                bidder: String::from(bidder),
+            };
+            let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+            from_binary::<Option<Uint128>>(&res).unwrap()
+        };
+        assert_eq!(bond(&deps, "bob"), None);
+        let err = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), reclaim.clone())
+            .unwrap_err();
+        match err {
+            ContractError::BidBondHeld {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("alice"),
+            amount: Uint128::new(110),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), reclaim.clone())
+            .unwrap();
+        assert_eq!(res.attributes[2].value, "10");
+        assert_eq!(res.events[0].ty, "refund");
+        assert_eq!(res.events[0].attributes[3].value, "reclaimed");
+        assert_eq!(bond(&deps, "alice"), None);
+        let err = execute(deps.as_mut(), env, mock_info("alice", &[]), reclaim).unwrap_err();
+        match err {
+            ContractError::NoBidBond {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_direct_offer() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            escrow: Some(true),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let receive = |sender: &str, amount: u128, msg: ReceiveMsg| {
+            ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+                sender: String::from(sender),
+                amount: Uint128::new(amount),
+                msg: to_binary(&msg).unwrap(),
+            }))
+        };
+        let make_offer = |price: u128| ReceiveMsg::MakeOffer {
+            price: Uint128::new(price),
+        };
+        let transfer = |recipient: &str, amount: u128| {
+            Cw20Contract(Addr::unchecked("cw20 token"))
+                .call(Cw20ExecuteMsg::Transfer {
+                    recipient: String::from(recipient),
+                    amount: Uint128::new(amount),
+                })
+                .unwrap()
+        };
+        let bid = ReceiveMsg::Bid {
+            price: Uint128::new(120),
+            quantity: None,
+        };
+        let msg = receive("alice", 120, bid);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("cw20 token", &[]),
+            msg,
+        )
+        .unwrap();
+
+        // Offers can be below the reserve, and a new one replaces the old.
+        let msg = receive("bob", 60, make_offer(60));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("cw20 token", &[]),
+            msg,
+        )
+        .unwrap();
+        let msg = receive("bob", 90, make_offer(90));
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("cw20 token", &[]),
+            msg,
+        )
+        .unwrap();
+        assert_eq!(res.messages[0].msg, transfer("bob", 60));
+        let msg = receive("carol", 70, make_offer(70));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("cw20 token", &[]),
+            msg,
+        )
+        .unwrap();
+        let msg = QueryMsg::GetDirectOffers {
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let offers: OffersResponse = from_binary(&res).unwrap();
+        assert_eq!(offers.offers.len(), 2);
+        assert_eq!(offers.offers[0].price, Uint128::new(90));
+
+        let msg = ExecuteMsg::AcceptOffer {
+            buyer: String::from("bob"),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("bob", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        // Accepting ends bidding, refunds the best bidder and pays the seller.
+        let res = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(res.messages[0].msg, transfer("alice", 120));
+        assert_eq!(res.messages[1].msg, transfer("creator", 90));
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetBestBid).unwrap();
+        let best_bid: BestBid = from_binary(&res).unwrap();
+        assert_eq!(best_bid.bid_record.buyer, Addr::unchecked("bob"));
+        assert!(best_bid.sold);
+        #[cfg(feature = "debug")]
+        {
+            let res = query(deps.as_ref(), env.clone(), QueryMsg::GetInvariants).unwrap();
+            let invariants: debug::InvariantsResponse = from_binary(&res).unwrap();
+            assert!(invariants.violations.is_empty());
+        }
+        let msg = receive(
+            "dave",
+            120,
+            ReceiveMsg::Bid {
+                price: Uint128::new(130),
+                quantity: None,
+            },
+        );
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("cw20 token", &[]),
+            msg,
+        )
+        .unwrap_err();
+        match err {
+            ContractError::AlreadySold {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // Offers that were not accepted stay reclaimable.
+        let msg = QueryMsg::GetRefundOwed {
+            address: String::from("carol"),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let owed: RefundOwedResponse = from_binary(&res).unwrap();
+        assert_eq!((owed.amount, owed.offer), (Uint128::new(70), Uint128::new(70)));
+        let msg = ExecuteMsg::WithdrawOffer {};
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("carol", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(res.messages[0].msg, transfer("carol", 70));
+        env.block.height = 200_300;
+        let err = execute(deps.as_mut(), env, mock_info("carol", &[]), msg).unwrap_err();
+        match err {
+            ContractError::NoBidOrOffer { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_relist() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            reserve_hash: Some(sealed_commitment(Uint128::new(150), "salt")),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::SetManager {
+            manager: Some(String::from("manager")),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let bid = |price: u128| ExecuteMsg::Bid {
+            price: Uint128::new(price),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), bid(120)).unwrap();
+
+        let relist = ExecuteMsg::Relist {
+            new_config: RelistConfig {
+                reserve_price: Uint128::new(80),
+                increment: Uint128::new(5),
+                duration_in_blocks: Some(Uint64::new(100)),
+                duration: None,
+                expiration: None,
+            },
+        };
+        env.block.height = 200_300;
+        let err = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), relist.clone())
+            .unwrap_err();
+        match err {
+            ContractError::HasWinningBid {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        // The secret reserve is not met, so the auction fails and can be relisted.
+        let msg = ExecuteMsg::RevealReserve {
+            reserve: Uint128::new(150),
+            salt: String::from("salt"),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info("manager", &[]), relist).unwrap();
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetRound).unwrap();
+        assert_eq!(from_binary::<u64>(&res).unwrap(), 1);
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetBidSeq).unwrap();
+        assert_eq!(from_binary::<u64>(&res).unwrap(), 0);
+        let msg = QueryMsg::GetRoundBidRecord {
+            round: 0,
+            id: Uint64::new(1),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let bid_record: BidResponse = from_binary(&res).unwrap();
+        assert_eq!(bid_record.price, Uint128::new(120));
+
+        // The new round runs on the new terms.
+        execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), bid(85)).unwrap();
+        env.block.height = 200_400;
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("bob"),
+            amount: Uint128::new(85),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        execute(deps.as_mut(), env, mock_info("cw20 token", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn test_pause() {
+        let mut deps = mock_dependencies_with_token();
         let mut env = mock_env();
         env.block.height = 200_000;
-        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
-
-        let msg = ExecuteMsg::Bid {
-            price: Uint128::new(80),
+        let msg = InstantiateMsg {
+            admin: Some(String::from("admin")),
+            ..mock_instantiate_msg()
         };
-        let info = mock_info("buyer", &[]);
-        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let pause_all = ExecuteMsg::Pause { scopes: None };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), pause_all.clone())
+            .unwrap_err();
         match err {
-            ContractError::CustomError { val } => assert!(val.contains("Bid price lower than reserve price")),
+            ContractError::Unauthorized {} => {}
             e => panic!("unexpected error: {}", e),
         }
+        let admin_info = mock_info("admin", &[]);
+        execute(deps.as_mut(), env.clone(), admin_info.clone(), pause_all).unwrap();
+        let paused = |deps: &OwnedDeps<_, _, _>| -> Vec<PauseScope> {
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetPaused).unwrap()).unwrap()
+        };
+        assert_eq!(paused(&deps), PAUSE_SCOPES.to_vec());
 
-        let msg = ExecuteMsg::Bid {
-            price: Uint128::new(109),
+        let bid = |price: u128| ExecuteMsg::Bid {
+            price: Uint128::new(price),
+            quantity: None,
         };
-        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        let err = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), bid(110))
+            .unwrap_err();
         match err {
-            ContractError::CustomError { val } => assert!(val.contains("Bid increment too low")),
+            ContractError::ContractPaused {} => {}
             e => panic!("unexpected error: {}", e),
         }
 
-        let bid_price = Uint128::new(110);
-        let msg = ExecuteMsg::Bid { price: bid_price };
-        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
-        assert_eq!(res.attributes.len(), 4);
+        // Unpausing settlement and refunds leaves bidding paused.
+        let msg = ExecuteMsg::Unpause {
+            scopes: Some(vec![PauseScope::Settlement, PauseScope::Refunds]),
+        };
+        execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+        assert_eq!(paused(&deps), vec![PauseScope::Bidding]);
+        let msg = ExecuteMsg::Unpause { scopes: None };
+        execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+        assert_eq!(paused(&deps), vec![]);
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), bid(110)).unwrap();
 
-        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetBidSeq).unwrap();
-        let bid_seq: u64 = from_binary(&res).unwrap();
-        assert_eq!(bid_seq, 1u64);
+        // With bidding paused, new bids are rejected but the sale still settles.
+        let msg = ExecuteMsg::Pause {
+            scopes: Some(vec![PauseScope::Bidding]),
+        };
+        execute(deps.as_mut(), env.clone(), admin_info, msg).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), bid(120))
+            .unwrap_err();
+        match err {
+            ContractError::ContractPaused {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        env.block.height = 200_300;
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("alice"),
+            amount: Uint128::new(110),
+            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+        }));
+        execute(deps.as_mut(), env, mock_info("cw20 token", &[]), msg).unwrap();
+    }
 
-        let res = query(
-            deps.as_ref(),
+    #[test]
+    fn test_status() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        instantiate(
+            deps.as_mut(),
             env.clone(),
-            QueryMsg::GetBidRecord {
-                id: Uint64::new(bid_seq),
-            },
+            mock_info("creator", &coins(1_000, "ulot")),
+            mock_instantiate_msg(),
         )
         .unwrap();
-        let bid_record: BidRecord = from_binary(&res).unwrap();
-        assert_eq!(bid_record.buyer, "buyer");
-        assert_eq!(bid_record.price, bid_price);
+        let status: AuctionStatus =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::GetStatus).unwrap()).unwrap();
+        assert_eq!(status, AuctionStatus::Active);
 
-        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetBestBid).unwrap();
-        let best_bid: BestBid = from_binary(&res).unwrap();
-        assert_eq!(best_bid.id, Uint64::new(1));
-        assert_eq!(best_bid.bid_record.buyer, "buyer");
-        assert_eq!(best_bid.bid_record.price, bid_price);
-        assert_eq!(best_bid.sold, false);
+        // The auction ends at the timeout even before any handler runs.
+        env.block.height = 200_200;
+        let status: AuctionStatus =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::GetStatus).unwrap()).unwrap();
+        assert_eq!(status, AuctionStatus::Ended);
+        let msg = ExecuteMsg::Offer {
+            price: Uint128::new(90),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), msg).unwrap_err();
+        match err {
+            ContractError::AuctionClosed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::ReclaimLot {},
+        )
+        .unwrap();
+        let status: AuctionStatus =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::GetStatus).unwrap()).unwrap();
+        assert_eq!(status, AuctionStatus::Cancelled);
 
-        let err = execute(deps.as_mut(), env, info.clone(), msg).unwrap_err();
+        let msg = ExecuteMsg::SetManager {
+            manager: Some(String::from("manager")),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap_err();
+        match err {
+            ContractError::LotReclaimed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let err = execute(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            ExecuteMsg::Close {},
+        )
+        .unwrap_err();
         match err {
-            ContractError::CustomError { val } => assert!(val.contains("Bid price not greater than best price")),
+            ContractError::LotReclaimed {} => {}
             e => panic!("unexpected error: {}", e),
         }
+    }
+
+    #[test]
+    fn test_nonpayable() {
+        let mut deps = mock_dependencies_with_token();
+        let env = mock_env();
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), mock_instantiate_msg())
+            .unwrap();
 
         let msg = ExecuteMsg::Bid {
-            price: Uint128::new(130),
+            price: Uint128::new(110),
+            quantity: None,
         };
-        let mut env = mock_env();
-        env.block.height = 200_200;
-        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("buyer", &coins(110, "uatom")),
+            msg.clone(),
+        )
+        .unwrap_err();
         match err {
-            ContractError::CustomError { val } => assert!(val.contains("Auction closed")),
+            ContractError::Payment(PaymentError::NonPayable {}) => {}
             e => panic!("unexpected error: {}", e),
         }
+        assert_eq!(BID_SEQ.load(&deps.storage).unwrap(), 0);
+
+        execute(deps.as_mut(), env, mock_info("buyer", &[]), msg).unwrap();
     }
 
     #[test]
-    fn test_buy() {
-        let mut deps = mock_dependencies();
+    fn test_expiration() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        let info = mock_info("creator", &[]);
         let msg = InstantiateMsg {
-            token_addr: String::from("cw20 token"),
-            reserve_price: Uint128::new(100),
-            increment: Uint128::new(10),
-            duration_in_blocks: Uint64::new(200),
+            expiration: Some(Expiration::AtHeight(env.block.height + 200)),
+            ..mock_instantiate_msg()
         };
-        let info = mock_info("creator", &[]);
-        let mut env = mock_env();
-        env.block.height = 200_000;
-        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let err = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::InvalidExpiration {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = InstantiateMsg {
+            duration_in_blocks: None,
+            expiration: Some(Expiration::AtTime(env.block.time.plus_seconds(10))),
+            ..mock_instantiate_msg()
+        };
+        let err = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::DurationOutOfBounds { duration, min, .. } => {
+                assert_eq!(duration, Uint64::new(10));
+                assert_eq!(min, MIN_DURATION_IN_SECONDS);
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = InstantiateMsg {
+            duration_in_blocks: None,
+            expiration: Some(Expiration::AtTime(env.block.time.plus_seconds(3_600))),
+            installment_plan: Some(InstallmentPlan {
+                count: 2,
+                interval_in_blocks: Uint64::new(100),
+                forfeit_bps: 1_000,
+                down_payment_bps: None,
+            }),
+            ..mock_instantiate_msg()
+        };
+        let err = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::HeightExpirationRequired {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = InstantiateMsg {
+            duration: Some(Duration::Time(3_600)),
+            ..mock_instantiate_msg()
+        };
+        let err = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        match err {
+            ContractError::InvalidExpiration {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
 
+        // A time-based auction closes on block time, however many blocks have passed.
+        let msg = InstantiateMsg {
+            duration_in_blocks: None,
+            duration: Some(Duration::Time(3_600)),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        env.block.height += 1_000;
         let msg = ExecuteMsg::Bid {
             price: Uint128::new(110),
+            quantity: None,
         };
-        let buyer_info = mock_info("buyer", &[]);
-        execute(deps.as_mut(), env.clone(), buyer_info.clone(), msg).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), msg).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(3_600);
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(120),
+            quantity: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("another", &[]), msg).unwrap_err();
+        match err {
+            ContractError::AuctionClosed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let mut deps = mock_dependencies_with_token();
+        let msg = InstantiateMsg {
+            duration_in_blocks: None,
+            expiration: Some(Expiration::Never {}),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+        env.block.height += MAX_DURATION_IN_BLOCKS;
+        let status: AuctionStatus =
+            from_binary(&query(deps.as_ref(), env, QueryMsg::GetStatus).unwrap()).unwrap();
+        assert_eq!(status, AuctionStatus::Active);
+    }
+
+    #[test]
+    fn test_migrate() {
+        let mut deps = mock_dependencies_with_token();
+        let env = mock_env();
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), mock_instantiate_msg())
+            .unwrap();
 
-        let proper_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.0.1").unwrap();
+        let res = migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+        assert_eq!(res.attributes[1].value, "0.0.1");
+        assert_eq!(res.attributes[2].value, CONTRACT_VERSION);
+        let version = cw2::get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        // Migrating to the same version is allowed so state fixes can be shipped.
+        migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "99.0.0").unwrap();
+        let err = migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap_err();
+        match err {
+            ContractError::CannotDowngrade { from, .. } => assert_eq!(from, "99.0.0"),
+            e => panic!("unexpected error: {}", e),
+        }
+
+        set_contract_version(&mut deps.storage, "crates.io:cw20-base", "0.0.1").unwrap();
+        let err = migrate(deps.as_mut(), env, MigrateMsg {}).unwrap_err();
+        match err {
+            ContractError::InvalidContractName { found, .. } => {
+                assert_eq!(found, "crates.io:cw20-base")
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_sudo() {
+        let mut deps = mock_dependencies_with_token();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            escrow: Some(true),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
             sender: String::from("buyer"),
             amount: Uint128::new(110),
-            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
-        });
-        let err = execute(
-            deps.as_mut(),
-            env.clone(),
-            buyer_info.clone(),
-            proper_msg.clone(),
-        )
-        .unwrap_err();
+            msg: to_binary(&ReceiveMsg::Bid {
+                price: Uint128::new(110),
+                quantity: None,
+            })
+            .unwrap(),
+        }));
+        execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+
+        // Governance can close before the timeout, which settles the escrowed bid.
+        let res = sudo(deps.as_mut(), env.clone(), SudoMsg::ForceClose {}).unwrap();
+        let payment = Cw20Contract(Addr::unchecked("cw20 token"))
+            .call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from("creator"),
+                amount: Uint128::new(110),
+            })
+            .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, payment);
+        assert_eq!(STATUS.load(&deps.storage).unwrap(), AuctionStatus::Settled);
+        let err = sudo(deps.as_mut(), env.clone(), SudoMsg::ForceCancelAndRefund {}).unwrap_err();
         match err {
-            ContractError::CustomError { val } => assert!(val.contains("Auction not yet closed")),
+            ContractError::AlreadySold {} => {}
             e => panic!("unexpected error: {}", e),
         }
 
-        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
-            sender: String::from("anyone"),
+        let mut deps = mock_dependencies_with_token();
+        let msg = InstantiateMsg {
+            escrow: Some(true),
+            ..mock_instantiate_msg()
+        };
+        let info = mock_info("creator", &coins(1_000, "ulot"));
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let msg = ExecuteMsg::Receive(TokenReceiveMsg::Cw20(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
             amount: Uint128::new(110),
-            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
+            msg: to_binary(&ReceiveMsg::Bid {
+                price: Uint128::new(110),
+                quantity: None,
+            })
+            .unwrap(),
+        }));
+        execute(deps.as_mut(), env.clone(), mock_info("cw20 token", &[]), msg).unwrap();
+
+        let res = sudo(deps.as_mut(), env.clone(), SudoMsg::ForceCancelAndRefund {}).unwrap();
+        let refund = Cw20Contract(Addr::unchecked("cw20 token"))
+            .call(Cw20ExecuteMsg::Transfer {
+                recipient: String::from("buyer"),
+                amount: Uint128::new(110),
+            })
+            .unwrap();
+        let lot = CosmosMsg::from(BankMsg::Send {
+            to_address: String::from("creator"),
+            amount: coins(1_000, "ulot"),
         });
-        let info = mock_info("anyone", &[]);
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(res.messages[0].msg, refund);
+        assert_eq!(res.messages[1].msg, lot);
+        assert_eq!(ESCROWED.may_load(&deps.storage).unwrap(), None);
+        assert_eq!(STATUS.load(&deps.storage).unwrap(), AuctionStatus::Cancelled);
+
+        let err = sudo(deps.as_mut(), env, SudoMsg::ForceClose {}).unwrap_err();
+        match err {
+            ContractError::LotReclaimed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_extend_deadline() {
+        let mut deps = mock_dependencies_with_token();
         let mut env = mock_env();
-        env.block.height = 200_300;
-        let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            max_extension: Some(Duration::Time(600)),
+            ..mock_instantiate_msg()
+        };
+        let err = instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg)
+            .unwrap_err();
+        match err {
+            ContractError::InvalidExtension {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = InstantiateMsg {
+            max_extension: Some(Duration::Height(100)),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let extend = |blocks| ExecuteMsg::ExtendDeadline {
+            additional: Duration::Height(blocks),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), extend(50))
+            .unwrap_err();
         match err {
             ContractError::Unauthorized {} => {}
             e => panic!("unexpected error: {}", e),
         }
 
-        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
-            sender: String::from("buyer"),
-            amount: Uint128::new(105),
-            msg: to_binary(&ReceiveMsg::Buy).unwrap(),
-        });
-        let err = execute(deps.as_mut(), env.clone(), buyer_info.clone(), msg).unwrap_err();
+        let res = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), extend(60))
+            .unwrap();
+        let event = &res.events[0];
+        assert_eq!(event.ty, "deadline_extended");
+        assert_eq!(event.attributes[0].value, Expiration::AtHeight(200_200).to_string());
+        assert_eq!(event.attributes[1].value, Expiration::AtHeight(200_260).to_string());
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.timeout, Expiration::AtHeight(200_260));
+
+        // The manager shares the seller's limit.
+        let msg = ExecuteMsg::SetManager {
+            manager: Some(String::from("manager")),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        execute(deps.as_mut(), env.clone(), mock_info("manager", &[]), extend(10)).unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.timeout, Expiration::AtHeight(200_270));
+
+        let err = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), extend(50))
+            .unwrap_err();
+        match err {
+            ContractError::ExtensionExceedsLimit {
+                requested,
+                remaining,
+            } => {
+                assert_eq!(requested, 50);
+                assert_eq!(remaining, 30);
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+        let msg = ExecuteMsg::ExtendDeadline {
+            additional: Duration::Time(60),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap_err();
         match err {
-            ContractError::CustomError { val } => assert!(val.contains("Amount lower than bid price")),
+            ContractError::InvalidExtension {} => {}
             e => panic!("unexpected error: {}", e),
         }
 
-        let res = execute(
-            deps.as_mut(),
-            env.clone(),
-            buyer_info.clone(),
-            proper_msg.clone(),
-        )
-        .unwrap();
-        assert_eq!(res.messages.len(), 1);
-        assert_eq!(res.attributes.len(), 4);
+        // Bids are still accepted past the original timeout, but the deadline cannot be moved
+        // once it has passed.
+        env.block.height = 200_230;
+        let msg = ExecuteMsg::Bid {
+            price: Uint128::new(110),
+            quantity: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), msg).unwrap();
+        env.block.height = 200_270;
+        let err = execute(deps.as_mut(), env, mock_info("creator", &[]), extend(10)).unwrap_err();
+        match err {
+            ContractError::AuctionClosed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
 
-        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetBestBid).unwrap();
-        let best_bid: BestBid = from_binary(&res).unwrap();
-        assert_eq!(best_bid.sold, true);
+    #[test]
+    fn test_soft_close() {
+        let mut deps = mock_dependencies_with_token();
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let msg = InstantiateMsg {
+            soft_close_window: Some(Duration::Height(20)),
+            ..mock_instantiate_msg()
+        };
+        let err = instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg)
+            .unwrap_err();
+        match err {
+            ContractError::InvalidSoftClose {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let msg = InstantiateMsg {
+            soft_close_window: Some(Duration::Height(20)),
+            extension: Some(Duration::Height(30)),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let bid = |price: u128| ExecuteMsg::Bid {
+            price: Uint128::new(price),
+            quantity: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), bid(110)).unwrap();
+        assert!(res.events.is_empty());
 
-        let err = execute(deps.as_mut(), env, buyer_info, proper_msg).unwrap_err();
+        // A bid in the last 20 blocks moves the deadline back by 30.
+        env.block.height = 200_180;
+        let res = execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), bid(120)).unwrap();
+        assert_eq!(res.events[0].ty, "deadline_extended");
+        let deadline: Expiration =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::GetDeadline).unwrap())
+                .unwrap();
+        assert_eq!(deadline, Expiration::AtHeight(200_230));
+
+        env.block.height = 200_225;
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), bid(130)).unwrap();
+        let deadline: Expiration =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::GetDeadline).unwrap())
+                .unwrap();
+        assert_eq!(deadline, Expiration::AtHeight(200_260));
+
+        env.block.height = 200_260;
+        let err = execute(deps.as_mut(), env, mock_info("bob", &[]), bid(140)).unwrap_err();
+        match err {
+            ContractError::AuctionClosed {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_strict_increments() {
+        let mut deps = mock_dependencies_with_token();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            strict_increments: Some(true),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let bid = |price: u128| ExecuteMsg::Bid {
+            price: Uint128::new(price),
+            quantity: None,
+        };
+        let err =
+            execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), bid(125)).unwrap_err();
+        match err {
+            ContractError::BidNotOnIncrement { lower, higher, .. } => {
+                assert_eq!(lower, Uint128::new(120));
+                assert_eq!(higher, Uint128::new(130));
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), bid(120)).unwrap();
+        execute(deps.as_mut(), env, mock_info("bob", &[]), bid(140)).unwrap();
+    }
+
+    #[test]
+    fn test_increment_tiers() {
+        let mut deps = mock_dependencies_with_token();
+        let env = mock_env();
+        let tier = |below: u128, increment: u128| IncrementTier {
+            below: Uint128::new(below),
+            increment: Uint128::new(increment),
+        };
+        let msg = InstantiateMsg {
+            increment_tiers: Some(vec![tier(1_000, 50), tier(200, 10)]),
+            ..mock_instantiate_msg()
+        };
+        let err =
+            instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap_err();
+        match err {
+            ContractError::InvalidIncrementTiers {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let msg = InstantiateMsg {
+            increment: Uint128::new(100),
+            increment_tiers: Some(vec![tier(200, 10), tier(1_000, 50)]),
+            ..mock_instantiate_msg()
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let bid = |price: u128| ExecuteMsg::Bid {
+            price: Uint128::new(price),
+            quantity: None,
+        };
+        for (bidder, price) in [("alice", 110), ("bob", 195), ("alice", 205)] {
+            execute(deps.as_mut(), env.clone(), mock_info(bidder, &[]), bid(price)).unwrap();
+        }
+        let err =
+            execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), bid(250)).unwrap_err();
+        match err {
+            ContractError::IncrementTooLow { min_increment, .. } => {
+                assert_eq!(min_increment, Uint128::new(50))
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+        execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), bid(1_000)).unwrap();
+        let err =
+            execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), bid(1_050)).unwrap_err();
         match err {
-            ContractError::CustomError { val } => assert!(val.contains("Item already sold")),
+            ContractError::IncrementTooLow { min_increment, .. } => {
+                assert_eq!(min_increment, Uint128::new(100))
+            }
             e => panic!("unexpected error: {}", e),
         }
+        execute(deps.as_mut(), env, mock_info("alice", &[]), bid(1_100)).unwrap();
     }
 }
@@ -0,0 +1,49 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{to_binary, Binary, CosmosMsg, StdResult, Uint128, WasmMsg};
+
+// Minimal subset of the cw1155 interface needed to hold and release bundled token amounts.
+// `from` is None for tokens minted straight to the contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw1155ReceiveMsg {
+    pub operator: String,
+    pub from: Option<String>,
+    pub token_id: String,
+    pub amount: Uint128,
+    pub msg: Binary,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw1155ExecuteMsg {
+    SendFrom {
+        from: String,
+        to: String,
+        token_id: String,
+        value: Uint128,
+        msg: Option<Binary>,
+    },
+}
+
+// cw1155 has no plain send, so the contract sends from its own balance.
+pub fn send_tokens_msg(
+    contract_addr: &str,
+    from: &str,
+    recipient: &str,
+    token_id: &str,
+    amount: Uint128,
+) -> StdResult<CosmosMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: contract_addr.to_string(),
+        msg: to_binary(&Cw1155ExecuteMsg::SendFrom {
+            from: from.to_string(),
+            to: recipient.to_string(),
+            token_id: token_id.to_string(),
+            value: amount,
+            msg: None,
+        })?,
+        funds: vec![],
+    }
+    .into())
+}
@@ -0,0 +1,41 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{
+    to_binary, Addr, CosmosMsg, QuerierWrapper, QueryRequest, StdResult, WasmQuery,
+};
+
+// Minimal subset of the cw3 query interface needed to check a proposal status and its messages.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw3QueryMsg {
+    Proposal { proposal_id: u64 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Pending,
+    Open,
+    Rejected,
+    Passed,
+    Executed,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProposalResponse {
+    pub id: u64,
+    pub msgs: Vec<CosmosMsg>,
+    pub status: Status,
+}
+
+pub fn query_proposal(
+    querier: &QuerierWrapper,
+    multisig: &Addr,
+    proposal_id: u64,
+) -> StdResult<ProposalResponse> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: multisig.to_string(),
+        msg: to_binary(&Cw3QueryMsg::Proposal { proposal_id })?,
+    }))
+}
@@ -0,0 +1,34 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{to_binary, Binary, CosmosMsg, StdResult, WasmMsg};
+
+// Minimal subset of the cw721 interface needed to hold and release bundled NFTs.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw721ReceiveMsg {
+    pub sender: String,
+    pub token_id: String,
+    pub msg: Binary,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw721ExecuteMsg {
+    TransferNft { recipient: String, token_id: String },
+}
+
+pub fn transfer_nft_msg(
+    contract_addr: &str,
+    recipient: &str,
+    token_id: &str,
+) -> StdResult<CosmosMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: contract_addr.to_string(),
+        msg: to_binary(&Cw721ExecuteMsg::TransferNft {
+            recipient: recipient.to_string(),
+            token_id: token_id.to_string(),
+        })?,
+        funds: vec![],
+    }
+    .into())
+}
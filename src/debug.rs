@@ -0,0 +1,154 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Deps, Order, StdResult, Uint128};
+use cw_storage_plus::Bound;
+
+use crate::state::{
+    AuctionStatus, AuctionType, ACCEPTED_OFFER, BEST_BID, BID_RECORDS, BID_SEQ, CANDLE_END,
+    CANDLE_SNAPSHOTS, CONFIG, ESCROWED, FORFEITED, PAYOUT, RETRACTED, RUNNER_UP, STATUS,
+};
+use crate::validation::clearing_price;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct InvariantsResponse {
+    pub violations: Vec<String>,
+}
+
+// Walks the whole bid history, so it is only meant for tests and testnets.
+pub fn query_invariants(deps: Deps) -> StdResult<InvariantsResponse> {
+    let mut violations = vec![];
+
+    let bid_seq = BID_SEQ.load(deps.storage)?;
+    let records = BID_RECORDS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    if records.len() as u64 != bid_seq {
+        violations.push(format!(
+            "Bid record count does not match bid seq, records: {}, bid seq: {}",
+            records.len(),
+            bid_seq
+        ));
+    }
+    for (expected, (id, _)) in (1u64..).zip(records.iter()) {
+        if *id != expected {
+            violations.push(format!("Bid record id out of sequence, id: {}", id));
+            break;
+        }
+    }
+
+    // Bids from forfeited winners no longer count towards the best bid.
+    let forfeited = FORFEITED
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, buyer)| buyer))
+        .collect::<StdResult<Vec<Addr>>>()?;
+    // Neither do bids placed after the drawn end of a candle auction.
+    let candle_cutoff = match CANDLE_END.may_load(deps.storage)? {
+        Some(candle_end) => CANDLE_SNAPSHOTS
+            .range(
+                deps.storage,
+                None,
+                Some(Bound::inclusive(candle_end)),
+                Order::Descending,
+            )
+            .next()
+            .transpose()?
+            .map_or(0, |(_, id)| id),
+        None => u64::MAX,
+    };
+    // Nor do bids placed before the last retraction, after which bidding started over.
+    let last_retracted = RETRACTED
+        .keys(deps.storage, None, None, Order::Descending)
+        .next()
+        .transpose()?
+        .unwrap_or_default();
+    // Or before an accepted direct offer, which ended bidding.
+    let accepted_offer = ACCEPTED_OFFER.may_load(deps.storage)?.unwrap_or_default();
+    let records = records
+        .into_iter()
+        .filter(|(id, record)| {
+            *id > last_retracted
+                && *id >= accepted_offer
+                && *id <= candle_cutoff
+                && !forfeited.contains(&record.buyer)
+        })
+        .collect::<Vec<_>>();
+
+    // Batch bids are all kept without a best bid and settle together.
+    let batch = CONFIG.load(deps.storage)?.auction_type == AuctionType::Batch;
+    let best_bid = BEST_BID.may_load(deps.storage)?;
+    match (&best_bid, records.last()) {
+        (None, None) => {}
+        (None, Some(_)) if batch => {}
+        (None, Some(_)) if !forfeited.is_empty() => {}
+        (Some(best_bid), Some((id, record))) => {
+            if best_bid.id.u64() != *id || best_bid.bid_record != *record {
+                violations.push(format!(
+                    "Best bid does not match the latest bid record, best bid id: {}, latest id: {}",
+                    best_bid.id, id
+                ));
+            }
+            let max_price = records
+                .iter()
+                .map(|(_, record)| record.price)
+                .max()
+                .unwrap_or_default();
+            if best_bid.bid_record.price != max_price {
+                violations.push(format!(
+                    "Best bid is not the highest bid, best price: {}, highest price: {}",
+                    best_bid.bid_record.price, max_price
+                ));
+            }
+        }
+        (Some(_), None) => violations.push("Best bid saved without any bid record".to_string()),
+        (None, Some(_)) => violations.push("Bid records saved without a best bid".to_string()),
+    }
+
+    let status = STATUS.load(deps.storage)?;
+    let sold = matches!(&best_bid, Some(best_bid) if best_bid.sold);
+    if !batch && sold != (status == AuctionStatus::Settled) {
+        violations.push(format!(
+            "Sold flag does not match the status, sold: {}, status: {:?}",
+            sold, status
+        ));
+    }
+
+    if let Some(payout) = PAYOUT.may_load(deps.storage)? {
+        match &best_bid {
+            Some(best_bid) if best_bid.sold => {
+                let config = CONFIG.load(deps.storage)?;
+                let runner_up = RUNNER_UP.may_load(deps.storage)?;
+                let runner_up = runner_up.as_ref().map(|runner_up| &runner_up.bid_record);
+                let total = clearing_price(&config, &best_bid.bid_record, runner_up)
+                    .checked_mul(best_bid.bid_record.quantity)
+                    .unwrap_or(Uint128::MAX);
+                if payout.amount < total {
+                    violations.push(format!(
+                        "Payout lower than the sold price, payout: {}, sold price: {}",
+                        payout.amount, total
+                    ));
+                }
+            }
+            _ => violations.push("Payout saved before the lot was sold".to_string()),
+        }
+    }
+
+    if let Some(escrowed) = ESCROWED.may_load(deps.storage)? {
+        let total = best_bid.as_ref().map(|best_bid| {
+            best_bid
+                .bid_record
+                .price
+                .checked_mul(best_bid.bid_record.quantity)
+                .unwrap_or(Uint128::MAX)
+        });
+        match total {
+            Some(total) if escrowed >= total => {}
+            _ => violations.push(format!(
+                "Escrow does not cover the best bid, escrowed: {}",
+                escrowed
+            )),
+        }
+    }
+
+    Ok(InvariantsResponse { violations })
+}
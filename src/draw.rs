@@ -0,0 +1,48 @@
+use cosmwasm_std::{Binary, StdResult, Storage};
+use cw_storage_plus::Item;
+use sha2::{Digest, Sha256};
+
+// Candle ends and raffle winners are drawn from a seed the seller commits to at instantiate and
+// reveals after the timeout, hashed together with every entry. The seller cannot choose the seed
+// once entries come in, and whoever sends the next message or proposes the block has no say in
+// the result. The seller is trusted not to take part: they can compute the draw before revealing,
+// so a seller entering through another address could steer it with a last entry. Withholding the
+// seed fails the auction once `SEED_REVEAL_PERIOD_IN_BLOCKS` have passed, which refunds raffle
+// tickets and lets the seller reclaim the lot.
+
+// About a day at six second blocks.
+pub const SEED_REVEAL_PERIOD_IN_BLOCKS: u64 = 14_400;
+
+pub const SEED: Item<String> = Item::new("seed");
+// Running hash of every entry, in order.
+pub const ENTRY_MIX: Item<Binary> = Item::new("emix");
+
+pub fn seed_commitment(seed: &str) -> Binary {
+    Binary::from(Sha256::digest(seed.as_bytes()).as_slice())
+}
+
+pub fn mix_entry(storage: &mut dyn Storage, entry: &str) -> StdResult<()> {
+    let mut hasher = Sha256::new();
+    if let Some(mix) = ENTRY_MIX.may_load(storage)? {
+        hasher.update(mix.as_slice());
+    }
+    hasher.update(entry.as_bytes());
+    ENTRY_MIX.save(storage, &Binary::from(hasher.finalize().as_slice()))
+}
+
+// None until the seed is revealed.
+pub fn drawn_value(storage: &dyn Storage) -> StdResult<Option<u64>> {
+    let seed = match SEED.may_load(storage)? {
+        Some(seed) => seed,
+        None => return Ok(None),
+    };
+    let mut hasher = Sha256::new();
+    hasher.update(seed.as_bytes());
+    if let Some(mix) = ENTRY_MIX.may_load(storage)? {
+        hasher.update(mix.as_slice());
+    }
+    let hash = hasher.finalize();
+    let mut value = [0u8; 8];
+    value.copy_from_slice(&hash[..8]);
+    Ok(Some(u64::from_be_bytes(value)))
+}
@@ -1,4 +1,5 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{OverflowError, StdError, Uint128, Uint64};
+use cw_utils::{Expiration, PaymentError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,9 +7,385 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
+    #[error("Invalid contract name, expected: {expected}, found: {found}")]
+    InvalidContractName { expected: String, found: String },
+
+    #[error("Invalid contract version: {version}")]
+    InvalidVersion { version: String },
+
+    #[error("Cannot migrate from {from} down to {to}")]
+    CannotDowngrade { from: String, to: String },
+
     #[error("Unauthorized")]
     Unauthorized {},
 
-    #[error("Custom Error val: {val:?}")]
-    CustomError { val: String },
+    #[error("Contract paused")]
+    ContractPaused {},
+
+    #[error("Invalid token: {token}")]
+    InvalidToken { token: String },
+
+    #[error("Not a cw20 token: {addr}")]
+    NotACw20Token { addr: String },
+
+    #[error("Exactly one of duration_in_blocks, duration and expiration must be set")]
+    InvalidExpiration {},
+
+    #[error("Duration must be greater than zero")]
+    ZeroDuration {},
+
+    #[error("Duration out of bounds, duration: {duration}, min: {min}, max: {max}")]
+    DurationOutOfBounds {
+        duration: Uint64,
+        min: u64,
+        max: u64,
+    },
+
+    #[error("Increment must be greater than zero")]
+    ZeroIncrement {},
+
+    #[error("Quantity must be greater than zero")]
+    ZeroQuantity {},
+
+    #[error("Max bid multiplier must be greater than one")]
+    InvalidMaxBidMultiplier {},
+
+    #[error("Invalid increment tiers")]
+    InvalidIncrementTiers {},
+
+    #[error("Bid cooldown must be greater than zero")]
+    ZeroBidCooldown {},
+
+    #[error("Bid limit must be greater than zero")]
+    ZeroBidLimit {},
+
+    #[error("Bid bond must be greater than zero")]
+    ZeroBidBond {},
+
+    #[error("Bid bond required, amount: {amount}")]
+    BidBondRequired { amount: Uint128 },
+
+    #[error("Bid bond already posted")]
+    BidBondPosted {},
+
+    #[error("No bid bond posted")]
+    NoBidBond {},
+
+    #[error("Bid bond held until the winning bid is settled")]
+    BidBondHeld {},
+
+    #[error("Invalid retraction penalty")]
+    InvalidRetractionPenalty {},
+
+    #[error("Bid retraction not allowed")]
+    RetractionNotAllowed {},
+
+    #[error("Invalid reserve decay schedule")]
+    InvalidReserveDecay {},
+
+    #[error("Invalid Dutch auction price curve")]
+    InvalidPriceCurve {},
+
+    #[error("Option not supported by the auction type")]
+    UnsupportedByAuctionType {},
+
+    #[error("Candle window must be non-zero and fit within the auction")]
+    InvalidCandleWindow {},
+
+    #[error("A batch auction needs escrow and a cw20 lot token other than the payment token")]
+    InvalidBatchAuction {},
+
+    #[error("Batch auction already holds the maximum of {max} bids")]
+    BatchFull { max: u64 },
+
+    #[error("Lot not deposited")]
+    LotNotDeposited {},
+
+    #[error("Lot already deposited")]
+    LotAlreadyDeposited {},
+
+    #[error("Lot deposit must match the quantity, amount: {amount}, quantity: {quantity}")]
+    InvalidLotDeposit { amount: Uint128, quantity: Uint128 },
+
+    #[error("Bundle items must be non-zero, distinct and not the payment token")]
+    InvalidBundle {},
+
+    #[error("No bundle item left to deposit matches the deposit")]
+    InvalidBundleDeposit {},
+
+    #[error("Ticket price must be greater than zero")]
+    ZeroTicketPrice {},
+
+    #[error("Penny auction fee, price step and extension must be greater than zero")]
+    InvalidPennyAuction {},
+
+    #[error("No bid fees to claim")]
+    NoBidFees {},
+
+    #[error("No tickets to refund")]
+    NoTicketsToRefund {},
+
+    #[error("No secret reserve")]
+    NoSecretReserve {},
+
+    #[error("Secret reserve not yet revealed")]
+    ReserveNotRevealed {},
+
+    #[error("Secret reserve already revealed")]
+    ReserveAlreadyRevealed {},
+
+    #[error("Reveal period must be greater than zero")]
+    ZeroRevealPeriod {},
+
+    #[error("English window must be greater than zero")]
+    ZeroEnglishWindow {},
+
+    #[error("Only supported in a sealed-bid auction")]
+    NotSealedAuction {},
+
+    #[error("Bid already committed")]
+    AlreadyCommitted {},
+
+    #[error("No commitment found for bidder: {bidder}")]
+    NoCommitment { bidder: String },
+
+    #[error("Price and salt do not match the commitment")]
+    InvalidReveal {},
+
+    #[error("Candle auctions and raffles need a seed hash")]
+    SeedHashRequired {},
+
+    #[error("No unrevealed deposits to slash")]
+    NothingToSlash {},
+
+    #[error("Only supported in an English auction")]
+    NotEnglishAuction {},
+
+    #[error("Buy now price must be at least the reserve price")]
+    InvalidBuyNowPrice {},
+
+    #[error("Invalid installment plan")]
+    InvalidInstallmentPlan {},
+
+    #[error("Installment plan not supported in escrow mode")]
+    EscrowInstallmentsUnsupported {},
+
+    #[error("Settlement window cannot be zero")]
+    ZeroSettlementWindow {},
+
+    #[error("Settlement window not supported with an installment plan")]
+    SettlementWindowInstallmentsUnsupported {},
+
+    #[error("Extension must be non-zero and in the same unit as the timeout")]
+    InvalidExtension {},
+
+    #[error("Soft close window and extension must be set together")]
+    InvalidSoftClose {},
+
+    #[error("Installments and settlement windows require a block height expiration")]
+    HeightExpirationRequired {},
+
+    #[error("Invalid event prefix: {prefix}")]
+    InvalidEventPrefix { prefix: String },
+
+    #[error("Invalid pagination limits, default limit: {default_limit}, max limit: {max_limit}")]
+    InvalidPaginationLimits { default_limit: u32, max_limit: u32 },
+
+    #[error("Auction not yet started")]
+    AuctionNotStarted {},
+
+    #[error("Auction in the reveal phase")]
+    AuctionRevealing {},
+
+    #[error("Auction closed")]
+    AuctionClosed {},
+
+    #[error("Auction not yet closed")]
+    AuctionStillOpen {},
+
+    #[error("Item already sold")]
+    AlreadySold {},
+
+    #[error("Invalid bid quantity, quantity: {quantity}, available quantity: {available}")]
+    InvalidBidQuantity {
+        quantity: Uint128,
+        available: Uint128,
+    },
+
+    #[error("Bid price lower than reserve price, bid price: {bid}, reserve price: {reserve}")]
+    BidBelowReserve { bid: Uint128, reserve: Uint128 },
+
+    #[error("Bid price not greater than best price, bid price: {bid}, best price: {best}")]
+    BidNotAboveBest { bid: Uint128, best: Uint128 },
+
+    #[error("Bid increment too low, increment: {increment}, minimum increment: {min_increment}")]
+    IncrementTooLow {
+        increment: Uint128,
+        min_increment: Uint128,
+    },
+
+    #[error("Bid price not on an increment step, bid price: {bid}, nearest: {lower} or {higher}")]
+    BidNotOnIncrement {
+        bid: Uint128,
+        lower: Uint128,
+        higher: Uint128,
+    },
+
+    #[error("Bid price outside price band, bid price: {price}, max price: {max_price}")]
+    BidOutsidePriceBand { price: Uint128, max_price: Uint128 },
+
+    #[error("Bidder cooling down until {until}")]
+    BidCooldown { until: Expiration },
+
+    #[error("Bid limit reached, limit: {limit}")]
+    BidLimitReached { limit: u64 },
+
+    #[error("Seller cannot bid on their own auction")]
+    SellerCannotBid {},
+
+    #[error("Sender already holds the best bid")]
+    AlreadyBestBidder {},
+
+    #[error("Amount lower than required, amount: {amount}, required: {required}")]
+    InsufficientPayment { amount: Uint128, required: Uint128 },
+
+    #[error("Amount must match the price exactly, amount: {amount}, required: {required}")]
+    IncorrectPaymentAmount { amount: Uint128, required: Uint128 },
+
+    #[error(
+        "Insufficient funds, balance: {balance}, allowance: {allowance}, required: {required}"
+    )]
+    InsufficientFunds {
+        balance: Uint128,
+        allowance: Uint128,
+        required: Uint128,
+    },
+
+    #[error("Bids must be sent with the payment token in escrow mode")]
+    EscrowRequired {},
+
+    #[error("Pools need an English auction without escrow or installments")]
+    PoolsUnsupported {},
+
+    #[error("Pool holds the best bid")]
+    PoolBidStanding {},
+
+    #[error("Pool already spent on the lot")]
+    PoolSpent {},
+
+    #[error("No contribution to the pool")]
+    NoContribution {},
+
+    #[error("Escrow mode not enabled")]
+    EscrowNotEnabled {},
+
+    #[error("Best bid already paid in escrow")]
+    AlreadyEscrowed {},
+
+    #[error("Auction has a winning bid")]
+    HasWinningBid {},
+
+    #[error("Auction already has bids")]
+    HasBids {},
+
+    #[error("No winning bid")]
+    NoWinningBid {},
+
+    #[error("Offer price must be greater than zero")]
+    ZeroOfferPrice {},
+
+    #[error(
+        "Offer price not lower than reserve price, offer price: {price}, reserve price: {reserve}"
+    )]
+    OfferNotBelowReserve { price: Uint128, reserve: Uint128 },
+
+    #[error("No bid or offer found for buyer: {buyer}")]
+    NoBidOrOffer { buyer: String },
+
+    #[error("Counteroffer expired")]
+    CounterofferExpired {},
+
+    #[error("Fixed price must be greater than zero")]
+    ZeroFixedPrice {},
+
+    #[error("Not converted to a fixed-price sale")]
+    NotFixedPrice {},
+
+    #[error("Proposal id required to release the payout")]
+    ProposalIdRequired {},
+
+    #[error("Proposal not passed, proposal id: {proposal_id}, status: {status}")]
+    ProposalNotPassed { proposal_id: u64, status: String },
+
+    #[error("Proposal does not claim this payout, proposal id: {proposal_id}")]
+    ProposalNotForPayout { proposal_id: u64 },
+
+    #[error("Payout already claimed")]
+    PayoutAlreadyClaimed {},
+
+    #[error("Payout not yet claimable, claimable at: {claimable_at}")]
+    PayoutNotClaimable { claimable_at: Uint64 },
+
+    #[error("Invalid payout vesting schedule")]
+    InvalidVestingSchedule {},
+
+    #[error("Proceeds vest, claim them with ClaimVested")]
+    PayoutVests {},
+
+    #[error("Nothing vested to claim")]
+    NothingVested {},
+
+    #[error("No installment plan")]
+    NoInstallmentPlan {},
+
+    #[error("Auction failed, installments defaulted or reserve not met")]
+    AuctionFailed {},
+
+    #[error("Installment overdue, due at: {due_at}")]
+    InstallmentOverdue { due_at: u64 },
+
+    #[error("Installment not yet overdue, due at: {due_at}")]
+    InstallmentNotOverdue { due_at: u64 },
+
+    #[error("Invalid lease terms")]
+    InvalidLeaseTerms {},
+
+    #[error("No lease")]
+    NoLease {},
+
+    #[error("Lease payment overdue, due at: {due_at}")]
+    LeasePaymentOverdue { due_at: u64 },
+
+    #[error("Every lease period is paid")]
+    LeasePaid {},
+
+    #[error("Lease still running, ends at: {ends_at}")]
+    LeaseActive { ends_at: u64 },
+
+    #[error("No lot to reclaim")]
+    NoLot {},
+
+    #[error("Lot reclaimed by seller")]
+    LotReclaimed {},
+
+    #[error("Deadline extensions not allowed")]
+    ExtensionNotAllowed {},
+
+    #[error("Extension exceeds the limit, requested: {requested}, remaining: {remaining}")]
+    ExtensionExceedsLimit { requested: u64, remaining: u64 },
+
+    #[error("No settlement window")]
+    NoSettlementWindow {},
+
+    #[error("Settlement window still open, deadline: {deadline}")]
+    SettlementWindowOpen { deadline: u64 },
+
+    #[error("Settlement window expired, deadline: {deadline}")]
+    SettlementWindowExpired { deadline: u64 },
 }
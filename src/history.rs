@@ -0,0 +1,43 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Deps, Order, StdResult, Storage, Uint128, Uint64};
+use cw_storage_plus::{Bound, Item, Map};
+
+use crate::msg::{SettlementsResponse, API_VERSION};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Settlement {
+    pub bid_id: Uint64,
+    pub price: Uint128,
+    pub quantity: Uint128,
+    pub height: Uint64,
+}
+
+pub const SETTLEMENT_SEQ: Item<u64> = Item::new("sseq");
+pub const SETTLEMENTS: Map<u64, Settlement> = Map::new("stl");
+
+pub fn record_settlement(storage: &mut dyn Storage, settlement: &Settlement) -> StdResult<()> {
+    let id = SETTLEMENT_SEQ.may_load(storage)?.unwrap_or_default() + 1;
+    SETTLEMENT_SEQ.save(storage, &id)?;
+    SETTLEMENTS.save(storage, id, settlement)
+}
+
+pub fn query_settlements(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: usize,
+) -> StdResult<SettlementsResponse> {
+    let start = start_after.map(Bound::exclusive);
+
+    let settlements = SETTLEMENTS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, settlement)| settlement))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(SettlementsResponse {
+        api_version: API_VERSION,
+        settlements,
+    })
+}
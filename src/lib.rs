@@ -1,4 +1,23 @@
+pub mod batch;
 pub mod contract;
+pub mod cw1155;
+mod cw3;
+pub mod cw721;
+#[cfg(feature = "debug")]
+pub mod debug;
+pub mod draw;
 mod error;
+#[cfg(feature = "history")]
+pub mod history;
+pub mod math;
+mod migrations;
 pub mod msg;
+pub mod pool;
 pub mod state;
+#[cfg(feature = "stats")]
+pub mod stats;
+pub mod strategy;
+pub mod validation;
+pub mod vesting;
+
+pub use crate::error::ContractError;
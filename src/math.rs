@@ -0,0 +1,35 @@
+use cosmwasm_std::Uint128;
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+// Share of `amount` for `bps` basis points, rounded down. Anything above 100% is capped, so the
+// intermediate product cannot overflow.
+pub fn bps_of(amount: Uint128, bps: u64) -> Uint128 {
+    amount.multiply_ratio(bps.min(BPS_DENOMINATOR), BPS_DENOMINATOR)
+}
+
+// Splits `amount` into its `bps` share and the remainder; the two always sum back to `amount`.
+pub fn split_bps(amount: Uint128, bps: u64) -> (Uint128, Uint128) {
+    let share = bps_of(amount, bps);
+    (share, amount - share)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_bps() {
+        let amounts = [0u128, 1, 3, 7, 99, 9_999, 10_001, 123_456_789, u128::MAX - 1, u128::MAX];
+        for amount in amounts {
+            for bps in (0..=BPS_DENOMINATOR).step_by(37).chain([1, 9_999, 10_000, 20_000]) {
+                let amount = Uint128::new(amount);
+                let (share, rest) = split_bps(amount, bps);
+                assert_eq!(share + rest, amount);
+                assert!(share <= amount);
+            }
+        }
+        assert_eq!(split_bps(Uint128::new(101), 5_000), (Uint128::new(50), Uint128::new(51)));
+        assert_eq!(split_bps(Uint128::new(100), 20_000), (Uint128::new(100), Uint128::zero()));
+    }
+}
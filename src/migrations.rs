@@ -0,0 +1,67 @@
+use cosmwasm_std::Storage;
+use cw2::{get_contract_version, set_contract_version};
+
+use crate::error::ContractError;
+
+type Migration = (
+    &'static str,
+    fn(&mut dyn Storage) -> Result<(), ContractError>,
+);
+
+// Storage transforms keyed by the version that introduced them, in ascending order. Each one runs
+// once, when migrating from an older version up to or past its own.
+const MIGRATIONS: &[Migration] = &[];
+
+// Versions are compared numerically as `major.minor.patch`, ignoring any pre-release suffix.
+fn parse_version(version: &str) -> Result<(u64, u64, u64), ContractError> {
+    let invalid = || ContractError::InvalidVersion {
+        version: version.to_string(),
+    };
+    let core = version.split(&['-', '+'][..]).next().unwrap_or_default();
+    let mut parts = core.split('.').map(|part| part.parse::<u64>());
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch)), None) => Ok((major, minor, patch)),
+        _ => Err(invalid()),
+    }
+}
+
+// Only allows migrating the same contract from an older or equal version, then records the new
+// version. Returns the version migrated from.
+pub fn ensure_from_older_version(
+    storage: &mut dyn Storage,
+    name: &str,
+    new_version: &str,
+) -> Result<String, ContractError> {
+    let stored = get_contract_version(storage)?;
+    if stored.contract != name {
+        return Err(ContractError::InvalidContractName {
+            expected: name.to_string(),
+            found: stored.contract,
+        });
+    }
+    if parse_version(&stored.version)? > parse_version(new_version)? {
+        return Err(ContractError::CannotDowngrade {
+            from: stored.version,
+            to: new_version.to_string(),
+        });
+    }
+    set_contract_version(storage, name, new_version)?;
+    Ok(stored.version)
+}
+
+// Applies every transform newer than `from`, up to and including `to`.
+pub fn run_migrations(
+    storage: &mut dyn Storage,
+    from: &str,
+    to: &str,
+) -> Result<(), ContractError> {
+    let from = parse_version(from)?;
+    let to = parse_version(to)?;
+    for (version, migration) in MIGRATIONS {
+        let version = parse_version(version)?;
+        if version > from && version <= to {
+            migration(storage)?;
+        }
+    }
+    Ok(())
+}
@@ -1,40 +1,450 @@
-use cosmwasm_std::{Uint128, Uint64};
+use cosmwasm_std::{Binary, Uint128, Uint64};
 use cw20::Cw20ReceiveMsg;
+use cw_utils::{Duration, Expiration};
+
+use crate::cw1155::Cw1155ReceiveMsg;
+use crate::cw721::Cw721ReceiveMsg;
+
+#[cfg(feature = "history")]
+use crate::history::Settlement;
+use crate::state::{
+    AuctionType, BundleItem, IncrementTier, InstallmentPlan, LeaseTerms, PauseScope, ReserveDecay,
+};
+use crate::vesting::VestingSchedule;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+// Bumped whenever a query response changes in a breaking way.
+pub const API_VERSION: u32 = 1;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub token_addr: String,
     pub reserve_price: Uint128,
     pub increment: Uint128,
-    pub duration_in_blocks: Uint64,
+    // Exactly one of these sets when the auction closes. `duration_in_blocks` is kept for
+    // existing deployments and is the same as a `duration` in blocks.
+    pub duration_in_blocks: Option<Uint64>,
+    pub duration: Option<Duration>,
+    pub expiration: Option<Expiration>,
+    pub payout_delay_in_blocks: Option<Uint64>,
+    pub quantity: Option<Uint128>,
+    pub max_bid_multiplier: Option<Uint128>,
+    pub default_limit: Option<u32>,
+    pub max_limit: Option<u32>,
+    pub proposal_confirmation: Option<bool>,
+    pub reserve_decay: Option<ReserveDecay>,
+    pub allow_self_raise: Option<bool>,
+    pub installment_plan: Option<InstallmentPlan>,
+    pub event_prefix: Option<String>,
+    pub escrow: Option<bool>,
+    pub allow_seller_bids: Option<bool>,
+    pub verify_funds: Option<bool>,
+    pub settlement_window_in_blocks: Option<Uint64>,
+    pub max_extension: Option<Duration>,
+    // A bid within `soft_close_window` of the timeout pushes it back by `extension`.
+    pub soft_close_window: Option<Duration>,
+    pub extension: Option<Duration>,
+    pub strict_increments: Option<bool>,
+    // Ascending price bands with their own increment. `increment` applies above the last one.
+    pub increment_tiers: Option<Vec<IncrementTier>>,
+    // Minimum blocks or seconds between two bids from the same address.
+    pub bid_cooldown: Option<Duration>,
+    // Maximum number of bids a single address may place.
+    pub max_bids_per_bidder: Option<u64>,
+    // Lets the best bidder retract before close in escrow mode, keeping this share of the escrow
+    // for the seller.
+    pub retraction_penalty_bps: Option<u64>,
+    pub require_exact_payment: Option<bool>,
+    // Can pause and unpause the contract, independently of the seller.
+    pub admin: Option<String>,
+    // Defaults to an English auction.
+    pub auction_type: Option<AuctionType>,
+    // The winner pays the runner-up's bid plus one increment, capped at their own bid.
+    pub second_price: Option<bool>,
+    // Per unit, like bids. Paying it for the whole quantity while the auction runs wins outright.
+    pub buy_now_price: Option<Uint128>,
+    // The cw20 token sold in a batch auction, deposited with `DepositLot` through the token.
+    pub lot_token: Option<String>,
+    // Hides a second reserve as the sha256 of `"{reserve}:{salt}"`, see
+    // `validation::sealed_commitment`. The seller reveals it after close, and the auction fails if
+    // the best bid is below it.
+    pub reserve_hash: Option<Binary>,
+    // The sha256 of a seed the seller reveals with `RevealSeed` after the timeout, see
+    // `draw::seed_commitment`. Required for candle auctions and raffles, whose effective end or
+    // winner it draws.
+    pub seed_hash: Option<Binary>,
+    // cw20 amounts, NFTs and cw1155 amounts sold together with the native lot. The seller deposits
+    // each one with `DepositLot` through its contract, and bidding opens once all of them are held.
+    pub bundle: Option<Vec<BundleItemMsg>>,
+    // Posted with `PostDeposit` before a first bid, returned with `ReclaimDeposit` after the
+    // auction. A winner who forfeits or defaults loses it to the seller.
+    pub bid_bond: Option<Uint128>,
+    // Streams the proceeds to the seller from settlement instead of paying them at once. The seller
+    // claims what has vested with `ClaimVested`. Cannot be combined with a payout delay or
+    // proposal confirmation.
+    pub payout_vesting: Option<VestingSchedule>,
+    // Leases the lot to the winner instead of selling it. Only for English auctions without
+    // installments.
+    pub lease: Option<LeaseTerms>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleItemMsg {
+    Cw20 { address: String, amount: Uint128 },
+    Cw721 { address: String, token_id: String },
+    Cw1155 {
+        address: String,
+        token_id: String,
+        amount: Uint128,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
+// cw20 and cw1155 tokens both call `Receive`, so the two hooks are told apart by their fields.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(untagged)]
+pub enum TokenReceiveMsg {
+    Cw20(Cw20ReceiveMsg),
+    Cw1155(Cw1155ReceiveMsg),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    Bid { price: Uint128 },
-    Receive(Cw20ReceiveMsg),
+    Bid {
+        price: Uint128,
+        quantity: Option<Uint128>,
+    },
+    Receive(TokenReceiveMsg),
+    ReceiveNft(Cw721ReceiveMsg),
+    ClaimPayout {
+        proposal_id: Option<u64>,
+    },
+    Offer {
+        price: Uint128,
+    },
+    AcceptOffer {
+        buyer: String,
+    },
+    Counteroffer {
+        buyer: String,
+        price: Uint128,
+        duration_in_blocks: Uint64,
+    },
+    AcceptCounteroffer {},
+    SetManager {
+        manager: Option<String>,
+    },
+    ReclaimLot {},
+    // Calls off an auction nobody has bid on yet and returns the lot to the seller.
+    Cancel {},
+    ClaimDefault {},
+    Close {},
+    ForfeitWinner {},
+    ExtendDeadline {
+        additional: Duration,
+    },
+    // Leaving out `scopes` pauses or unpauses every scope.
+    Pause {
+        scopes: Option<Vec<PauseScope>>,
+    },
+    Unpause {
+        scopes: Option<Vec<PauseScope>>,
+    },
+    Reveal {
+        price: Uint128,
+        salt: String,
+    },
+    SlashUnrevealed {},
+    ClaimBatchFill {},
+    ClaimTicketRefund {},
+    ConvertToFixedPrice {
+        price: Uint128,
+    },
+    RevealReserve {
+        reserve: Uint128,
+        salt: String,
+    },
+    RevealSeed {
+        seed: String,
+    },
+    AcceptBid {},
+    Retract {},
+    WithdrawOffer {},
+    CreatePool {},
+    // Placed by the pool creator and backed by the pool's contributions.
+    PoolBid {
+        pool_id: u64,
+        price: Uint128,
+        quantity: Option<Uint128>,
+    },
+    SettlePool {
+        pool_id: u64,
+    },
+    WithdrawFromPool {
+        pool_id: u64,
+    },
+    Relist {
+        new_config: RelistConfig,
+    },
+    ReclaimDeposit {},
+    ClaimVested {},
+    // Returns a leased lot to the seller once the lease is over or a period payment was missed.
+    EndLease {},
+    // Sends the bid fees of a penny auction to the seller once bidding is over.
+    ClaimBidFees {},
+}
+
+// Terms of a fresh round after an unsold one. Everything else carries over, and the timeout is
+// set the same way as at instantiate.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RelistConfig {
+    pub reserve_price: Uint128,
+    pub increment: Uint128,
+    pub duration_in_blocks: Option<Uint64>,
+    pub duration: Option<Duration>,
+    pub expiration: Option<Expiration>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    ForceClose {},
+    ForceCancelAndRefund {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ReceiveMsg {
     Buy,
+    Bid {
+        price: Uint128,
+        quantity: Option<Uint128>,
+    },
+    // `commitment` is the sha256 of `"{price}:{salt}"`, see `validation::sealed_commitment`.
+    Commit {
+        commitment: Binary,
+    },
+    DepositLot {},
+    BuyTickets {
+        count: Uint64,
+    },
+    BuyFixedPrice {},
+    // Pays the bid fee of a penny auction and takes the lead one price step up.
+    PennyBid {},
+    // Escrows `max_price` for the quantity and bids just enough to stay on top, up to it.
+    ProxyBid {
+        max_price: Uint128,
+        quantity: Option<Uint128>,
+    },
+    // Raises the sender's escrowed best bid, sending only the difference.
+    TopUp {
+        new_price: Uint128,
+    },
+    // Escrows a direct offer for the whole lot, replacing any earlier one from the sender. The
+    // seller may accept it with `AcceptOffer` at any time before the sale, which ends bidding.
+    MakeOffer {
+        price: Uint128,
+    },
+    ContributeToPool {
+        pool_id: u64,
+    },
+    // Posts the bid bond. Overpayments are refunded like any other payment.
+    PostDeposit {},
+    // Pays the next period of a lease, from the lessee.
+    PayPeriod {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
+    GetApiVersion,
     GetConfig,
+    GetStatus,
+    // The paused scopes, empty when nothing is paused.
+    GetPaused,
+    GetDeadline,
     GetBidSeq,
-    GetBidRecord { id: Uint64 },
+    GetBidRecord {
+        id: Uint64,
+    },
     GetBestBid,
+    GetRound,
+    GetRoundBidRecord {
+        round: u64,
+        id: Uint64,
+    },
+    GetReservePrice,
+    GetCurrentPrice,
+    GetCandleEnd,
+    GetBatchClearing,
+    GetTickets {
+        buyer: String,
+    },
+    GetPayout,
+    // None for formats other than hybrid.
+    GetHybridPhase,
+    // None until a leased lot is settled.
+    GetLease,
+    // Bid fees of a penny auction not yet claimed.
+    GetFeePool,
+    // None until proceeds under a vesting schedule are settled.
+    GetVesting,
+    GetOffers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetDirectOffers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetCounteroffer,
+    GetBundle,
+    GetFixedPrice,
+    GetRevealedReserve,
+    GetInstallments,
+    GetInstallmentBalance,
+    GetPool {
+        pool_id: u64,
+    },
+    GetPoolContribution {
+        pool_id: u64,
+        contributor: String,
+    },
+    // Refunds the address can claim right now.
+    GetRefundOwed {
+        address: String,
+    },
+    GetRunnerUp,
+    GetCommitment {
+        bidder: String,
+    },
+    #[cfg(feature = "stats")]
+    GetBidderStats {
+        bidder: String,
+    },
+    GetBidderPosition {
+        bidder: String,
+    },
+    GetBidCountByBidder {
+        bidder: String,
+    },
+    // None when the number of bids per address is not limited.
+    GetRemainingBids {
+        bidder: String,
+    },
+    // None when the bidder has no bond posted.
+    GetBidBond {
+        bidder: String,
+    },
+    #[cfg(feature = "debug")]
+    GetInvariants,
+    #[cfg(feature = "history")]
+    GetSettlements {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BidResponse {
     pub buyer: String,
     pub price: Uint128,
+    pub quantity: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BidderPositionResponse {
+    pub rank: Option<u32>,
+    pub best_price: Option<Uint128>,
+    pub gap: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OffersResponse {
+    pub api_version: u32,
+    pub offers: Vec<BidResponse>,
+}
+
+// `active` while the lessee holds the lot: not ended, not past the last period and no payment
+// missed. No `next_due_at` once every period is paid.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LeaseResponse {
+    pub lessee: String,
+    pub paid_periods: u32,
+    pub next_due_at: Option<u64>,
+    pub ends_at: u64,
+    pub active: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingResponse {
+    pub total: Uint128,
+    pub vested: Uint128,
+    pub claimed: Uint128,
+    pub claimable: Uint128,
+}
+
+// Zero `next_amount` and no `next_due_at` once the plan is paid off or defaulted.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstallmentBalanceResponse {
+    pub total: Uint128,
+    pub paid: Uint128,
+    pub outstanding: Uint128,
+    pub remaining_payments: u32,
+    pub next_amount: Uint128,
+    pub next_due_at: Option<Uint64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PoolContributionResponse {
+    pub contribution: Uint128,
+    pub share_bps: u64,
+    pub refund: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PoolRefund {
+    pub pool_id: u64,
+    pub refund: Uint128,
+}
+
+// `amount` is the sum of the batch escrow, ticket, offer, bond and pool refunds, each claimed
+// separately.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RefundOwedResponse {
+    pub amount: Uint128,
+    pub batch: Uint128,
+    pub tickets: Uint128,
+    pub offer: Uint128,
+    pub bond: Uint128,
+    pub pools: Vec<PoolRefund>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BundleItemResponse {
+    pub item: BundleItem,
+    pub deposited: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BundleResponse {
+    pub api_version: u32,
+    pub items: Vec<BundleItemResponse>,
+}
+
+#[cfg(feature = "history")]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SettlementsResponse {
+    pub api_version: u32,
+    pub settlements: Vec<Settlement>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ApiVersionResponse {
+    pub api_version: u32,
 }
@@ -0,0 +1,47 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Empty, Uint128};
+use cw_storage_plus::{Item, Map};
+
+use crate::math::BPS_DENOMINATOR;
+
+// A pool bids through its creator with the tokens contributed to it, which stay in the contract
+// until the pool wins or its contributors withdraw.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Pool {
+    pub creator: Addr,
+    // Sum of the contributions, the basis of every contributor's share.
+    pub contributed: Uint128,
+    // Paid to the seller once the pool won, zero until then.
+    pub spent: Uint128,
+}
+
+pub const POOL_SEQ: Item<u64> = Item::new("plseq");
+pub const POOLS: Map<u64, Pool> = Map::new("pools");
+// Contributions by pool id and contributor, removed once withdrawn.
+pub const POOL_CONTRIBUTIONS: Map<(u64, &Addr), Uint128> = Map::new("plcn");
+// The same contributions by contributor, to find an address's pools without scanning them all.
+pub const CONTRIBUTOR_POOLS: Map<(&Addr, u64), Empty> = Map::new("plct");
+// The pool behind each bid a pool placed, by round and bid id, as bid ids start over when the
+// auction is relisted.
+pub const POOL_BIDS: Map<(u64, u64), u64> = Map::new("plrb");
+
+// What a contributor gets back: their contribution in full before the pool spends anything, and
+// their share of what is left after, rounded down.
+pub fn refund_for(pool: &Pool, contribution: Uint128) -> Uint128 {
+    if pool.spent.is_zero() {
+        return contribution;
+    }
+    contribution.multiply_ratio(pool.contributed - pool.spent, pool.contributed)
+}
+
+// A contributor's share of the pool, and of the lot if it won, in basis points rounded down.
+pub fn share_bps(pool: &Pool, contribution: Uint128) -> u64 {
+    if pool.contributed.is_zero() {
+        return 0;
+    }
+    contribution
+        .multiply_ratio(BPS_DENOMINATOR, pool.contributed)
+        .u128() as u64
+}
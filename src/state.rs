@@ -1,28 +1,251 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Uint128, Uint64};
+use cosmwasm_std::{Addr, Binary, Coin, Uint128, Uint64};
 use cw_storage_plus::{Item, Map};
+use cw_utils::{Duration, Expiration};
+
+use crate::vesting::VestingSchedule;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub seller: Addr,
     pub token_addr: Addr,
+    pub token_decimals: u8,
+    pub token_symbol: String,
     pub reserve_price: Uint128,
     pub increment: Uint128,
-    pub timeout: Uint64,
+    pub timeout: Expiration,
+    pub payout_delay: Option<Uint64>,
+    pub quantity: Uint128,
+    pub max_bid_multiplier: Option<Uint128>,
+    pub default_limit: u32,
+    pub max_limit: u32,
+    pub proposal_confirmation: bool,
+    pub manager: Option<Addr>,
+    pub start: Uint64,
+    pub reserve_decay: Option<ReserveDecay>,
+    pub allow_self_raise: bool,
+    pub lot: Vec<Coin>,
+    pub installment_plan: Option<InstallmentPlan>,
+    pub event_prefix: Option<String>,
+    pub escrow: bool,
+    pub allow_seller_bids: bool,
+    pub verify_funds: bool,
+    pub settlement_window: Option<Uint64>,
+    pub max_extension: Option<Duration>,
+    pub soft_close_window: Option<Duration>,
+    pub extension: Option<Duration>,
+    pub strict_increments: bool,
+    pub increment_tiers: Vec<IncrementTier>,
+    pub bid_cooldown: Option<Duration>,
+    pub max_bids_per_bidder: Option<u64>,
+    pub retraction_penalty_bps: Option<u64>,
+    pub require_exact_payment: bool,
+    pub admin: Option<Addr>,
+    pub auction_type: AuctionType,
+    pub second_price: bool,
+    pub buy_now_price: Option<Uint128>,
+    pub lot_token: Option<Addr>,
+    pub reserve_hash: Option<Binary>,
+    pub seed_hash: Option<Binary>,
+    pub bundle: Vec<BundleItem>,
+    pub bid_bond: Option<Uint128>,
+    pub payout_vesting: Option<VestingSchedule>,
+    pub lease: Option<LeaseTerms>,
+}
+
+// An asset the seller escrows alongside the native lot, released to the winner on settlement.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleItem {
+    Cw20 { address: Addr, amount: Uint128 },
+    Cw721 { address: Addr, token_id: String },
+    Cw1155 {
+        address: Addr,
+        token_id: String,
+        amount: Uint128,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuctionType {
+    English,
+    // The price falls from `start_price` towards `floor_price` and the first buyer to pay it wins.
+    Dutch {
+        start_price: Uint128,
+        floor_price: Uint128,
+        curve: PriceCurve,
+    },
+    // Bids are committed as hashes until the timeout and revealed within `reveal_period_in_blocks`
+    // after it. Each commitment is backed by at least `deposit`, slashed if it is not revealed.
+    // With `second_price` the winner pays just above the runner-up, as in a Vickrey auction.
+    Sealed {
+        reveal_period_in_blocks: Uint64,
+        deposit: Uint128,
+    },
+    // Bids as usual, but the effective end is drawn at random from the last `window_in_blocks`
+    // before the timeout, and the best bid at that block wins.
+    Candle {
+        window_in_blocks: Uint64,
+    },
+    // Sells `quantity` of the deposited `lot_token` to every escrowed bid above a single clearing
+    // price, computed at close.
+    Batch,
+    // Sells tickets at `ticket_price` and draws one winner at close. Losing tickets are refunded
+    // with `refund_losers`, or kept by the seller otherwise.
+    Raffle {
+        ticket_price: Uint128,
+        refund_losers: bool,
+    },
+    // Falls like a Dutch auction until the first bid at or above the current price, which opens
+    // an English auction ending `english_window_in_blocks` later.
+    Hybrid {
+        start_price: Uint128,
+        floor_price: Uint128,
+        curve: PriceCurve,
+        english_window_in_blocks: Uint64,
+    },
+    // Every bid costs `bid_fee`, kept for the seller whoever wins, and raises the price by
+    // `price_step` from the reserve. Each bid pushes the timeout to at least
+    // `extension_in_blocks` away, and the last bidder buys at the final price.
+    Penny {
+        bid_fee: Uint128,
+        price_step: Uint128,
+        extension_in_blocks: Uint64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HybridPhase {
+    Dutch,
+    English,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceCurve {
+    // Falls evenly every block and reaches the floor at the timeout.
+    Linear,
+    // Falls by `step` every `interval_in_blocks`.
+    Stepwise {
+        interval_in_blocks: Uint64,
+        step: Uint128,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReserveDecay {
+    pub interval_in_blocks: Uint64,
+    pub decay_bps: u64,
+    pub floor: Uint128,
+}
+
+// Raises over a best price below `below` need at least `increment`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IncrementTier {
+    pub below: Uint128,
+    pub increment: Uint128,
 }
 
-pub const CONFIG: Item<Config> = Item::new("config");
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstallmentPlan {
+    pub count: u32,
+    pub interval_in_blocks: Uint64,
+    pub forfeit_bps: u64,
+    // Share of the total paid first, before `count` installments split the rest. It is forfeited
+    // in full on default.
+    pub down_payment_bps: Option<u64>,
+}
+
+// The lot is leased for `periods` periods from settlement instead of sold. The winning bid pays
+// for the first period and `period_payment` is due by the start of each following one.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LeaseTerms {
+    pub period_in_blocks: Uint64,
+    pub period_payment: Uint128,
+    pub periods: u32,
+}
+
+pub const CONFIG: Item<Config> = Item::new("cfg");
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuctionStatus {
+    Created,
+    Active,
+    Revealing,
+    Ended,
+    Settled,
+    Cancelled,
+    Failed,
+}
+
+// Only transitions made by handlers are stored; `Active` and `Ended` follow from the start and
+// the timeout expiring until a handler closes the auction.
+pub const STATUS: Item<AuctionStatus> = Item::new("sts");
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseScope {
+    // New bids, commitments, offers, tickets, pool contributions, bonds and lot deposits.
+    Bidding,
+    // Payments, reveals, acceptances, closing and payouts that complete a sale.
+    Settlement,
+    // Refunds, withdrawals and the return of the lot to the seller.
+    Refunds,
+}
+
+pub const PAUSE_SCOPES: [PauseScope; 3] =
+    [PauseScope::Bidding, PauseScope::Settlement, PauseScope::Refunds];
+
+// Set by the admin to halt the execute handlers in each scope until unpaused. Pausing and
+// unpausing, managing the auction and extending its deadline are never paused.
+pub const PAUSED: Item<Vec<PauseScope>> = Item::new("psds");
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Commitment {
+    pub commitment: Binary,
+    pub deposit: Uint128,
+}
+
+// Sealed bids awaiting reveal, removed once revealed, slashed or refunded on cancel.
+pub const COMMITMENTS: Map<&Addr, Commitment> = Map::new("cmt");
+// The deposit of the leading revealed sealed bid, held until the winner pays or defaults.
+pub const LEADING_DEPOSIT: Item<Uint128> = Item::new("ldep");
+// In a candle auction, the id of the best bid at the end of every block that had a bid.
+pub const CANDLE_SNAPSHOTS: Map<u64, u64> = Map::new("cnds");
+// The effective end height, drawn by the first message after the timeout.
+pub const CANDLE_END: Item<u64> = Item::new("cend");
+// Raffle tickets are numbered from zero in order of purchase. Each purchase is keyed by its first
+// ticket number, so the owner of a ticket is the last purchase starting at or before it.
+pub const TICKET_SEQ: Item<u64> = Item::new("tseq");
+pub const TICKET_PURCHASES: Map<u64, Addr> = Map::new("tpur");
+// Tickets held by each buyer, removed once refunded.
+pub const TICKETS: Map<&Addr, u64> = Map::new("tkts");
+// Amount of the cw20 lot deposited by the seller.
+pub const LOT_DEPOSITED: Item<Uint128> = Item::new("lotd");
+// Indexes of the bundle items held by the contract, removed once released.
+pub const BUNDLE_DEPOSITED: Map<u32, bool> = Map::new("bndl");
+// Bonds posted by bidders, removed once reclaimed or slashed.
+pub const BID_BONDS: Map<&Addr, Uint128> = Map::new("bbnd");
+// Total the seller has extended the deadline by, in the unit of the timeout.
+pub const EXTENDED: Item<u64> = Item::new("ext");
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BidRecord {
     pub buyer: Addr,
     pub price: Uint128,
+    pub quantity: Uint128,
 }
 
-pub const BID_SEQ: Item<u64> = Item::new("bid_seq");
-pub const BID_RECORDS: Map<u64, BidRecord> = Map::new("bid_records");
+pub const BID_SEQ: Item<u64> = Item::new("bseq");
+pub const BID_RECORDS: Map<u64, BidRecord> = Map::new("bids");
+pub const BID_COUNTS: Map<&Addr, u64> = Map::new("bcnt");
+// Rounds are counted from zero and a relist starts the next one. Bids of earlier rounds are kept
+// by round and bid id.
+pub const ROUND: Item<u64> = Item::new("rnd");
+pub const ROUND_BIDS: Map<(u64, u64), BidRecord> = Map::new("rbds");
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BestBid {
@@ -31,4 +254,75 @@ pub struct BestBid {
     pub sold: bool,
 }
 
-pub const BEST_BID: Item<BestBid> = Item::new("best_bid");
+pub const BEST_BID: Item<BestBid> = Item::new("best");
+// The previous best bid from a different bidder, who takes over if the winner forfeits.
+pub const RUNNER_UP: Item<BestBid> = Item::new("rup");
+// Forfeited bid ids and their bidders.
+pub const FORFEITED: Map<u64, Addr> = Map::new("fft");
+// Retracted bid ids and their bidders. Bidding starts over from the reserve after each one.
+pub const RETRACTED: Map<u64, Addr> = Map::new("rtr");
+// Set when settlement passes to the runner-up, who gets a fresh window.
+pub const SETTLEMENT_DEADLINE: Item<Uint64> = Item::new("sdl");
+// When each bidder may bid again after their last bid.
+pub const BID_COOLDOWNS: Map<&Addr, Expiration> = Map::new("bcd");
+// Tokens held for the best bid in escrow mode.
+pub const ESCROWED: Item<Uint128> = Item::new("esc");
+// Hidden per-unit ceiling of a best bid placed by proxy. The whole ceiling is escrowed and the
+// contract counterbids for the bidder until it is reached.
+pub const PROXY_MAX: Item<Uint128> = Item::new("pmax");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Payout {
+    pub amount: Uint128,
+    pub claimable_at: Uint64,
+    pub claimed: bool,
+}
+
+pub const PAYOUT: Item<Payout> = Item::new("pay");
+
+pub const OFFERS: Map<&Addr, Uint128> = Map::new("ofr");
+// Per-unit price of each direct offer, escrowed in full for the lot. Removed once accepted or
+// withdrawn.
+pub const DIRECT_OFFERS: Map<&Addr, Uint128> = Map::new("dofr");
+// Id of the bid record an accepted direct offer was saved as. Bidding ended with it, so earlier
+// bids no longer compete with it.
+pub const ACCEPTED_OFFER: Item<u64> = Item::new("aofr");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Counteroffer {
+    pub buyer: Addr,
+    pub price: Uint128,
+    pub expires: Uint64,
+}
+
+pub const COUNTEROFFER: Item<Counteroffer> = Item::new("cofr");
+
+// Secret reserve once the seller has revealed it.
+pub const SECRET_RESERVE: Item<Uint128> = Item::new("srsv");
+
+// Per-unit price of the fixed-price sale an unsold auction was converted to.
+pub const FIXED_PRICE: Item<Uint128> = Item::new("fxp");
+// Bid fees of a penny auction held for the seller, reset once claimed.
+pub const FEE_POOL: Item<Uint128> = Item::new("fpool");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Installments {
+    pub paid: Uint128,
+    pub paid_count: u32,
+    pub defaulted: bool,
+}
+
+pub const INSTALLMENTS: Item<Installments> = Item::new("inst");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Lease {
+    pub lessee: Addr,
+    // Height of the settlement the periods count from.
+    pub start: Uint64,
+    // Periods paid for, the first one included.
+    pub paid_periods: u32,
+    // Set once the lot went back to the seller.
+    pub ended: bool,
+}
+
+pub const LEASE: Item<Lease> = Item::new("lse");
@@ -0,0 +1,46 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Deps, StdResult, Storage, Uint128};
+use cw_storage_plus::Map;
+
+use crate::error::ContractError;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct BidderStats {
+    pub bids_placed: u64,
+    pub auctions_won: u64,
+    pub total_spent: Uint128,
+}
+
+pub const BIDDER_STATS: Map<&Addr, BidderStats> = Map::new("bst");
+
+pub fn record_bid(storage: &mut dyn Storage, bidder: &Addr) -> StdResult<()> {
+    BIDDER_STATS.update(storage, bidder, |stats| -> StdResult<_> {
+        let mut stats = stats.unwrap_or_default();
+        stats.bids_placed += 1;
+        Ok(stats)
+    })?;
+    Ok(())
+}
+
+pub fn record_win(
+    storage: &mut dyn Storage,
+    buyer: &Addr,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    BIDDER_STATS.update(storage, buyer, |stats| -> Result<_, ContractError> {
+        let mut stats = stats.unwrap_or_default();
+        stats.auctions_won += 1;
+        stats.total_spent = stats.total_spent.checked_add(amount)?;
+        Ok(stats)
+    })?;
+    Ok(())
+}
+
+pub fn query_bidder_stats(deps: Deps, bidder: String) -> StdResult<BidderStats> {
+    let bidder = deps.api.addr_validate(&bidder)?;
+    Ok(BIDDER_STATS
+        .may_load(deps.storage, &bidder)?
+        .unwrap_or_default())
+}
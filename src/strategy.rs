@@ -0,0 +1,167 @@
+use cosmwasm_std::Uint128;
+use cw_utils::Expiration;
+
+use crate::error::ContractError;
+use crate::state::{AuctionType, Config, PriceCurve};
+
+// What each auction format allows, for the rules several handlers share. Handlers that only
+// apply to some formats still match on `AuctionType` themselves.
+
+// Checks the parameters carried by the auction type, and that the rest of the config only uses
+// features the format supports.
+pub fn validate_auction_type(config: &Config) -> Result<(), ContractError> {
+    match &config.auction_type {
+        AuctionType::English | AuctionType::Batch => {}
+        AuctionType::Dutch {
+            start_price,
+            floor_price,
+            curve,
+        } => validate_price_curve(*start_price, *floor_price, curve)?,
+        AuctionType::Hybrid {
+            start_price,
+            floor_price,
+            curve,
+            english_window_in_blocks,
+        } => {
+            validate_price_curve(*start_price, *floor_price, curve)?;
+            // The English phase is counted in blocks, so the Dutch one is as well.
+            if !matches!(config.timeout, Expiration::AtHeight(_)) {
+                return Err(ContractError::HeightExpirationRequired {});
+            }
+            if english_window_in_blocks.is_zero() {
+                return Err(ContractError::ZeroEnglishWindow {});
+            }
+        }
+        AuctionType::Sealed {
+            reveal_period_in_blocks,
+            ..
+        } => {
+            if reveal_period_in_blocks.is_zero() {
+                return Err(ContractError::ZeroRevealPeriod {});
+            }
+        }
+        AuctionType::Candle { window_in_blocks } => {
+            let duration = match config.timeout {
+                Expiration::AtHeight(height) => height - config.start.u64(),
+                _ => return Err(ContractError::HeightExpirationRequired {}),
+            };
+            if window_in_blocks.is_zero() || window_in_blocks.u64() > duration {
+                return Err(ContractError::InvalidCandleWindow {});
+            }
+        }
+        AuctionType::Penny {
+            bid_fee,
+            price_step,
+            extension_in_blocks,
+        } => {
+            if !matches!(config.timeout, Expiration::AtHeight(_)) {
+                return Err(ContractError::HeightExpirationRequired {});
+            }
+            if bid_fee.is_zero() || price_step.is_zero() || extension_in_blocks.is_zero() {
+                return Err(ContractError::InvalidPennyAuction {});
+            }
+        }
+        AuctionType::Raffle { ticket_price, .. } => {
+            if ticket_price.is_zero() {
+                return Err(ContractError::ZeroTicketPrice {});
+            }
+        }
+    }
+    match (is_drawn(&config.auction_type), &config.seed_hash) {
+        (true, None) => return Err(ContractError::SeedHashRequired {}),
+        (false, Some(_)) => return Err(ContractError::UnsupportedByAuctionType {}),
+        _ => {}
+    }
+    // These are built around open bids and deadlines counted from the timeout, which other
+    // formats either settle before or follow with a reveal phase. A sealed auction counts its
+    // settlement window from the end of the reveal phase instead, so a winner who does not pay
+    // can be made to forfeit their deposit.
+    let settlement_window_unsupported = config.settlement_window.is_some()
+        && !matches!(config.auction_type, AuctionType::Sealed { .. });
+    if config.auction_type != AuctionType::English
+        && ((config.escrow && config.auction_type != AuctionType::Batch)
+            || config.installment_plan.is_some()
+            || settlement_window_unsupported
+            || config.lease.is_some())
+    {
+        return Err(ContractError::UnsupportedByAuctionType {});
+    }
+    if config.second_price && !has_second_price(&config.auction_type) {
+        return Err(ContractError::UnsupportedByAuctionType {});
+    }
+    // Every winning bidder in a batch auction pays up front and the proceeds go to the seller at
+    // close, so there is no single buyer, native lot or held payout. Short demand clears at the
+    // reserve, so it cannot decay below what earlier bids were accepted at.
+    if config.auction_type == AuctionType::Batch {
+        let lot_token_valid = match &config.lot_token {
+            Some(lot_token) => *lot_token != config.token_addr,
+            None => false,
+        };
+        if !config.escrow || !lot_token_valid || !config.lot.is_empty() || !config.bundle.is_empty()
+        {
+            return Err(ContractError::InvalidBatchAuction {});
+        }
+        if config.payout_delay.is_some()
+            || config.proposal_confirmation
+            || config.reserve_decay.is_some()
+        {
+            return Err(ContractError::UnsupportedByAuctionType {});
+        }
+    } else if config.lot_token.is_some() {
+        return Err(ContractError::UnsupportedByAuctionType {});
+    }
+    Ok(())
+}
+
+fn validate_price_curve(
+    start_price: Uint128,
+    floor_price: Uint128,
+    curve: &PriceCurve,
+) -> Result<(), ContractError> {
+    let invalid_step = match curve {
+        PriceCurve::Linear => false,
+        PriceCurve::Stepwise {
+            interval_in_blocks,
+            step,
+        } => interval_in_blocks.is_zero() || step.is_zero(),
+    };
+    if start_price <= floor_price || invalid_step {
+        return Err(ContractError::InvalidPriceCurve {});
+    }
+    Ok(())
+}
+
+// Formats where bidders outbid each other in the open with `Bid`.
+pub fn takes_open_bids(auction_type: &AuctionType) -> bool {
+    matches!(
+        auction_type,
+        AuctionType::English | AuctionType::Candle { .. } | AuctionType::Hybrid { .. }
+    )
+}
+
+// A Dutch auction has a single buyer at the current price, and the runner-up of a candle auction
+// is not known until its end is drawn, so neither has a second price. A sealed auction with a
+// second price is a Vickrey auction.
+pub fn has_second_price(auction_type: &AuctionType) -> bool {
+    matches!(
+        auction_type,
+        AuctionType::English | AuctionType::Sealed { .. }
+    )
+}
+
+// Formats whose end or winner is drawn from the seller's seed, see `draw`.
+pub fn is_drawn(auction_type: &AuctionType) -> bool {
+    matches!(
+        auction_type,
+        AuctionType::Candle { .. } | AuctionType::Raffle { .. }
+    )
+}
+
+// Batch auctions fill many bids and raffles draw among tickets, so neither sells the whole lot
+// to one buyer at a price the seller can set.
+pub fn has_single_buyer(auction_type: &AuctionType) -> bool {
+    !matches!(
+        auction_type,
+        AuctionType::Batch | AuctionType::Raffle { .. }
+    )
+}
@@ -0,0 +1,229 @@
+use cosmwasm_std::{Addr, Binary, BlockInfo, Uint128};
+use cw_utils::Expiration;
+use sha2::{Digest, Sha256};
+
+use crate::error::ContractError;
+use crate::math::{bps_of, BPS_DENOMINATOR};
+use crate::state::{AuctionType, BidRecord, Config, PriceCurve};
+use crate::strategy::takes_open_bids;
+
+// Pure bid checks shared by the contract and off-chain callers (build with `library`).
+
+// The reserve steps down by `decay_bps` of the initial reserve every interval, never below the floor.
+pub fn reserve_price_at(config: &Config, block_height: u64) -> Uint128 {
+    let reserve_decay = match &config.reserve_decay {
+        Some(reserve_decay) => reserve_decay,
+        None => return config.reserve_price,
+    };
+    let elapsed = block_height.saturating_sub(config.start.u64());
+    let steps = elapsed / reserve_decay.interval_in_blocks.u64();
+    let decay_bps = steps
+        .saturating_mul(reserve_decay.decay_bps)
+        .min(BPS_DENOMINATOR);
+    let decayed = bps_of(config.reserve_price, BPS_DENOMINATOR - decay_bps);
+    decayed.max(reserve_decay.floor)
+}
+
+// The minimum raise over `price`, from the first tier it falls below or the flat increment.
+pub fn increment_at(config: &Config, price: Uint128) -> Uint128 {
+    config
+        .increment_tiers
+        .iter()
+        .find(|tier| price < tier.below)
+        .map_or(config.increment, |tier| tier.increment)
+}
+
+// The price a Dutch auction sells at, or the descending price of a hybrid auction before its
+// first bid. None for other auction types.
+pub fn dutch_price_at(config: &Config, block_height: u64) -> Option<Uint128> {
+    let (start_price, floor_price, curve) = match &config.auction_type {
+        AuctionType::Dutch {
+            start_price,
+            floor_price,
+            curve,
+        }
+        | AuctionType::Hybrid {
+            start_price,
+            floor_price,
+            curve,
+            ..
+        } => (*start_price, *floor_price, curve),
+        _ => return None,
+    };
+    let elapsed = block_height.saturating_sub(config.start.u64());
+    let drop = match curve {
+        PriceCurve::Linear => {
+            // Dutch auctions are only instantiated with a height timeout.
+            let length = match config.timeout {
+                Expiration::AtHeight(height) => height.saturating_sub(config.start.u64()).max(1),
+                _ => return Some(floor_price),
+            };
+            (start_price - floor_price).multiply_ratio(elapsed.min(length), length)
+        }
+        PriceCurve::Stepwise {
+            interval_in_blocks,
+            step,
+        } => step.saturating_mul(Uint128::from(elapsed / interval_in_blocks.u64())),
+    };
+    Some(start_price.saturating_sub(drop).max(floor_price))
+}
+
+// The per-unit price the winner pays. In a second-price auction this is the runner-up's bid plus
+// one increment, or the reserve without a runner-up, but never more than the winning bid.
+pub fn clearing_price(
+    config: &Config,
+    best_bid: &BidRecord,
+    runner_up: Option<&BidRecord>,
+) -> Uint128 {
+    if !config.second_price {
+        return best_bid.price;
+    }
+    let second_price = match runner_up {
+        Some(runner_up) => runner_up
+            .price
+            .saturating_add(increment_at(config, runner_up.price)),
+        None => config.reserve_price,
+    };
+    second_price.min(best_bid.price)
+}
+
+// Sealed bids commit to the sha256 of the price and a salt, so the price stays hidden until the
+// bidder reveals both.
+pub fn sealed_commitment(price: Uint128, salt: &str) -> Binary {
+    let hash = Sha256::digest(format!("{}:{}", price, salt).as_bytes());
+    Binary::from(hash.as_slice())
+}
+
+pub fn validate_timing(config: &Config, block: &BlockInfo) -> Result<(), ContractError> {
+    if config.timeout.is_expired(block) {
+        return Err(ContractError::AuctionClosed {});
+    }
+    Ok(())
+}
+
+pub fn validate_quantity(config: &Config, quantity: Uint128) -> Result<(), ContractError> {
+    if quantity.is_zero() || quantity > config.quantity {
+        return Err(ContractError::InvalidBidQuantity {
+            quantity,
+            available: config.quantity,
+        });
+    }
+    Ok(())
+}
+
+pub fn validate_reserve(
+    config: &Config,
+    block_height: u64,
+    price: Uint128,
+) -> Result<Uint128, ContractError> {
+    let reserve_price = reserve_price_at(config, block_height);
+    if price < reserve_price {
+        return Err(ContractError::BidBelowReserve {
+            bid: price,
+            reserve: reserve_price,
+        });
+    }
+    Ok(reserve_price)
+}
+
+pub fn validate_increment(
+    config: &Config,
+    best_price: Uint128,
+    price: Uint128,
+) -> Result<(), ContractError> {
+    // Guard against fat-finger bids far above the current best (or the reserve before any bid).
+    if let Some(max_bid_multiplier) = config.max_bid_multiplier {
+        let max_price = best_price
+            .checked_mul(max_bid_multiplier)
+            .unwrap_or(Uint128::MAX);
+        if price > max_price {
+            return Err(ContractError::BidOutsidePriceBand { price, max_price });
+        }
+    }
+    let increment = price.checked_sub(best_price)?;
+    let min_increment = increment_at(config, best_price);
+    if increment < min_increment {
+        return Err(ContractError::IncrementTooLow {
+            increment,
+            min_increment,
+        });
+    }
+    Ok(())
+}
+
+// With strict increments only `reserve + k * increment` is a valid price.
+pub fn validate_step(
+    config: &Config,
+    reserve_price: Uint128,
+    price: Uint128,
+) -> Result<(), ContractError> {
+    if !config.strict_increments {
+        return Ok(());
+    }
+    // Increment is non-zero and the price is at or above the reserve, so neither can panic.
+    let remainder = (price - reserve_price) % config.increment;
+    if !remainder.is_zero() {
+        let lower = price - remainder;
+        return Err(ContractError::BidNotOnIncrement {
+            bid: price,
+            lower,
+            higher: lower.checked_add(config.increment)?,
+        });
+    }
+    Ok(())
+}
+
+// Runs every check `Bid` performs against the current best bid and returns the total price.
+pub fn validate_bid(
+    config: &Config,
+    block: &BlockInfo,
+    best_bid: Option<&BidRecord>,
+    bidder: &Addr,
+    price: Uint128,
+    quantity: Uint128,
+) -> Result<Uint128, ContractError> {
+    if !takes_open_bids(&config.auction_type) {
+        return Err(ContractError::NotEnglishAuction {});
+    }
+    validate_timing(config, block)?;
+    if !config.allow_seller_bids && *bidder == config.seller {
+        return Err(ContractError::SellerCannotBid {});
+    }
+    validate_quantity(config, quantity)?;
+    // Price is per unit, so the buyer is exposed to the total over the whole quantity.
+    let total = price.checked_mul(quantity)?;
+    // The first bid of a hybrid auction only has to meet the descending price, and opens the
+    // English phase.
+    if best_bid.is_none() {
+        if let Some(dutch_price) = dutch_price_at(config, block.height) {
+            if price < dutch_price {
+                return Err(ContractError::BidBelowReserve {
+                    bid: price,
+                    reserve: dutch_price,
+                });
+            }
+            return Ok(total);
+        }
+    }
+    let reserve_price = validate_reserve(config, block.height, price)?;
+    validate_step(config, reserve_price, price)?;
+
+    let best_price = match best_bid {
+        None => reserve_price,
+        Some(best_bid) => {
+            if !config.allow_self_raise && *bidder == best_bid.buyer {
+                return Err(ContractError::AlreadyBestBidder {});
+            }
+            if price <= best_bid.price {
+                return Err(ContractError::BidNotAboveBest {
+                    bid: price,
+                    best: best_bid.price,
+                });
+            }
+            best_bid.price
+        }
+    };
+    validate_increment(config, best_price, price)?;
+
+    Ok(total)
+}
@@ -0,0 +1,40 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Uint128, Uint64};
+use cw_storage_plus::Item;
+
+// Streams the proceeds to the seller linearly over `duration_in_blocks` from settlement, none of
+// them before `cliff_in_blocks` have passed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingSchedule {
+    pub cliff_in_blocks: Uint64,
+    pub duration_in_blocks: Uint64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Vesting {
+    pub total: Uint128,
+    // Height of the settlement the schedule counts from.
+    pub start: Uint64,
+    pub claimed: Uint128,
+}
+
+pub const VESTING: Item<Vesting> = Item::new("vest");
+
+pub fn validate_schedule(schedule: &VestingSchedule) -> bool {
+    !schedule.duration_in_blocks.is_zero()
+        && schedule.cliff_in_blocks <= schedule.duration_in_blocks
+}
+
+// Everything vested by `height`, claimed or not. Rounds down so the last block releases the rest.
+pub fn vested_amount(schedule: &VestingSchedule, vesting: &Vesting, height: u64) -> Uint128 {
+    let elapsed = height.saturating_sub(vesting.start.u64());
+    if elapsed < schedule.cliff_in_blocks.u64() {
+        return Uint128::zero();
+    }
+    let duration = schedule.duration_in_blocks.u64();
+    vesting
+        .total
+        .multiply_ratio(elapsed.min(duration), duration)
+}